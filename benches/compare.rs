@@ -0,0 +1,67 @@
+//! Compares the throughput and latency of the `Rcu` with `arc-swap` and locks
+//!
+//! Run with `cargo bench --features bench`.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, RwLock},
+};
+
+use arc_swap::ArcSwap;
+use axka_rcu::{
+    bench::{self, Contender},
+    Rcu,
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+/// The numbers of threads reading in the background
+const READERS: [usize; 4] = [0, 1, 3, 7];
+
+type Config = HashMap<String, u64>;
+
+fn config() -> Config {
+    (0..16).map(|i| (format!("key{i}"), i)).collect()
+}
+
+fn compare<C: Contender<Config>>(c: &mut Criterion, new: impl Fn(Config) -> C) {
+    let value = config();
+    for readers in READERS {
+        let contender = new(config());
+        let id = BenchmarkId::new(C::NAME, readers);
+
+        let mut group = c.benchmark_group("read");
+        group.throughput(Throughput::Elements(1));
+        group.bench_with_input(id.clone(), &readers, |b, &readers| {
+            b.iter_custom(|iters| bench::measure_reads(&contender, readers, iters));
+        });
+        group.finish();
+
+        let mut group = c.benchmark_group("write");
+        group.throughput(Throughput::Elements(1));
+        group.bench_with_input(id.clone(), &readers, |b, &readers| {
+            b.iter_custom(|iters| bench::measure_writes(&contender, &value, readers, iters));
+        });
+        group.finish();
+
+        let mut group = c.benchmark_group("update");
+        group.throughput(Throughput::Elements(1));
+        group.bench_with_input(id, &readers, |b, &readers| {
+            b.iter_custom(|iters| {
+                bench::measure_updates(&contender, readers, iters, |config| {
+                    *config.get_mut("key0").unwrap() += 1;
+                })
+            });
+        });
+        group.finish();
+    }
+}
+
+fn benches(c: &mut Criterion) {
+    compare(c, |config| Rcu::new(Arc::new(config)));
+    compare(c, ArcSwap::from_pointee);
+    compare(c, |config| RwLock::new(Arc::new(config)));
+    compare(c, Mutex::new);
+}
+
+criterion_group!(compare_benches, benches);
+criterion_main!(compare_benches);
@@ -0,0 +1,217 @@
+//! Switching between copy-on-write and locked in-place updates
+//!
+//! The value always lives in an `Rcu`. While locked, readers also hold a read lock, so a writer
+//! holding the write lock can modify the current version in place, as long as no `Arc` of it was
+//! handed out.
+
+use core::{
+    fmt,
+    mem::ManuallyDrop,
+    ops::Deref,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use std::time::{Duration, Instant};
+
+use crate::{
+    lock::{Mutex, RwLock, RwLockReadGuard},
+    Arc, Rcu, ReadGuard,
+};
+
+/// Lock once cloning takes more than this fraction of the time between writes
+const LOCK_ABOVE: f64 = 1.0 / 4.0;
+/// Stop locking once cloning takes less than this fraction of the time between writes
+const UNLOCK_BELOW: f64 = 1.0 / 16.0;
+
+/// A value which picks between [`Rcu`]-style copy-on-write and lock-protected in-place updates on
+/// its own
+///
+/// Copy-on-write keeps reads wait-free, but every update clones the value. When cloning takes a
+/// large part of the time between updates, e.g. because the value is large and updated often,
+/// `Adaptive` switches to modifying the value in place while holding a lock, which readers then
+/// have to wait for. When updates become rare again, it switches back.
+///
+/// The decision is based on how long cloning takes compared to the time between updates, averaged
+/// over recent updates.
+///
+/// # Example
+///
+/// ```
+/// use axka_rcu::Adaptive;
+/// let counts = Adaptive::new(vec![0u32; 1024]);
+///
+/// for _ in 0..100 {
+///     counts.update(|counts| counts[0] += 1);
+/// }
+/// assert_eq!(counts.read()[0], 100);
+/// ```
+pub struct Adaptive<T> {
+    rcu: Rcu<T>,
+    /// Set while updates modify the value in place
+    locked: AtomicBool,
+    /// Held for reading by readers and for writing by writers while locked
+    lock: RwLock<()>,
+    /// Also serializes writers
+    monitor: Mutex<Monitor>,
+}
+
+/// Moving averages of the cost of cloning and the time between writes
+struct Monitor {
+    last_write: Option<Instant>,
+    clone_secs: f64,
+    interval_secs: f64,
+}
+
+impl Monitor {
+    fn record_write(&mut self, now: Instant) {
+        if let Some(last_write) = self.last_write {
+            Self::record(&mut self.interval_secs, now - last_write);
+        }
+        self.last_write = Some(now);
+    }
+
+    fn record_clone(&mut self, took: Duration) {
+        Self::record(&mut self.clone_secs, took);
+    }
+
+    fn record(average: &mut f64, sample: Duration) {
+        *average += (sample.as_secs_f64() - *average) / 8.0;
+    }
+
+    fn should_lock(&self, locked: bool) -> bool {
+        // Nothing to compare against before the second write
+        if self.interval_secs <= 0.0 {
+            return locked;
+        }
+        let share = self.clone_secs / self.interval_secs;
+        if locked {
+            share >= UNLOCK_BELOW
+        } else {
+            share > LOCK_ABOVE
+        }
+    }
+}
+
+impl<T> Adaptive<T> {
+    /// Creates a new `Adaptive` containing `value`, starting with copy-on-write updates.
+    pub fn new(value: T) -> Self {
+        Self {
+            rcu: Rcu::new(Arc::new(value)),
+            locked: AtomicBool::new(false),
+            lock: RwLock::new(()),
+            monitor: Mutex::new(Monitor {
+                last_write: None,
+                clone_secs: 0.0,
+                interval_secs: 0.0,
+            }),
+        }
+    }
+
+    /// Returns a guard to the value.
+    ///
+    /// This only blocks while an in-place update is running.
+    pub fn read(&self) -> AdaptiveGuard<'_, T> {
+        if !self.locked.load(Ordering::SeqCst) {
+            let guard = self.rcu.read_guard();
+            // Checked after pinning, so a writer which started locking afterwards waits for this
+            // guard
+            if !self.locked.load(Ordering::SeqCst) {
+                return AdaptiveGuard { guard, _lock: None };
+            }
+        }
+
+        let lock = self.lock.read();
+        AdaptiveGuard {
+            guard: self.rcu.read_guard(),
+            _lock: Some(lock),
+        }
+    }
+
+    /// Returns `true` if updates currently modify the value in place.
+    pub fn is_locked(&self) -> bool {
+        self.locked.load(Ordering::Relaxed)
+    }
+}
+
+impl<T: Clone> Adaptive<T> {
+    /// Runs `updater` on the value, either on a clone which replaces the value or in place.
+    ///
+    /// Unlike [`Rcu::update`], updates are serialized, so no updates are lost.
+    ///
+    /// If `updater` panics during an in-place update, readers may see its partial modifications.
+    pub fn update<F, R>(&self, updater: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let mut monitor = self.monitor.lock();
+        monitor.record_write(Instant::now());
+
+        let locked = self.locked.load(Ordering::SeqCst);
+        let ret = if locked {
+            let _lock = self.lock.write();
+            let ptr = self.rcu.ptr.load(Ordering::SeqCst);
+            // SAFETY: The ptr was created by Arc::into_raw, and the `Rcu` owns the reference
+            let mut current = ManuallyDrop::new(unsafe { Arc::from_raw(ptr) });
+            // The write lock excludes readers which use guards, and the `Arc` is unique if no
+            // reader holds it
+            match Arc::get_mut(&mut current) {
+                Some(value) => updater(value),
+                None => self.update_clone(&mut monitor, updater),
+            }
+        } else {
+            self.update_clone(&mut monitor, updater)
+        };
+
+        let should_lock = monitor.should_lock(locked);
+        if should_lock && !locked {
+            self.locked.store(true, Ordering::SeqCst);
+            // Wait for the guards of readers which didn't see the lock
+            self.rcu.synchronize();
+        } else if !should_lock && locked {
+            self.locked.store(false, Ordering::SeqCst);
+        }
+        ret
+    }
+
+    fn update_clone<F, R>(&self, monitor: &mut Monitor, updater: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let start = Instant::now();
+        let mut value = T::clone(&self.rcu.read_guard());
+        monitor.record_clone(start.elapsed());
+
+        let ret = updater(&mut value);
+        self.rcu.write(Arc::new(value));
+        ret
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Adaptive<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("Adaptive");
+        d.field("data", &*self.read());
+        d.field("locked", &self.is_locked());
+        d.finish_non_exhaustive()
+    }
+}
+
+/// A guard to the value of an [`Adaptive`], returned by [`Adaptive::read`]
+pub struct AdaptiveGuard<'a, T> {
+    guard: ReadGuard<'a, T>,
+    /// Dropped after the guard
+    _lock: Option<RwLockReadGuard<'a, ()>>,
+}
+
+impl<T> Deref for AdaptiveGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for AdaptiveGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
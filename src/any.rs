@@ -0,0 +1,46 @@
+//! Helpers for type-erased versions
+
+use std::{any::Any, sync::Arc};
+
+use crate::{Rcu, Reclaim};
+
+/// Helpers for storing heterogeneous values
+///
+/// `Rcu` can't hold unsized values directly, so the type-erased value is stored behind another
+/// [`Arc`].
+impl<S: Reclaim<Arc<dyn Any + Send + Sync>>> Rcu<Arc<dyn Any + Send + Sync>, S> {
+    /// Returns the current version if it is of type `U`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::{any::Any, sync::Arc};
+    /// use axka_rcu::Rcu;
+    /// let rcu: Rcu<Arc<dyn Any + Send + Sync>> = Rcu::new(Arc::new(Arc::new(42u32)));
+    ///
+    /// assert_eq!(rcu.read_downcast::<u32>().as_deref(), Some(&42));
+    /// assert!(rcu.read_downcast::<String>().is_none());
+    /// ```
+    pub fn read_downcast<U>(&self) -> Option<Arc<U>>
+    where
+        U: Any + Send + Sync,
+    {
+        Arc::clone(&*self.read()).downcast().ok()
+    }
+
+    /// Writes a new version from a boxed value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::{any::Any, sync::Arc};
+    /// use axka_rcu::Rcu;
+    /// let rcu: Rcu<Arc<dyn Any + Send + Sync>> = Rcu::new(Arc::new(Arc::new(42u32)));
+    ///
+    /// rcu.write_boxed(Box::new("foo"));
+    /// assert_eq!(rcu.read_downcast::<&str>().as_deref(), Some(&"foo"));
+    /// ```
+    pub fn write_boxed(&self, new_value: Box<dyn Any + Send + Sync>) {
+        self.write(Arc::new(Arc::from(new_value)))
+    }
+}
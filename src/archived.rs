@@ -0,0 +1,146 @@
+//! Zero-copy versions stored as rkyv archives
+
+use alloc::{boxed::Box, vec::Vec};
+use core::{fmt, marker::PhantomData, ops::Deref};
+
+use rkyv::{api::high::HighValidator, bytecheck::CheckBytes, rancor, util::AlignedVec, Archive};
+
+use crate::{Arc, Rcu, Reclaim};
+
+/// A byte buffer which can back [`ArchivedBytes`]
+///
+/// # Safety
+///
+/// The bytes returned by `deref` must be the same on every call, and must not change or move
+/// while the buffer exists, even if the buffer itself is moved.
+///
+/// Memory-mapped files can only implement this if the mapped file isn't modified while mapped.
+pub unsafe trait ArchiveBuffer: Deref<Target = [u8]> {}
+
+// SAFETY: These own their bytes on the heap and only hand out shared references
+unsafe impl<const A: usize> ArchiveBuffer for AlignedVec<A> {}
+unsafe impl ArchiveBuffer for Vec<u8> {}
+unsafe impl ArchiveBuffer for Box<[u8]> {}
+unsafe impl ArchiveBuffer for alloc::sync::Arc<[u8]> {}
+// SAFETY: The bytes are borrowed immutably forever
+unsafe impl ArchiveBuffer for &'static [u8] {}
+
+/// A validated rkyv archive of `T`, which dereferences to the archived `T` without deserializing
+///
+/// Use it as the version of an `Rcu<ArchivedBytes<T>>`, and publish new archives with
+/// [`Rcu::write_bytes`].
+///
+/// # Example
+///
+/// ```
+#[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+#[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+/// use axka_rcu::{ArchivedBytes, Rcu};
+/// use rkyv::rancor::Error;
+///
+/// #[derive(rkyv::Archive, rkyv::Serialize)]
+/// struct Config {
+///     name: String,
+///     port: u16,
+/// }
+///
+/// let bytes = rkyv::to_bytes::<Error>(&Config { name: "foo".to_owned(), port: 80 }).unwrap();
+/// let rcu = Rcu::new(Arc::new(ArchivedBytes::<Config>::new(bytes).unwrap()));
+/// assert_eq!(rcu.read().name, "foo");
+///
+/// let bytes = rkyv::to_bytes::<Error>(&Config { name: "bar".to_owned(), port: 8080 }).unwrap();
+/// rcu.write_bytes(bytes).unwrap();
+/// assert_eq!(rcu.read().port, 8080);
+/// ```
+pub struct ArchivedBytes<T: Archive, B = AlignedVec> {
+    bytes: B,
+    _marker: PhantomData<T::Archived>,
+}
+
+impl<T, B> ArchivedBytes<T, B>
+where
+    T: Archive,
+    T::Archived: for<'a> CheckBytes<HighValidator<'a, rancor::Error>>,
+    B: ArchiveBuffer,
+{
+    /// Validates that `bytes` contain an archived `T`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes aren't a valid archive or they aren't aligned for `T`.
+    pub fn new(bytes: B) -> Result<Self, rancor::Error> {
+        rkyv::access::<T::Archived, rancor::Error>(&bytes)?;
+        Ok(Self {
+            bytes,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<T: Archive, B: ArchiveBuffer> ArchivedBytes<T, B> {
+    /// Wraps `bytes` without validating them.
+    ///
+    /// # Safety
+    ///
+    /// `bytes` must contain a valid archived `T`, see [`rkyv::access_unchecked`].
+    pub unsafe fn new_unchecked(bytes: B) -> Self {
+        Self {
+            bytes,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the buffer backing the archive.
+    pub fn bytes(&self) -> &B {
+        &self.bytes
+    }
+
+    /// Unwraps the buffer backing the archive.
+    pub fn into_bytes(self) -> B {
+        self.bytes
+    }
+}
+
+impl<T: Archive, B: ArchiveBuffer> Deref for ArchivedBytes<T, B> {
+    type Target = T::Archived;
+
+    fn deref(&self) -> &T::Archived {
+        // SAFETY: The bytes were validated on construction and can't change
+        unsafe { rkyv::access_unchecked::<T::Archived>(&self.bytes) }
+    }
+}
+
+impl<T, B> fmt::Debug for ArchivedBytes<T, B>
+where
+    T: Archive,
+    T::Archived: fmt::Debug,
+    B: ArchiveBuffer,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T, B, S> Rcu<ArchivedBytes<T, B>, S>
+where
+    T: Archive,
+    T::Archived: for<'a> CheckBytes<HighValidator<'a, rancor::Error>>,
+    B: ArchiveBuffer,
+    S: Reclaim<ArchivedBytes<T, B>>,
+{
+    /// Validates `bytes` and [`write`](Self::write)s them as a new version.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes aren't a valid archive of `T`, in which case the current
+    /// version is kept.
+    ///
+    /// # Panics
+    ///
+    /// See [`write`](Self::write).
+    #[track_caller]
+    pub fn write_bytes(&self, bytes: B) -> Result<(), rancor::Error> {
+        self.write(Arc::new(ArchivedBytes::new(bytes)?));
+        Ok(())
+    }
+}
@@ -0,0 +1,321 @@
+//! Versions allocated from a fixed-size static arena, without a heap
+
+use core::{
+    cell::UnsafeCell,
+    fmt,
+    mem::MaybeUninit,
+    ops::Deref,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{errors::ArenaFull, Rcu};
+
+/// Set in [`Slot::state`] while the slot holds a value which can be read
+const LIVE: usize = !(usize::MAX >> 1);
+
+/// A slot of an [`Arena`]
+///
+/// `state` is zero while the slot is free. Otherwise the low bits count the references to the
+/// value, i.e. the `ArenaRcu` it's current in and the [`ArenaGuard`]s, and [`LIVE`] is set until
+/// the value is dropped. Readers which find the slot without `LIVE` back off again.
+struct Slot<T> {
+    state: AtomicUsize,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+impl<T> Slot<T> {
+    const fn new() -> Self {
+        Self {
+            state: AtomicUsize::new(0),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+}
+
+/// A fixed number of slots for the versions of [`ArenaRcu`]s, which never allocates
+///
+/// `Arena::new` is a `const fn`, so the arena can be a `static`. Each `ArenaRcu` in the arena
+/// occupies a slot for its current version, and each version which is still being read occupies
+/// another one. A write fails with [`ArenaFull`] when every slot is occupied.
+///
+/// See [`Rcu::new_in_arena`] for an example.
+pub struct Arena<T, const N: usize> {
+    slots: [Slot<T>; N],
+}
+
+// SAFETY: Values are only written to free slots, and shared with readers afterwards
+unsafe impl<T: Send + Sync, const N: usize> Send for Arena<T, N> {}
+// SAFETY: See above
+unsafe impl<T: Send + Sync, const N: usize> Sync for Arena<T, N> {}
+
+impl<T, const N: usize> Arena<T, N> {
+    /// Creates an arena with `N` free slots.
+    pub const fn new() -> Self {
+        Self {
+            slots: [const { Slot::new() }; N],
+        }
+    }
+
+    /// Returns the number of free slots.
+    pub fn available(&self) -> usize {
+        self.slots
+            .iter()
+            .filter(|slot| slot.state.load(Ordering::Relaxed) == 0)
+            .count()
+    }
+
+    /// Moves `value` to a free slot, holding one reference to it.
+    fn alloc(&self, value: T) -> Result<usize, ArenaFull<T>> {
+        let Some(index) = self.slots.iter().position(|slot| {
+            slot.state
+                .compare_exchange(0, LIVE | 1, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+        }) else {
+            return Err(ArenaFull(value));
+        };
+
+        // SAFETY: The slot was free, so nobody else accesses the value
+        unsafe { (*self.slots[index].value.get()).write(value) };
+        Ok(index)
+    }
+
+    /// Takes a reference to the value in slot `index`, returning `false` if it has been dropped.
+    fn acquire(&self, index: usize) -> bool {
+        let state = &self.slots[index].state;
+        if state.fetch_add(1, Ordering::AcqRel) & LIVE != 0 {
+            return true;
+        }
+        // Slots can't be allocated while this reference is held, so `LIVE` is still unset
+        state.fetch_sub(1, Ordering::Release);
+        false
+    }
+
+    /// Releases a reference to the value in slot `index`, and frees it if it was the last one.
+    fn release(&self, index: usize) {
+        let slot = &self.slots[index];
+        if slot.state.fetch_sub(1, Ordering::AcqRel) != LIVE | 1 {
+            return;
+        }
+        // Readers may have acquired the value after the count dropped to zero, then the last of
+        // them frees it. Otherwise keep one reference while dropping, so it can't be allocated.
+        if slot
+            .state
+            .compare_exchange(LIVE, 1, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+        {
+            // SAFETY: The value was live and isn't referenced anymore
+            unsafe { (*slot.value.get()).assume_init_drop() };
+            slot.state.fetch_sub(1, Ordering::Release);
+        }
+    }
+
+    /// Returns the value in slot `index`.
+    ///
+    /// # Safety
+    ///
+    /// A reference to the value must be held.
+    unsafe fn get(&self, index: usize) -> &T {
+        // SAFETY: The value is live while it's referenced
+        unsafe { (*self.slots[index].value.get()).assume_init_ref() }
+    }
+}
+
+impl<T, const N: usize> Default for Arena<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> fmt::Debug for Arena<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("Arena");
+        d.field("capacity", &N);
+        d.field("available", &self.available());
+        d.finish_non_exhaustive()
+    }
+}
+
+impl<T> Rcu<T> {
+    /// Creates an [`ArenaRcu`] whose versions are allocated from `arena`, with `value` as the
+    /// initial version.
+    ///
+    /// This doesn't use the heap at all, so it's usable in firmware without an allocator.
+    ///
+    /// # Errors
+    ///
+    /// Returns `value` back if the arena has no free slot.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use axka_rcu::{Arena, Rcu};
+    /// static ARENA: Arena<[u8; 4], 3> = Arena::new();
+    ///
+    /// let address = Rcu::new_in_arena(&ARENA, [192, 168, 0, 1]).unwrap();
+    /// let old = address.read();
+    /// address.write([10, 0, 0, 1]).unwrap();
+    ///
+    /// // The current version and `old` occupy two slots
+    /// assert_eq!(ARENA.available(), 1);
+    /// let older = address.read();
+    /// address.write([10, 0, 0, 2]).unwrap();
+    /// assert!(address.write([10, 0, 0, 3]).is_err());
+    ///
+    /// drop((old, older));
+    /// address.write([10, 0, 0, 3]).unwrap();
+    /// assert_eq!(*address.read(), [10, 0, 0, 3]);
+    /// ```
+    pub fn new_in_arena<const N: usize>(
+        arena: &'static Arena<T, N>,
+        value: T,
+    ) -> Result<ArenaRcu<T, N>, ArenaFull<T>> {
+        ArenaRcu::new(arena, value)
+    }
+}
+
+/// An [`Rcu`] whose versions are allocated from an [`Arena`] instead of the heap
+///
+/// Created with [`Rcu::new_in_arena`]. Reads never block, but may retry while a write is
+/// replacing the current version. Writes fail with [`ArenaFull`] instead of allocating when the
+/// arena is exhausted.
+pub struct ArenaRcu<T: 'static, const N: usize> {
+    arena: &'static Arena<T, N>,
+    /// The slot of the current version
+    current: AtomicUsize,
+}
+
+impl<T, const N: usize> ArenaRcu<T, N> {
+    /// Creates a new `ArenaRcu`, see [`Rcu::new_in_arena`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `value` back if the arena has no free slot.
+    pub fn new(arena: &'static Arena<T, N>, value: T) -> Result<Self, ArenaFull<T>> {
+        let index = arena.alloc(value)?;
+        Ok(Self {
+            arena,
+            current: AtomicUsize::new(index),
+        })
+    }
+
+    /// Returns the arena the versions are allocated from.
+    pub fn arena(&self) -> &'static Arena<T, N> {
+        self.arena
+    }
+
+    /// Returns a guard to the current version, which keeps its slot occupied until dropped.
+    pub fn read(&self) -> ArenaGuard<T, N> {
+        loop {
+            let index = self.current.load(Ordering::Acquire);
+            if !self.arena.acquire(index) {
+                continue;
+            }
+            // The slot may have been freed and reused by another version in the meantime
+            if self.current.load(Ordering::Acquire) == index {
+                return ArenaGuard {
+                    arena: self.arena,
+                    index,
+                };
+            }
+            self.arena.release(index);
+        }
+    }
+
+    /// Runs `f` on a reference to the current version.
+    pub fn read_with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        f(&self.read())
+    }
+
+    /// Writes a new version.
+    ///
+    /// The replaced version is dropped once it's no longer read, freeing its slot.
+    ///
+    /// # Errors
+    ///
+    /// Returns `new_value` back if the arena has no free slot.
+    pub fn write(&self, new_value: T) -> Result<(), ArenaFull<T>> {
+        let index = self.arena.alloc(new_value)?;
+        let old = self.current.swap(index, Ordering::AcqRel);
+        self.arena.release(old);
+        Ok(())
+    }
+
+    /// Clones the current version, runs `updater` on it and [`write`](Self::write)s it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the updated value back if the arena has no free slot.
+    pub fn update<F, R>(&self, updater: F) -> Result<R, ArenaFull<T>>
+    where
+        T: Clone,
+        F: FnOnce(&mut T) -> R,
+    {
+        let mut new_value = T::clone(&self.read());
+        let ret = updater(&mut new_value);
+        self.write(new_value)?;
+        Ok(ret)
+    }
+}
+
+impl<T, const N: usize> Drop for ArenaRcu<T, N> {
+    fn drop(&mut self) {
+        self.arena.release(*self.current.get_mut());
+    }
+}
+
+/// Formats the current version.
+impl<T: fmt::Debug, const N: usize> fmt::Debug for ArenaRcu<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("ArenaRcu");
+        d.field("data", &&*self.read());
+        d.finish_non_exhaustive()
+    }
+}
+
+/// A version of an [`ArenaRcu`], returned by [`ArenaRcu::read`]
+pub struct ArenaGuard<T: 'static, const N: usize> {
+    arena: &'static Arena<T, N>,
+    index: usize,
+}
+
+impl<T, const N: usize> Deref for ArenaGuard<T, N> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: The guard holds a reference
+        unsafe { self.arena.get(self.index) }
+    }
+}
+
+impl<T, const N: usize> Clone for ArenaGuard<T, N> {
+    fn clone(&self) -> Self {
+        // The value can't be dropped while this guard holds a reference
+        let acquired = self.arena.acquire(self.index);
+        debug_assert!(acquired);
+        Self {
+            arena: self.arena,
+            index: self.index,
+        }
+    }
+}
+
+impl<T, const N: usize> Drop for ArenaGuard<T, N> {
+    fn drop(&mut self) {
+        self.arena.release(self.index);
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for ArenaGuard<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: fmt::Display, const N: usize> fmt::Display for ArenaGuard<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
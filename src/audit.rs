@@ -0,0 +1,178 @@
+//! Recording which thread and principal wrote each version
+
+use alloc::{borrow::Cow, collections::VecDeque, vec::Vec};
+use core::{cell::RefCell, fmt, marker::PhantomData};
+use std::{
+    thread::{self, Thread},
+    time::SystemTime,
+};
+
+use crate::{lock::Lock, Rcu, Reclaim};
+
+/// The number of writes kept by an `Rcu`
+const CAPACITY: usize = 1024;
+
+std::thread_local! {
+    /// The principal entered on the current thread
+    static PRINCIPAL: RefCell<Option<Cow<'static, str>>> = const { RefCell::new(None) };
+}
+
+/// Records the last [`CAPACITY`] writes of an `Rcu`
+pub(crate) struct Trail {
+    records: Lock<VecDeque<AuditRecord>>,
+}
+
+impl Trail {
+    pub(crate) const fn new() -> Self {
+        Self {
+            records: Lock::new(VecDeque::new()),
+        }
+    }
+
+    /// Records that the current thread wrote the version at `generation`.
+    pub(crate) fn record(&self, generation: u64) {
+        let record = AuditRecord {
+            generation,
+            thread: thread::current(),
+            principal: PRINCIPAL.with(|principal| principal.borrow().clone()),
+            written: SystemTime::now(),
+        };
+
+        let mut records = self.records.lock();
+        if records.len() == CAPACITY {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+}
+
+impl<T, S: Reclaim<T>> Rcu<T, S> {
+    /// Returns the last 1024 writes, oldest first.
+    ///
+    /// Each write records the thread which wrote the version, and the [`AuditPrincipal`] entered
+    /// on that thread, if any. The initial version isn't recorded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::sync::Arc;
+    /// use axka_rcu::{AuditPrincipal, Rcu};
+    /// let config = Rcu::new(Arc::new("debug = false"));
+    ///
+    /// config.write(Arc::new("debug = true"));
+    /// std::thread::scope(|s| {
+    ///     s.spawn(|| {
+    ///         let _principal = AuditPrincipal::enter("deploy-bot");
+    ///         config.write(Arc::new("debug = false"));
+    ///     });
+    /// });
+    ///
+    /// let trail = config.audit_trail();
+    /// assert_eq!(trail.len(), 2);
+    /// assert_eq!(trail[0].thread().id(), std::thread::current().id());
+    /// assert_eq!(trail[0].principal(), None);
+    /// assert_eq!(config.audit_record(2).unwrap().principal(), Some("deploy-bot"));
+    /// ```
+    pub fn audit_trail(&self) -> Vec<AuditRecord> {
+        self.audit.records.lock().iter().cloned().collect()
+    }
+
+    /// Returns the last recorded write of the version at `generation`, see
+    /// [`audit_trail`](Self::audit_trail).
+    ///
+    /// Returns `None` for the initial version, and for writes which are no longer kept.
+    pub fn audit_record(&self, generation: u64) -> Option<AuditRecord> {
+        let records = self.audit.records.lock();
+        records
+            .iter()
+            .rfind(|record| record.generation == generation)
+            .cloned()
+    }
+}
+
+/// A write recorded by an [`Rcu`], returned by [`Rcu::audit_trail`]
+#[derive(Clone, Debug)]
+pub struct AuditRecord {
+    generation: u64,
+    thread: Thread,
+    principal: Option<Cow<'static, str>>,
+    written: SystemTime,
+}
+
+impl AuditRecord {
+    /// Returns the [generation](Rcu::generation) of the written version.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Returns the thread which wrote the version.
+    pub fn thread(&self) -> &Thread {
+        &self.thread
+    }
+
+    /// Returns the [`AuditPrincipal`] entered on the thread, if any.
+    pub fn principal(&self) -> Option<&str> {
+        self.principal.as_deref()
+    }
+
+    /// Returns when the version was written.
+    pub fn written(&self) -> SystemTime {
+        self.written
+    }
+}
+
+impl fmt::Display for AuditRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "v{} written by ", self.generation)?;
+        match self.thread.name() {
+            Some(name) => write!(f, "thread '{name}'")?,
+            None => write!(f, "{:?}", self.thread.id())?,
+        }
+        if let Some(principal) = &self.principal {
+            write!(f, " as {principal}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Attributes the writes on the current thread to a principal, e.g. a user or a service, until
+/// dropped
+///
+/// The principal is recorded in the [`AuditRecord`]s of all `Rcu`s written on this thread.
+/// Entering another principal replaces this one until it's dropped.
+///
+/// See [`Rcu::audit_trail`] for an example.
+pub struct AuditPrincipal {
+    previous: Option<Cow<'static, str>>,
+    /// The principal must be restored on the same thread
+    _marker: PhantomData<*const ()>,
+}
+
+impl AuditPrincipal {
+    /// Enters `principal` on the current thread.
+    pub fn enter(principal: impl Into<Cow<'static, str>>) -> Self {
+        let principal = Some(principal.into());
+        Self {
+            previous: PRINCIPAL.with(|current| current.replace(principal)),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl Drop for AuditPrincipal {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        PRINCIPAL.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+impl fmt::Debug for AuditPrincipal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("AuditPrincipal");
+        d.field(
+            "principal",
+            &PRINCIPAL.with(|principal| principal.borrow().clone()),
+        );
+        d.finish()
+    }
+}
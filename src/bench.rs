@@ -0,0 +1,207 @@
+//! Measuring the `Rcu` against other ways of sharing a value
+//!
+//! The benchmarks in `benches/` run these with Criterion:
+//!
+//! ```text
+//! cargo bench --features bench
+//! ```
+//!
+//! Each `measure_*` function runs `iters` operations on the main thread while `readers` other
+//! threads read continuously, and returns the time the main thread took. This fits
+//! `Bencher::iter_custom` of Criterion, but works with any other harness too.
+//!
+//! # Example
+//!
+//! ```
+//! # use std::sync::{Arc, RwLock};
+//! use axka_rcu::{bench, Rcu};
+//!
+//! let rcu = Rcu::new(Arc::new(1));
+//! let lock = RwLock::new(Arc::new(1));
+//!
+//! let rcu_time = bench::measure_writes(&rcu, &2, 4, 1000);
+//! let lock_time = bench::measure_writes(&lock, &2, 4, 1000);
+//! println!("Rcu: {rcu_time:?}, RwLock: {lock_time:?}");
+//! ```
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Barrier, Mutex, RwLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use arc_swap::ArcSwap;
+
+use crate::{Rcu, Reclaim};
+
+/// A way of sharing a value between threads, which is compared with the others
+///
+/// Implemented for [`Rcu`], [`ArcSwap`], `RwLock<Arc<T>>` and `Mutex<T>`, each used the way
+/// which is idiomatic for it.
+pub trait Contender<T>: Sync {
+    /// The name of the implementation, e.g. for Criterion benchmark IDs
+    const NAME: &'static str;
+
+    /// Runs `f` on a reference to the current value.
+    fn read_with<R>(&self, f: impl FnOnce(&T) -> R) -> R;
+
+    /// Replaces the current value.
+    fn write(&self, new_value: T);
+
+    /// Runs `f` on a copy of the current value, and writes it.
+    fn update(&self, f: impl FnOnce(&mut T))
+    where
+        T: Clone;
+}
+
+impl<T: Send + Sync, S: Reclaim<T> + Sync> Contender<T> for Rcu<T, S> {
+    const NAME: &'static str = "Rcu";
+
+    #[inline]
+    fn read_with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        Rcu::read_with(self, f)
+    }
+
+    fn write(&self, new_value: T) {
+        Rcu::write(self, Arc::new(new_value));
+    }
+
+    fn update(&self, f: impl FnOnce(&mut T))
+    where
+        T: Clone,
+    {
+        Rcu::update(self, f);
+    }
+}
+
+impl<T: Send + Sync> Contender<T> for ArcSwap<T> {
+    const NAME: &'static str = "ArcSwap";
+
+    #[inline]
+    fn read_with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        f(&self.load())
+    }
+
+    fn write(&self, new_value: T) {
+        self.store(Arc::new(new_value));
+    }
+
+    fn update(&self, f: impl FnOnce(&mut T))
+    where
+        T: Clone,
+    {
+        let mut new_value = T::clone(&self.load());
+        f(&mut new_value);
+        self.store(Arc::new(new_value));
+    }
+}
+
+impl<T: Send + Sync> Contender<T> for RwLock<Arc<T>> {
+    const NAME: &'static str = "RwLock<Arc<T>>";
+
+    #[inline]
+    fn read_with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        let value = Arc::clone(&self.read().unwrap());
+        f(&value)
+    }
+
+    fn write(&self, new_value: T) {
+        let old = std::mem::replace(&mut *self.write().unwrap(), Arc::new(new_value));
+        // Drop the old value outside of the lock
+        drop(old);
+    }
+
+    fn update(&self, f: impl FnOnce(&mut T))
+    where
+        T: Clone,
+    {
+        let mut value = self.write().unwrap();
+        f(Arc::make_mut(&mut value));
+    }
+}
+
+impl<T: Send> Contender<T> for Mutex<T> {
+    const NAME: &'static str = "Mutex<T>";
+
+    #[inline]
+    fn read_with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        f(&self.lock().unwrap())
+    }
+
+    fn write(&self, new_value: T) {
+        *self.lock().unwrap() = new_value;
+    }
+
+    fn update(&self, f: impl FnOnce(&mut T))
+    where
+        T: Clone,
+    {
+        f(&mut self.lock().unwrap());
+    }
+}
+
+/// Runs `op` `iters` times while `readers` threads read `contender`, and returns the time it
+/// took.
+fn measure<T, C, F>(contender: &C, readers: usize, iters: u64, mut op: F) -> Duration
+where
+    C: Contender<T>,
+    F: FnMut(),
+{
+    let done = AtomicBool::new(false);
+    let start = Barrier::new(readers + 1);
+
+    thread::scope(|s| {
+        for _ in 0..readers {
+            s.spawn(|| {
+                start.wait();
+                while !done.load(Ordering::Relaxed) {
+                    contender.read_with(|value| {
+                        std::hint::black_box(value);
+                    });
+                }
+            });
+        }
+
+        start.wait();
+        let started = Instant::now();
+        for _ in 0..iters {
+            op();
+        }
+        let elapsed = started.elapsed();
+        done.store(true, Ordering::Relaxed);
+        elapsed
+    })
+}
+
+/// Measures `iters` reads while `readers` other threads read, see the [module docs](self).
+pub fn measure_reads<T, C: Contender<T>>(contender: &C, readers: usize, iters: u64) -> Duration {
+    measure(contender, readers, iters, || {
+        contender.read_with(|value| {
+            std::hint::black_box(value);
+        });
+    })
+}
+
+/// Measures `iters` writes of clones of `value` while `readers` threads read, see the
+/// [module docs](self).
+pub fn measure_writes<T: Clone, C: Contender<T>>(
+    contender: &C,
+    value: &T,
+    readers: usize,
+    iters: u64,
+) -> Duration {
+    measure(contender, readers, iters, || contender.write(value.clone()))
+}
+
+/// Measures `iters` runs of `update` while `readers` threads read, see the [module docs](self).
+pub fn measure_updates<T, C, F>(contender: &C, readers: usize, iters: u64, update: F) -> Duration
+where
+    T: Clone,
+    C: Contender<T>,
+    F: Fn(&mut T),
+{
+    measure(contender, readers, iters, || contender.update(&update))
+}
@@ -0,0 +1,133 @@
+//! Sharing versions with Bevy systems
+
+use core::{fmt, ops::Deref};
+
+use bevy_app::{App, First, Plugin};
+use bevy_ecs::{resource::Resource, system::ResMut};
+
+use crate::{Arc, Rcu};
+
+/// A Bevy [`Plugin`] which mirrors the current version of an `Rcu` into an [`RcuRes`] resource
+/// once per frame
+///
+/// Background threads, e.g. asset loaders or network tasks, write versions to the `Rcu` without
+/// touching the `World`. At the start of each frame, a system in the [`First`] schedule picks up
+/// the newest version, so every system sees the same version during a frame.
+///
+/// # Example
+///
+/// ```
+#[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+#[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+/// use axka_rcu::{Rcu, RcuPlugin, RcuRes};
+/// use bevy_app::{App, Update};
+/// use bevy_ecs::{change_detection::DetectChanges, system::{Res, ResMut}};
+/// # use bevy_ecs::resource::Resource;
+/// # #[derive(Resource, Default)]
+/// # struct Applied(Vec<u32>);
+///
+/// fn apply_difficulty(difficulty: Res<RcuRes<u32>>, mut applied: ResMut<Applied>) {
+///     if difficulty.is_changed() {
+///         applied.0.push(**difficulty);
+///     }
+/// }
+///
+/// let difficulty = Arc::new(Rcu::new(Arc::new(1u32)));
+/// let mut app = App::new();
+/// app.init_resource::<Applied>()
+///     .add_plugins(RcuPlugin::new(Arc::clone(&difficulty)))
+///     .add_systems(Update, apply_difficulty);
+///
+/// app.update();
+/// app.update();
+/// std::thread::spawn(move || difficulty.write(Arc::new(2))).join().unwrap();
+/// app.update();
+///
+/// assert_eq!(app.world().resource::<Applied>().0, [1, 2]);
+/// ```
+pub struct RcuPlugin<T> {
+    rcu: Arc<Rcu<T>>,
+}
+
+impl<T> RcuPlugin<T> {
+    /// Creates a plugin mirroring `rcu` into an [`RcuRes<T>`].
+    pub fn new(rcu: Arc<Rcu<T>>) -> Self {
+        Self { rcu }
+    }
+}
+
+impl<T: Send + Sync + 'static> Plugin for RcuPlugin<T> {
+    fn build(&self, app: &mut App) {
+        let (current, generation) = self.rcu.read_versioned();
+        app.insert_resource(RcuRes {
+            rcu: Arc::clone(&self.rcu),
+            current,
+            generation,
+        })
+        .add_systems(First, sync::<T>);
+    }
+}
+
+impl<T> fmt::Debug for RcuPlugin<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("RcuPlugin");
+        d.field("type_name", &core::any::type_name::<T>());
+        d.finish_non_exhaustive()
+    }
+}
+
+/// A resource holding the version of an `Rcu` for the current frame, inserted by [`RcuPlugin`]
+///
+/// It's only marked as changed when a new version is picked up, so
+/// [`is_changed`](bevy_ecs::change_detection::DetectChanges::is_changed) and the
+/// `resource_changed` run condition work as for other resources.
+#[derive(Resource)]
+pub struct RcuRes<T: Send + Sync + 'static> {
+    rcu: Arc<Rcu<T>>,
+    current: Arc<T>,
+    generation: u64,
+}
+
+impl<T: Send + Sync + 'static> RcuRes<T> {
+    /// Returns the version for the current frame.
+    pub fn version(&self) -> &Arc<T> {
+        &self.current
+    }
+
+    /// Returns the [generation](Rcu::generation) of the version for the current frame.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Returns the `Rcu` the versions come from, e.g. for handing it to a background thread.
+    pub fn rcu(&self) -> &Arc<Rcu<T>> {
+        &self.rcu
+    }
+}
+
+impl<T: Send + Sync + 'static> Deref for RcuRes<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.current
+    }
+}
+
+impl<T: fmt::Debug + Send + Sync + 'static> fmt::Debug for RcuRes<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("RcuRes");
+        d.field("data", &self.current);
+        d.field("generation", &self.generation);
+        d.finish_non_exhaustive()
+    }
+}
+
+/// Picks up a new version, only marking the resource as changed if there is one.
+fn sync<T: Send + Sync + 'static>(mut res: ResMut<RcuRes<T>>) {
+    let (current, generation) = res.rcu.read_versioned();
+    if generation != res.generation {
+        let res = &mut *res;
+        res.current = current;
+        res.generation = generation;
+    }
+}
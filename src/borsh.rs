@@ -0,0 +1,35 @@
+//! Borsh serialization of the current version
+
+use ::borsh::{
+    io::{Read, Result, Write},
+    BorshDeserialize, BorshSerialize,
+};
+
+use crate::{Arc, Rcu, Reclaim};
+
+/// Serializes the current version.
+///
+/// # Example
+///
+/// ```
+#[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+#[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+/// use axka_rcu::Rcu;
+/// let rcu = Rcu::new(Arc::new(42u32));
+///
+/// let bytes = borsh::to_vec(&rcu).unwrap();
+/// let rcu2: Rcu<u32> = borsh::from_slice(&bytes).unwrap();
+/// assert_eq!(*rcu2.read(), 42);
+/// ```
+impl<T: BorshSerialize, S: Reclaim<T>> BorshSerialize for Rcu<T, S> {
+    fn serialize<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.read_with(|value| value.serialize(writer))
+    }
+}
+
+/// Deserializes a value into a new `Rcu`.
+impl<T: BorshDeserialize> BorshDeserialize for Rcu<T> {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> Result<Self> {
+        T::deserialize_reader(reader).map(|value| Self::new(Arc::new(value)))
+    }
+}
@@ -0,0 +1,136 @@
+//! Broadcasting new versions to many subscribers
+
+use core::{fmt, ptr};
+
+use tokio::sync::broadcast;
+
+use crate::{Arc, Rcu, Reclaim};
+
+impl<T, S> Rcu<T, S>
+where
+    T: Send + Sync + 'static,
+    S: Reclaim<T>,
+{
+    /// Creates a [`Broadcast`] which sends every version written after this call to all of its
+    /// subscribers.
+    ///
+    /// `capacity` is the number of versions kept for subscribers which fall behind, see
+    /// [`tokio::sync::broadcast`] for the lag semantics. Versions stop being sent once the
+    /// `Broadcast` is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new("foo"));
+    /// let broadcast = rcu.broadcast(16);
+    /// let mut audit = broadcast.subscribe();
+    /// let mut events = broadcast.subscribe();
+    ///
+    /// rcu.write(Arc::new("bar"));
+    /// rcu.write(Arc::new("baz"));
+    ///
+    /// assert_eq!(*audit.try_recv().unwrap(), "bar");
+    /// assert_eq!(*audit.try_recv().unwrap(), "baz");
+    /// assert_eq!(*events.try_recv().unwrap(), "bar");
+    /// ```
+    pub fn broadcast(&self, capacity: usize) -> Broadcast<T> {
+        let (sender, _) = broadcast::channel(capacity);
+
+        let weak = sender.downgrade();
+        self.hooks.add(move |version| match weak.upgrade() {
+            Some(sender) => {
+                // Sending only fails if there are no subscribers right now
+                let _ = sender.send(Arc::clone(version));
+                true
+            }
+            None => false,
+        });
+
+        Broadcast { sender }
+    }
+
+    /// Like [`broadcast`](Self::broadcast), but doesn't send versions which are equal to the last
+    /// sent version.
+    ///
+    /// This way, publishing the same value again doesn't wake every subscriber. The version at the
+    /// time of this call counts as sent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new("foo"));
+    /// let broadcast = rcu.broadcast_distinct(16);
+    /// let mut receiver = broadcast.subscribe();
+    ///
+    /// rcu.write(Arc::new("foo"));
+    /// rcu.write(Arc::new("bar"));
+    /// rcu.write(Arc::new("bar"));
+    ///
+    /// assert_eq!(*receiver.try_recv().unwrap(), "bar");
+    /// assert!(receiver.try_recv().is_err());
+    /// ```
+    pub fn broadcast_distinct(&self, capacity: usize) -> Broadcast<T>
+    where
+        T: PartialEq,
+    {
+        let (sender, _) = broadcast::channel(capacity);
+
+        let weak = sender.downgrade();
+        let last_sent = Rcu::new(self.read());
+        self.hooks.add(move |version| match weak.upgrade() {
+            Some(sender) => {
+                let is_sent = last_sent.read_with(|last_sent| {
+                    ptr::eq(last_sent, &**version) || last_sent == &**version
+                });
+                if !is_sent {
+                    last_sent.write(Arc::clone(version));
+                    // Sending only fails if there are no subscribers right now
+                    let _ = sender.send(Arc::clone(version));
+                }
+                true
+            }
+            None => false,
+        });
+
+        Broadcast { sender }
+    }
+}
+
+/// Hands out receivers of the versions written to an [`Rcu`], created by [`Rcu::broadcast`]
+pub struct Broadcast<T> {
+    sender: broadcast::Sender<Arc<T>>,
+}
+
+impl<T> Broadcast<T> {
+    /// Creates a receiver of the versions written after this call.
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<T>> {
+        self.sender.subscribe()
+    }
+
+    /// Returns the number of active receivers.
+    pub fn receiver_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+impl<T> fmt::Debug for Broadcast<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("Broadcast");
+        d.field("receiver_count", &self.receiver_count());
+        d.finish_non_exhaustive()
+    }
+}
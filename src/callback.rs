@@ -0,0 +1,72 @@
+//! Hot-swappable callbacks
+
+use alloc::boxed::Box;
+use core::fmt;
+
+use crate::{Arc, Rcu};
+
+/// A callback which can be replaced while it's being called, e.g. a handler, a filter or a
+/// scoring function of a plugin
+///
+/// Functions with several arguments take them as a tuple.
+///
+/// # Example
+///
+/// ```
+/// use axka_rcu::RcuFn;
+///
+/// let score = RcuFn::new(|(hits, misses): (u32, u32)| hits.saturating_sub(misses));
+/// assert_eq!(score.call((5, 2)), 3);
+///
+/// score.replace(|(hits, misses)| hits * 2 - misses);
+/// assert_eq!(score.call((5, 2)), 8);
+/// ```
+pub struct RcuFn<Args, Out> {
+    rcu: Rcu<Box<dyn Fn(Args) -> Out + Send + Sync>>,
+}
+
+impl<Args, Out> RcuFn<Args, Out> {
+    /// Creates a new `RcuFn` calling `f`.
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(Args) -> Out + Send + Sync + 'static,
+    {
+        Self {
+            rcu: Rcu::new(Arc::new(Box::new(f))),
+        }
+    }
+
+    /// Calls the current function with `args`.
+    ///
+    /// Replacing the function doesn't affect calls which already started.
+    #[inline]
+    pub fn call(&self, args: Args) -> Out {
+        self.rcu.read_with(|f| f(args))
+    }
+
+    /// Replaces the function for subsequent calls.
+    ///
+    /// # Panics
+    ///
+    /// See [`Rcu::write`].
+    pub fn replace<F>(&self, f: F)
+    where
+        F: Fn(Args) -> Out + Send + Sync + 'static,
+    {
+        self.rcu.write(Arc::new(Box::new(f)));
+    }
+
+    /// Returns the `Rcu` holding the function, e.g. for [`generation`](Rcu::generation) or
+    /// [freezing](Rcu::freeze) it.
+    pub fn rcu(&self) -> &Rcu<Box<dyn Fn(Args) -> Out + Send + Sync>> {
+        &self.rcu
+    }
+}
+
+impl<Args, Out> fmt::Debug for RcuFn<Args, Out> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("RcuFn");
+        d.field("generation", &self.rcu.generation());
+        d.finish_non_exhaustive()
+    }
+}
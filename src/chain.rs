@@ -0,0 +1,192 @@
+//! Layers of [`Rcu`]s which override each other when read
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{Arc, Rcu, Reclaim, RefCount};
+
+/// A stack of [`Rcu`]s, where reads resolve through the layers from the top, e.g. runtime
+/// overrides over file config over defaults
+///
+/// Each layer is a separate `Rcu`, which can be written to independently without merging the
+/// layers. Reads ask each layer for a value until one has it, so nothing is merged eagerly.
+/// Layers pushed later take precedence.
+///
+/// # Example
+///
+/// ```
+#[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+#[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+/// # use std::collections::BTreeMap;
+/// use axka_rcu::{Rcu, RcuChain};
+/// let defaults = Arc::new(Rcu::new(Arc::new(BTreeMap::from([("host", "::"), ("port", "80")]))));
+/// let file = Arc::new(Rcu::new(Arc::new(BTreeMap::from([("port", "8080")]))));
+/// let overrides = Arc::new(Rcu::new(Arc::new(BTreeMap::new())));
+///
+/// let config = RcuChain::new()
+///     .with_layer(defaults)
+///     .with_layer(file)
+///     .with_layer(Arc::clone(&overrides));
+/// assert_eq!(config.resolve(|layer| layer.get("port").copied()), Some("8080"));
+/// assert_eq!(config.resolve(|layer| layer.get("host").copied()), Some("::"));
+///
+/// overrides.update(|layer| {
+///     layer.insert("port", "9090");
+/// });
+/// assert_eq!(config.resolve(|layer| layer.get("port").copied()), Some("9090"));
+/// ```
+pub struct RcuChain<T, S = RefCount> {
+    /// The layers, from the bottom to the top
+    layers: Vec<Arc<Rcu<T, S>>>,
+}
+
+impl<T, S> RcuChain<T, S> {
+    /// Creates a chain without layers.
+    pub const fn new() -> Self {
+        Self { layers: Vec::new() }
+    }
+
+    /// Adds a layer on top of the others.
+    pub fn with_layer(mut self, layer: Arc<Rcu<T, S>>) -> Self {
+        self.push(layer);
+        self
+    }
+
+    /// Adds a layer on top of the others.
+    pub fn push(&mut self, layer: Arc<Rcu<T, S>>) {
+        self.layers.push(layer);
+    }
+
+    /// Returns the layers, from the bottom to the top.
+    pub fn layers(&self) -> &[Arc<Rcu<T, S>>] {
+        &self.layers
+    }
+
+    /// Returns the number of layers.
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Returns `true` if the chain has no layers.
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+}
+
+impl<T, S: Reclaim<T>> RcuChain<T, S> {
+    /// Runs `f` on the current version of each layer from the top, returning the first `Some`.
+    ///
+    /// Each layer is read separately, so a resolution may see a layer before and another one
+    /// after a concurrent write. Use [`read`](Self::read) to resolve several values from the same
+    /// versions.
+    pub fn resolve<F, R>(&self, mut f: F) -> Option<R>
+    where
+        F: FnMut(&T) -> Option<R>,
+    {
+        self.layers
+            .iter()
+            .rev()
+            .find_map(|layer| layer.read_with(&mut f))
+    }
+
+    /// Clones the [`Arc`]s of the current versions of all layers.
+    ///
+    /// The layers are read one after the other, so a write to a lower layer may be missed while a
+    /// later write to a higher layer is seen.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::{Rcu, RcuChain};
+    /// let overrides = Arc::new(Rcu::new(Arc::new((None, Some(10)))));
+    /// let config = RcuChain::new()
+    ///     .with_layer(Arc::new(Rcu::new(Arc::new((Some(1), Some(2))))))
+    ///     .with_layer(Arc::clone(&overrides));
+    ///
+    /// let snapshot = config.read();
+    /// overrides.write(Arc::new((Some(100), None)));
+    /// assert_eq!(snapshot.resolve(|layer| layer.0), Some(1));
+    /// assert_eq!(snapshot.resolve(|layer| layer.1), Some(10));
+    /// assert_eq!(config.resolve(|layer| layer.0), Some(100));
+    /// ```
+    pub fn read(&self) -> ChainSnapshot<T> {
+        ChainSnapshot {
+            versions: self.layers.iter().map(|layer| layer.read()).collect(),
+        }
+    }
+
+    /// Returns the sum of the [generations](Rcu::generation) of the layers, which changes whenever
+    /// a layer is written to.
+    pub fn generation(&self) -> u64 {
+        self.layers
+            .iter()
+            .fold(0, |sum, layer| sum.wrapping_add(layer.generation()))
+    }
+}
+
+impl<T, S> Default for RcuChain<T, S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, S> Clone for RcuChain<T, S> {
+    /// Clones the chain, sharing its layers.
+    fn clone(&self) -> Self {
+        Self {
+            layers: self.layers.clone(),
+        }
+    }
+}
+
+impl<T, S: Reclaim<T>> fmt::Debug for RcuChain<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("RcuChain");
+        d.field("layers", &self.layers.len());
+        d.field("generation", &self.generation());
+        d.finish_non_exhaustive()
+    }
+}
+
+/// The versions of the layers of an [`RcuChain`], created by [`RcuChain::read`]
+///
+/// Writes to the layers after the snapshot was taken aren't seen by it.
+pub struct ChainSnapshot<T> {
+    /// The versions, from the bottom to the top
+    versions: Vec<Arc<T>>,
+}
+
+impl<T> ChainSnapshot<T> {
+    /// Runs `f` on each version from the top, returning the first `Some`.
+    pub fn resolve<F, R>(&self, f: F) -> Option<R>
+    where
+        F: FnMut(&T) -> Option<R>,
+    {
+        self.versions
+            .iter()
+            .rev()
+            .map(|version| &**version)
+            .find_map(f)
+    }
+
+    /// Returns the versions, from the bottom to the top.
+    pub fn versions(&self) -> &[Arc<T>] {
+        &self.versions
+    }
+}
+
+impl<T> Clone for ChainSnapshot<T> {
+    fn clone(&self) -> Self {
+        Self {
+            versions: self.versions.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ChainSnapshot<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(&self.versions).finish()
+    }
+}
@@ -0,0 +1,174 @@
+//! Waiting for a new version without an async runtime
+
+use core::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::{Arc, Rcu, Reclaim};
+
+impl<T, S: Reclaim<T>> Rcu<T, S> {
+    /// Returns a future which resolves to the next version written after this call.
+    ///
+    /// Only one waiter is registered per `Rcu`: if multiple `Changed` futures are pending at the
+    /// same time, only the one polled last is woken. Use one task per `Rcu` to wait for changes.
+    ///
+    /// The future keeps the version at the time of this call alive until it resolves.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// use futures::executor::block_on;
+    /// let rcu = Arc::new(Rcu::new(Arc::new("foo")));
+    ///
+    /// let changed = rcu.changed();
+    /// let rcu2 = rcu.clone();
+    /// std::thread::spawn(move || rcu2.write(Arc::new("bar")));
+    /// assert_eq!(*block_on(changed), "bar");
+    /// ```
+    pub fn changed(&self) -> Changed<'_, T, S> {
+        Changed {
+            rcu: self,
+            start: self.read(),
+            eq: None,
+        }
+    }
+
+    /// Returns a future which resolves to the first version satisfying `predicate`, starting with
+    /// the current version.
+    ///
+    /// Versions are checked when the future is polled, so versions which are replaced before that
+    /// may be skipped. Like [`changed`](Self::changed), only the future polled last is woken.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// use futures::executor::block_on;
+    /// let status = Arc::new(Rcu::new(Arc::new("starting")));
+    ///
+    /// let status2 = status.clone();
+    /// std::thread::spawn(move || {
+    ///     status2.write(Arc::new("loading"));
+    ///     status2.write(Arc::new("ready"));
+    /// });
+    /// assert_eq!(*block_on(status.wait_for(|status| *status == "ready")), "ready");
+    /// ```
+    pub fn wait_for<F>(&self, predicate: F) -> WaitFor<'_, T, S, F>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        WaitFor {
+            rcu: self,
+            predicate,
+        }
+    }
+}
+
+/// A future which resolves to the next version of an [`Rcu`], created by [`Rcu::changed`]
+pub struct Changed<'a, T, S> {
+    rcu: &'a Rcu<T, S>,
+    /// Keeps the version alive, so its address can't be reused by a newer version
+    start: Arc<T>,
+    /// Set by [`distinct`](Self::distinct)
+    eq: Option<fn(&T, &T) -> bool>,
+}
+
+impl<T: PartialEq, S> Changed<'_, T, S> {
+    /// Skips new versions which are equal to the previous version.
+    ///
+    /// This way, publishing the same value again doesn't wake the waiting task.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// use futures::{executor::block_on, FutureExt};
+    /// let rcu = Rcu::new(Arc::new("foo"));
+    ///
+    /// let mut changed = rcu.changed().distinct();
+    /// rcu.write(Arc::new("foo"));
+    /// assert!((&mut changed).now_or_never().is_none());
+    ///
+    /// rcu.write(Arc::new("bar"));
+    /// assert_eq!(*block_on(changed), "bar");
+    /// ```
+    pub fn distinct(mut self) -> Self {
+        self.eq = Some(T::eq);
+        self
+    }
+}
+
+// The future is never pinned structurally, and `triomphe::Arc<T>` is only `Unpin` if `T` is.
+impl<T, S> Unpin for Changed<'_, T, S> {}
+
+impl<T, S: Reclaim<T>> Future for Changed<'_, T, S> {
+    type Output = Arc<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Arc<T>> {
+        let this = self.get_mut();
+        // The waker is registered before checking, so a write after the check wakes it
+        this.rcu.hooks.waker().register(cx.waker());
+
+        let current = this.rcu.read();
+        if Arc::ptr_eq(&current, &this.start) {
+            return Poll::Pending;
+        }
+        if this.eq.is_some_and(|eq| eq(&current, &this.start)) {
+            this.start = current;
+            return Poll::Pending;
+        }
+        Poll::Ready(current)
+    }
+}
+
+impl<T: fmt::Debug, S> fmt::Debug for Changed<'_, T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("Changed");
+        d.field("start", &self.start);
+        d.finish_non_exhaustive()
+    }
+}
+
+/// A future which resolves to the first version of an [`Rcu`] satisfying a predicate, created by
+/// [`Rcu::wait_for`]
+pub struct WaitFor<'a, T, S, F> {
+    rcu: &'a Rcu<T, S>,
+    predicate: F,
+}
+
+impl<T, S, F> Future for WaitFor<'_, T, S, F>
+where
+    S: Reclaim<T>,
+    F: FnMut(&T) -> bool + Unpin,
+{
+    type Output = Arc<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Arc<T>> {
+        // The waker is registered before checking, so a write after the check wakes it
+        self.rcu.hooks.waker().register(cx.waker());
+
+        let current = self.rcu.read();
+        if (self.predicate)(&current) {
+            Poll::Ready(current)
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl<T, S, F> fmt::Debug for WaitFor<'_, T, S, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("WaitFor");
+        d.finish_non_exhaustive()
+    }
+}
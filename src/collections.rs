@@ -0,0 +1,268 @@
+//! Helpers for collections protected by an [`Rcu`]
+
+use alloc::vec::Vec;
+#[cfg(not(feature = "triomphe"))]
+use core::{
+    borrow::Borrow,
+    hash::{BuildHasher, Hash},
+};
+use core::{
+    fmt,
+    ops::{Deref, DerefMut},
+};
+#[cfg(not(feature = "triomphe"))]
+use std::collections::HashMap;
+
+#[cfg(not(feature = "triomphe"))]
+use crate::MappedGuard;
+use crate::{Arc, Rcu, Reclaim, RefCount};
+
+/// Helpers for modifying an `Rcu<Vec<T>>` in one call
+///
+/// Each method clones the current version, modifies it and writes it using
+/// [`Rcu::update_retry`], so concurrent modifications aren't lost.
+///
+/// # Example
+///
+/// ```
+#[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+#[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+/// use axka_rcu::{Rcu, RcuVecExt};
+/// let rcu = Rcu::new(Arc::new(vec![1, 2, 3]));
+///
+/// rcu.push(4);
+/// rcu.extend([5, 6]);
+/// rcu.retain(|n| n % 2 == 0);
+/// assert_eq!(rcu.remove(0), 2);
+/// assert_eq!(*rcu.read(), [4, 6]);
+/// ```
+pub trait RcuVecExt<T> {
+    /// Appends an element to the back of the collection.
+    fn push(&self, value: T);
+
+    /// Retains only the elements specified by the predicate.
+    ///
+    /// The predicate may be called multiple times for each element.
+    fn retain<F>(&self, f: F)
+    where
+        F: FnMut(&T) -> bool;
+
+    /// Removes and returns the element at `index`, shifting all elements after it to the left.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    fn remove(&self, index: usize) -> T;
+
+    /// Appends all elements of an iterator to the back of the collection.
+    fn extend<I>(&self, iter: I)
+    where
+        I: IntoIterator<Item = T>;
+}
+
+impl<T: Clone, S: Reclaim<Vec<T>>> RcuVecExt<T> for Rcu<Vec<T>, S> {
+    fn push(&self, value: T) {
+        self.update_retry(|vec| vec.push(value.clone()));
+    }
+
+    fn retain<F>(&self, mut f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.update_retry(|vec| vec.retain(&mut f));
+    }
+
+    fn remove(&self, index: usize) -> T {
+        self.update_retry(|vec| vec.remove(index))
+    }
+
+    fn extend<I>(&self, iter: I)
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let values: Vec<T> = iter.into_iter().collect();
+        self.update_retry(|vec| vec.extend_from_slice(&values));
+    }
+}
+
+/// Helpers for modifying an `Rcu<HashMap<K, V>>` in one call
+///
+/// Each method that modifies the map clones the current version, modifies it and writes it using
+/// [`Rcu::update_retry`], so concurrent modifications aren't lost.
+///
+/// # Example
+///
+/// ```
+/// # use std::{collections::HashMap, sync::Arc};
+/// use axka_rcu::{Rcu, RcuMapExt};
+/// let rcu = Rcu::new(Arc::new(HashMap::new()));
+///
+/// assert_eq!(rcu.insert("foo", 1), None);
+/// rcu.insert("bar", 2);
+/// rcu.entry("foo", |value| *value = value.map(|n| n + 10));
+/// assert_eq!(rcu.remove("bar"), Some(2));
+///
+/// assert_eq!(rcu.get_arc("foo").as_deref(), Some(&11));
+/// assert!(rcu.get_arc("bar").is_none());
+/// ```
+#[cfg(not(feature = "triomphe"))]
+pub trait RcuMapExt<K, V> {
+    /// Inserts a key-value pair into the map, returning the old value.
+    fn insert(&self, key: K, value: V) -> Option<V>;
+
+    /// Removes a key from the map, returning the old value.
+    fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq;
+
+    /// Returns a guard to the value corresponding to the key in the current version.
+    ///
+    /// The guard keeps the whole version alive, see [`Rcu::map_read`].
+    fn get_arc<Q>(&self, key: &Q) -> Option<MappedGuard<V>>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq;
+
+    /// Runs `f` on the value corresponding to the key.
+    ///
+    /// The value is `None` if the key isn't in the map. Setting it to `None` removes the key and
+    /// setting it to `Some` inserts it. `f` may be called multiple times.
+    fn entry<F, R>(&self, key: K, f: F) -> R
+    where
+        F: FnMut(&mut Option<V>) -> R;
+}
+
+#[cfg(not(feature = "triomphe"))]
+impl<K, V, H, S> RcuMapExt<K, V> for Rcu<HashMap<K, V, H>, S>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+    H: BuildHasher + Clone,
+    S: Reclaim<HashMap<K, V, H>>,
+{
+    fn insert(&self, key: K, value: V) -> Option<V> {
+        self.update_retry(|map| map.insert(key.clone(), value.clone()))
+    }
+
+    fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        if !self.read_with(|map| map.contains_key(key)) {
+            return None;
+        }
+        self.update_retry(|map| map.remove(key))
+    }
+
+    fn get_arc<Q>(&self, key: &Q) -> Option<MappedGuard<V>>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        MappedGuard::try_new(self.read(), |map| map.get(key))
+    }
+
+    fn entry<F, R>(&self, key: K, mut f: F) -> R
+    where
+        F: FnMut(&mut Option<V>) -> R,
+    {
+        self.update_retry(|map| {
+            let mut value = map.remove(&key);
+            let ret = f(&mut value);
+            if let Some(value) = value {
+                map.insert(key.clone(), value);
+            }
+            ret
+        })
+    }
+}
+
+/// Collects into a collection, which becomes the first version.
+///
+/// This works for any collection implementing `FromIterator`, e.g. `Vec`, `HashMap` or
+/// `BTreeMap`.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::BTreeMap;
+///
+/// use axka_rcu::{Rcu, RcuVecExt};
+/// let squares: Rcu<Vec<_>> = (1..4).map(|n| n * n).collect();
+/// squares.push(16);
+/// assert_eq!(*squares.read(), [1, 4, 9, 16]);
+///
+/// let names: Rcu<BTreeMap<_, _>> = [(1, "foo"), (2, "bar")].into_iter().collect();
+/// assert_eq!(names.read()[&2], "bar");
+/// ```
+impl<A, C: FromIterator<A>> FromIterator<A> for Rcu<C> {
+    fn from_iter<I: IntoIterator<Item = A>>(iter: I) -> Self {
+        Self::new(Arc::new(iter.into_iter().collect()))
+    }
+}
+
+impl<T: Clone, S: Reclaim<Arc<[T]>>> Rcu<Arc<[T]>, S> {
+    /// Copies the current version into a growable buffer, which can be edited and then
+    /// [published](SliceEditor::publish) as a new version.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu: Rcu<Arc<[u8]>> = Rcu::new(Arc::new(Arc::from(vec![1, 2, 3])));
+    ///
+    /// let mut editor = rcu.edit();
+    /// editor.splice(1..2, [4, 5]);
+    /// editor.push(6);
+    /// editor.publish();
+    ///
+    /// assert_eq!(**rcu.read(), [1, 4, 5, 3, 6]);
+    /// ```
+    pub fn edit(&self) -> SliceEditor<'_, T, S> {
+        SliceEditor {
+            rcu: self,
+            buf: self.read_with(|slice| slice.to_vec()),
+        }
+    }
+}
+
+/// An editable copy of a slice version, created by [`Rcu::edit`]
+///
+/// The edits are discarded if this is dropped without [publishing](Self::publish).
+pub struct SliceEditor<'a, T, S = RefCount> {
+    rcu: &'a Rcu<Arc<[T]>, S>,
+    buf: Vec<T>,
+}
+
+impl<T, S: Reclaim<Arc<[T]>>> SliceEditor<'_, T, S> {
+    /// [Writes](Rcu::write) the edited slice as a new version.
+    pub fn publish(self) {
+        self.rcu.write(Arc::new(Arc::from(self.buf)))
+    }
+}
+
+impl<T, S> Deref for SliceEditor<'_, T, S> {
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Vec<T> {
+        &self.buf
+    }
+}
+
+impl<T, S> DerefMut for SliceEditor<'_, T, S> {
+    fn deref_mut(&mut self) -> &mut Vec<T> {
+        &mut self.buf
+    }
+}
+
+impl<T: fmt::Debug, S> fmt::Debug for SliceEditor<'_, T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("SliceEditor");
+        d.field("data", &self.buf);
+        d.finish_non_exhaustive()
+    }
+}
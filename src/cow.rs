@@ -0,0 +1,234 @@
+//! An `Rcu` whose default version is borrowed from a static, until the first write
+
+use alloc::boxed::Box;
+use core::{
+    fmt,
+    marker::PhantomData,
+    ops::Deref,
+    ptr,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+use crate::{errors::WriteError, Arc, Rcu};
+
+impl<T> Rcu<T> {
+    /// Creates a [`CowRcu`] with `value` as the initial version, without allocating.
+    ///
+    /// The static version can't be handed out as an [`Arc`], so this returns a `CowRcu` instead of
+    /// an `Rcu`. It switches to `Arc`-backed versions on the first write.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::{CowRcu, Rcu};
+    /// static LIMITS: CowRcu<[u32; 2]> = Rcu::from_static(&[10, 100]);
+    ///
+    /// assert_eq!(*LIMITS.read(), [10, 100]);
+    /// LIMITS.update(|limits| limits[0] = 20);
+    /// assert_eq!(*LIMITS.read(), [20, 100]);
+    /// ```
+    pub const fn from_static(value: &'static T) -> CowRcu<T> {
+        CowRcu::new(value)
+    }
+}
+
+/// An [`Rcu`] which starts out with a `&'static T` default, like a `Cow<'static, T>`
+///
+/// Creating a `CowRcu` doesn't allocate, so it can be used in a `static`, see also
+/// [`Rcu::from_static`]. The `Rcu` holding the written versions is only allocated by the first
+/// write.
+///
+/// # Example
+///
+/// ```
+#[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+#[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+/// use axka_rcu::CowRcu;
+///
+/// struct Config {
+///     port: u16,
+///     debug: bool,
+/// }
+///
+/// static DEFAULT: Config = Config { port: 80, debug: false };
+/// static CONFIG: CowRcu<Config> = CowRcu::new(&DEFAULT);
+///
+/// assert!(CONFIG.is_default());
+/// assert_eq!(CONFIG.read().port, 80);
+///
+/// CONFIG.write(Arc::new(Config { port: 8080, debug: true }));
+/// assert!(!CONFIG.is_default());
+/// assert_eq!(CONFIG.read_with(|config| config.port), 8080);
+/// ```
+pub struct CowRcu<T: 'static> {
+    default: &'static T,
+    /// Null until the first write
+    rcu: AtomicPtr<Rcu<T>>,
+    _marker: PhantomData<Box<Rcu<T>>>,
+}
+
+impl<T> CowRcu<T> {
+    /// Creates a new `CowRcu` with `default` as the current version.
+    pub const fn new(default: &'static T) -> Self {
+        Self {
+            default,
+            rcu: AtomicPtr::new(ptr::null_mut()),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the `Rcu` holding the written versions, or `None` if no version has been written.
+    pub fn rcu(&self) -> Option<&Rcu<T>> {
+        let rcu = self.rcu.load(Ordering::Acquire);
+        // SAFETY: The `Rcu` is only freed when dropping `self`
+        (!rcu.is_null()).then(|| unsafe { &*rcu })
+    }
+
+    /// Returns `true` if the current version is still the static default.
+    pub fn is_default(&self) -> bool {
+        self.rcu().is_none()
+    }
+
+    /// Returns the current version.
+    ///
+    /// This doesn't allocate, and only touches a reference count once a version has been written.
+    pub fn read(&self) -> CowVersion<T> {
+        match self.rcu() {
+            Some(rcu) => CowVersion::Shared(rcu.read()),
+            None => CowVersion::Static(self.default),
+        }
+    }
+
+    /// Runs `f` on a reference to the current version, see [`Rcu::read_with`].
+    pub fn read_with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        match self.rcu() {
+            Some(rcu) => rcu.read_with(f),
+            None => f(self.default),
+        }
+    }
+
+    /// Returns the generation of the current version, which is zero for the static default, see
+    /// [`Rcu::generation`].
+    pub fn generation(&self) -> u64 {
+        self.rcu().map_or(0, Rcu::generation)
+    }
+
+    /// Writes a new version.
+    ///
+    /// # Panics
+    ///
+    /// See [`Rcu::write`].
+    pub fn write(&self, new_value: Arc<T>) {
+        if let Err(err) = self.try_write(new_value) {
+            panic!("{err}");
+        }
+    }
+
+    /// Writes a new version, see [`Rcu::try_write`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Rcu::try_write`].
+    pub fn try_write(&self, new_value: Arc<T>) -> Result<(), WriteError> {
+        if let Some(rcu) = self.rcu() {
+            return rcu.try_write(new_value);
+        }
+
+        let new_rcu = Box::into_raw(Box::new(Rcu::new(new_value)));
+        match self.rcu.compare_exchange(
+            ptr::null_mut(),
+            new_rcu,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => Ok(()),
+            Err(rcu) => {
+                // SAFETY: The new `Rcu` wasn't shared
+                let new_rcu = unsafe { Box::from_raw(new_rcu) };
+                // SAFETY: The `Rcu` is only freed when dropping `self`
+                unsafe { &*rcu }.try_write(new_rcu.read())
+            }
+        }
+    }
+
+    /// Clones the current version, runs `updater` on it and writes it, see [`Rcu::update`].
+    ///
+    /// # Panics
+    ///
+    /// See [`Rcu::update`].
+    pub fn update<F, R>(&self, updater: F)
+    where
+        T: Clone,
+        F: FnOnce(&mut T) -> R,
+    {
+        if let Some(rcu) = self.rcu() {
+            return rcu.update(updater);
+        }
+
+        let mut new_value = self.default.clone();
+        updater(&mut new_value);
+        self.write(Arc::new(new_value));
+    }
+}
+
+impl<T> Drop for CowRcu<T> {
+    fn drop(&mut self) {
+        let rcu = *self.rcu.get_mut();
+        if !rcu.is_null() {
+            // SAFETY: The `Rcu` was created by Box::into_raw and isn't shared anymore
+            drop(unsafe { Box::from_raw(rcu) });
+        }
+    }
+}
+
+/// Formats the current version.
+impl<T: fmt::Debug> fmt::Debug for CowRcu<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("CowRcu");
+        self.read_with(|data| {
+            d.field("data", data);
+        });
+        d.field("is_default", &self.is_default());
+        d.finish_non_exhaustive()
+    }
+}
+
+/// A version of a [`CowRcu`], returned by [`CowRcu::read`]
+#[derive(Debug)]
+pub enum CowVersion<T: 'static> {
+    /// The static default
+    Static(&'static T),
+    /// A written version
+    Shared(Arc<T>),
+}
+
+impl<T> Deref for CowVersion<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match self {
+            Self::Static(version) => version,
+            Self::Shared(version) => version,
+        }
+    }
+}
+
+impl<T> Clone for CowVersion<T> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Static(version) => Self::Static(version),
+            Self::Shared(version) => Self::Shared(Arc::clone(version)),
+        }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for CowVersion<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
@@ -0,0 +1,56 @@
+//! Convergent updates for conflict-free replicated data types
+
+use alloc::collections::BTreeSet;
+
+use crate::{Rcu, Reclaim};
+
+/// A conflict-free replicated data type, which can be joined with another state of itself
+///
+/// [`merge`](Self::merge) must be commutative, associative and idempotent, so concurrent updates
+/// converge regardless of the order they're merged in.
+pub trait Merge {
+    /// Joins `other` into `self`.
+    fn merge(&mut self, other: &Self);
+}
+
+/// A grow-only set
+impl<T: Ord + Clone> Merge for BTreeSet<T> {
+    fn merge(&mut self, other: &Self) {
+        self.extend(other.iter().cloned());
+    }
+}
+
+impl<T: Merge + Clone, S: Reclaim<T>> Rcu<T, S> {
+    /// Clones `T`, runs `updater` on `T` and [`write`](Self::write)s `T`, joining it with versions
+    /// written concurrently.
+    ///
+    /// Unlike [`update`](Self::update), concurrent updates are never lost.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// # use std::collections::BTreeSet;
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new(BTreeSet::from(["foo"])));
+    ///
+    /// rcu.merge_update(|set| {
+    ///     // Another writer inserts "baz" while this one inserts "bar"
+    ///     rcu.merge_update(|set| { set.insert("baz"); });
+    ///     set.insert("bar");
+    /// });
+    /// assert_eq!(*rcu.read(), BTreeSet::from(["foo", "bar", "baz"]));
+    /// ```
+    #[track_caller]
+    pub fn merge_update<F, R>(&self, updater: F)
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        self.update_merge(updater, |_base, mine, theirs| {
+            let mut value = theirs.clone();
+            value.merge(mine);
+            value
+        })
+    }
+}
@@ -0,0 +1,162 @@
+//! Folding small operations into new versions
+//!
+//! Senders push their operation to a shared list, and whichever sender finds no reducer running
+//! becomes the reducer: it folds every pending operation into one new version, until the list is
+//! empty. This way, concurrent operations are batched into a single clone of `T`.
+
+use alloc::{boxed::Box, vec::Vec};
+use core::{
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::{lock::Lock, Rcu, Reclaim, RefCount};
+
+type Fold<T, Op> = Box<dyn Fn(&mut T, &Op) + Send + Sync>;
+
+struct Shared<T, Op> {
+    pending: Lock<Vec<Op>>,
+    /// Set while a sender is folding the pending operations
+    reducing: AtomicBool,
+    fold: Fold<T, Op>,
+}
+
+impl<T: Clone, S: Reclaim<T>> Rcu<T, S> {
+    /// Returns a [`DeltaSender`] which folds the operations sent to it into new versions with
+    /// `fold`, in the order they were sent.
+    ///
+    /// Unlike [`update`](Self::update), no operations are lost, and operations which are sent
+    /// concurrently are applied to a single clone of `T`. Like
+    /// [`update_retry`](Self::update_retry), a batch of operations is folded again if another
+    /// version was written concurrently, so `fold` may be called multiple times per operation.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    ///
+    /// enum Op {
+    ///     Add(u32),
+    ///     Double,
+    /// }
+    ///
+    /// let rcu = Rcu::new(Arc::new(vec![0u32; 1024]));
+    /// let sender = rcu.reducer(|counts: &mut Vec<u32>, op: &Op| match op {
+    ///     Op::Add(n) => counts[0] += n,
+    ///     Op::Double => counts[0] *= 2,
+    /// });
+    ///
+    /// std::thread::scope(|s| {
+    ///     for _ in 0..4 {
+    ///         s.spawn(|| {
+    ///             for _ in 0..100 {
+    ///                 sender.send(Op::Add(1));
+    ///             }
+    ///         });
+    ///     }
+    /// });
+    /// sender.send(Op::Double);
+    /// assert_eq!(rcu.read()[0], 800);
+    /// ```
+    pub fn reducer<Op, F>(&self, fold: F) -> DeltaSender<'_, T, Op, S>
+    where
+        F: Fn(&mut T, &Op) + Send + Sync + 'static,
+    {
+        DeltaSender {
+            rcu: self,
+            shared: alloc::sync::Arc::new(Shared {
+                pending: Lock::new(Vec::new()),
+                reducing: AtomicBool::new(false),
+                fold: Box::new(fold),
+            }),
+        }
+    }
+}
+
+/// Sends operations to be folded into new versions of an [`Rcu`], created by [`Rcu::reducer`]
+///
+/// Clones share the same list of pending operations.
+pub struct DeltaSender<'a, T, Op, S = RefCount> {
+    rcu: &'a Rcu<T, S>,
+    shared: alloc::sync::Arc<Shared<T, Op>>,
+}
+
+impl<T: Clone, Op, S: Reclaim<T>> DeltaSender<'_, T, Op, S> {
+    /// Folds `op` into a new version.
+    ///
+    /// When this returns, `op` is either written or will be written by a concurrent sender.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `Rcu` is [frozen](Rcu::freeze) or the [validator](Rcu::with_validator)
+    /// rejects the new version. The operations being folded are dropped in that case.
+    #[track_caller]
+    pub fn send(&self, op: Op) {
+        self.shared.pending.lock().push(op);
+
+        loop {
+            if self
+                .shared
+                .reducing
+                .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+                .is_err()
+            {
+                // The running reducer will fold the operation
+                return;
+            }
+            let reducing = Reducing(&self.shared.reducing);
+
+            loop {
+                let batch = core::mem::take(&mut *self.shared.pending.lock());
+                if batch.is_empty() {
+                    break;
+                }
+                self.rcu.update_retry(|value| {
+                    for op in &batch {
+                        (self.shared.fold)(value, op);
+                    }
+                });
+            }
+
+            drop(reducing);
+            // An operation may have been pushed after the last batch, by a sender which saw this
+            // reducer running
+            if self.shared.pending.lock().is_empty() {
+                return;
+            }
+        }
+    }
+
+    /// Returns the number of operations waiting to be folded.
+    pub fn pending(&self) -> usize {
+        self.shared.pending.lock().len()
+    }
+}
+
+/// Lets another sender become the reducer when dropped, even if folding panicked
+struct Reducing<'a>(&'a AtomicBool);
+
+impl Drop for Reducing<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+impl<T, Op, S> Clone for DeltaSender<'_, T, Op, S> {
+    fn clone(&self) -> Self {
+        Self {
+            rcu: self.rcu,
+            shared: alloc::sync::Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T, Op, S> fmt::Debug for DeltaSender<'_, T, Op, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("DeltaSender");
+        d.field("pending", &self.shared.pending.lock().len());
+        d.finish_non_exhaustive()
+    }
+}
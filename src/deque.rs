@@ -0,0 +1,236 @@
+//! A ring buffer which is appended to without cloning it
+
+use alloc::{collections::VecDeque, vec::Vec};
+use core::fmt;
+
+use crate::{Arc, Rcu};
+
+/// The number of elements in each chunk of a [`DequeSnapshot`]
+const CHUNK_LEN: usize = 64;
+
+/// A double-ended queue, e.g. of recent events or rolling metrics, which is appended to at the back
+/// and consumed from the front
+///
+/// The elements are stored in chunks which are shared between versions, so pushing or popping an
+/// element only clones the chunk it's in, plus one pointer per chunk. Readers get a
+/// [`DequeSnapshot`] which doesn't change while a writer appends.
+///
+/// # Example
+///
+/// ```
+/// use axka_rcu::RcuVecDeque;
+/// let events = RcuVecDeque::new().with_max_len(3);
+///
+/// events.push_back("connected");
+/// let snapshot = events.read();
+/// events.push_back("request");
+/// events.push_back("request");
+/// events.push_back("disconnected");
+///
+/// assert_eq!(snapshot.iter().collect::<Vec<_>>(), [&"connected"]);
+/// assert_eq!(
+///     events.read().iter().collect::<Vec<_>>(),
+///     [&"request", &"request", &"disconnected"],
+/// );
+/// assert_eq!(events.pop_front(), Some("request"));
+/// assert_eq!(events.len(), 2);
+/// ```
+pub struct RcuVecDeque<T> {
+    rcu: Rcu<DequeSnapshot<T>>,
+    max_len: Option<usize>,
+}
+
+impl<T: Clone> RcuVecDeque<T> {
+    /// Creates an empty `RcuVecDeque`.
+    pub fn new() -> Self {
+        Self {
+            rcu: Rcu::new(Arc::new(DequeSnapshot::new())),
+            max_len: None,
+        }
+    }
+
+    /// Limits the number of elements, so [`push_back`](Self::push_back) drops the front elements
+    /// which don't fit anymore.
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = Some(max_len);
+        self
+    }
+
+    /// Appends an element to the back.
+    pub fn push_back(&self, value: T) {
+        self.rcu.update_retry(|deque| {
+            deque.push_back(value.clone());
+            if let Some(max_len) = self.max_len {
+                while deque.len() > max_len {
+                    deque.pop_front();
+                }
+            }
+        });
+    }
+
+    /// Removes the first element and returns it, or `None` if the deque is empty.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use axka_rcu::RcuVecDeque;
+    /// let numbers = RcuVecDeque::new();
+    /// for n in 0..200 {
+    ///     numbers.push_back(n);
+    /// }
+    /// for n in 0..150 {
+    ///     assert_eq!(numbers.pop_front(), Some(n));
+    /// }
+    ///
+    /// let snapshot = numbers.read();
+    /// assert_eq!(snapshot.front(), Some(&150));
+    /// assert_eq!(snapshot.get(10), Some(&160));
+    /// assert_eq!(snapshot.back(), Some(&199));
+    /// assert!(snapshot.iter().copied().eq(150..200));
+    /// ```
+    pub fn pop_front(&self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        self.rcu.update_retry(DequeSnapshot::pop_front)
+    }
+
+    /// Returns a snapshot of the current elements.
+    pub fn read(&self) -> Arc<DequeSnapshot<T>> {
+        self.rcu.read()
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.rcu.read_with(DequeSnapshot::len)
+    }
+
+    /// Returns `true` if the deque is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the `Rcu` holding the snapshots, e.g. for subscribing to them.
+    pub fn rcu(&self) -> &Rcu<DequeSnapshot<T>> {
+        &self.rcu
+    }
+}
+
+impl<T: Clone> Default for RcuVecDeque<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for RcuVecDeque<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("RcuVecDeque");
+        d.field("data", &self.rcu.read());
+        d.field("max_len", &self.max_len);
+        d.finish_non_exhaustive()
+    }
+}
+
+/// A version of an [`RcuVecDeque`]
+///
+/// All chunks except the last one are full, and the first one may have been consumed up to
+/// `head`.
+pub struct DequeSnapshot<T> {
+    chunks: VecDeque<Arc<Vec<T>>>,
+    /// The index of the first element in the first chunk
+    head: usize,
+    len: usize,
+}
+
+impl<T> DequeSnapshot<T> {
+    const fn new() -> Self {
+        Self {
+            chunks: VecDeque::new(),
+            head: 0,
+            len: 0,
+        }
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the element at `index`, counting from the front.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+        let index = self.head + index;
+        self.chunks[index / CHUNK_LEN].get(index % CHUNK_LEN)
+    }
+
+    /// Returns the first element.
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    /// Returns the last element.
+    pub fn back(&self) -> Option<&T> {
+        self.get(self.len.checked_sub(1)?)
+    }
+
+    /// Iterates over the elements from the front to the back.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.chunks
+            .iter()
+            .flat_map(|chunk| chunk.iter())
+            .skip(self.head)
+    }
+}
+
+impl<T: Clone> DequeSnapshot<T> {
+    fn push_back(&mut self, value: T) {
+        match self.chunks.back_mut() {
+            Some(chunk) if chunk.len() < CHUNK_LEN => Arc::make_mut(chunk).push(value),
+            _ => {
+                let mut chunk = Vec::with_capacity(CHUNK_LEN);
+                chunk.push(value);
+                self.chunks.push_back(Arc::new(chunk));
+            }
+        }
+        self.len += 1;
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        // The chunk is shared with other versions, so the element is cloned
+        let value = self.front()?.clone();
+        self.head += 1;
+        self.len -= 1;
+        if self.len == 0 {
+            self.chunks.clear();
+            self.head = 0;
+        } else if self.head == CHUNK_LEN {
+            self.chunks.pop_front();
+            self.head = 0;
+        }
+        Some(value)
+    }
+}
+
+impl<T> Clone for DequeSnapshot<T> {
+    /// Clones the pointers to the chunks, sharing the elements.
+    fn clone(&self) -> Self {
+        Self {
+            chunks: self.chunks.clone(),
+            head: self.head,
+            len: self.len,
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for DequeSnapshot<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
@@ -0,0 +1,170 @@
+//! Values derived from the current version of an [`Rcu`]
+
+use alloc::boxed::Box;
+use core::{fmt, sync::atomic::Ordering};
+
+use crate::{Arc, Rcu, Reclaim, RefCount};
+
+/// A value derived from the current version of an [`Rcu`], created by [`Rcu::derive`]
+///
+/// The value is recomputed lazily on the first read after the source has a new version.
+pub struct DerivedRcu<'a, T, U, S = RefCount> {
+    source: &'a Rcu<T, S>,
+    f: Box<dyn Fn(&T) -> U + Send + Sync + 'a>,
+    cache: Rcu<Cached<T, U>>,
+}
+
+struct Cached<T, U> {
+    /// Keeps the source version alive, so its address can't be reused by a newer version
+    source: Arc<T>,
+    value: Arc<U>,
+}
+
+impl<T, S: Reclaim<T>> Rcu<T, S> {
+    /// Creates a value derived from the current version, which stays in sync with this `Rcu`.
+    ///
+    /// `f` is only called again when the value is read after a new version has been written.
+    ///
+    /// The derived value keeps the source version it was computed from alive until it's
+    /// recomputed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new(vec![1, 2, 3]));
+    /// let sum = rcu.derive(|numbers| numbers.iter().sum::<i32>());
+    /// assert_eq!(*sum.read(), 6);
+    ///
+    /// rcu.update(|numbers| numbers.push(4));
+    /// assert_eq!(*sum.read(), 10);
+    /// ```
+    pub fn derive<'a, U, F>(&'a self, f: F) -> DerivedRcu<'a, T, U, S>
+    where
+        F: Fn(&T) -> U + Send + Sync + 'a,
+    {
+        let source = self.read();
+        let value = Arc::new(f(&source));
+
+        DerivedRcu {
+            source: self,
+            f: Box::new(f),
+            cache: Rcu::new(Arc::new(Cached { source, value })),
+        }
+    }
+}
+
+impl<T, U, S: Reclaim<T>> DerivedRcu<'_, T, U, S> {
+    /// Returns the value derived from the current version of the source.
+    ///
+    /// Concurrent readers may compute the value more than once after a new version.
+    pub fn read(&self) -> Arc<U> {
+        let cached = self.cache.read();
+        if Arc::as_ptr(&cached.source) == self.source.ptr.load(Ordering::Acquire) {
+            return Arc::clone(&cached.value);
+        }
+
+        let source = self.source.read();
+        let value = Arc::new((self.f)(&source));
+        self.cache.write(Arc::new(Cached {
+            source,
+            value: Arc::clone(&value),
+        }));
+        value
+    }
+
+    /// Runs `f` on a reference to the value derived from the current version of the source.
+    pub fn read_with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&U) -> R,
+    {
+        f(&self.read())
+    }
+}
+
+/// A cache for a value computed from the current version of an [`Rcu`]
+///
+/// Unlike [`DerivedRcu`], this isn't shared between threads and doesn't hold onto the source
+/// `Rcu`. The computed value is keyed by the source version, which is kept alive until the value
+/// is recomputed.
+///
+/// # Example
+///
+/// ```
+#[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+#[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+/// use axka_rcu::{Memo, Rcu};
+/// let rcu = Rcu::new(Arc::new("foo bar"));
+/// let mut memo = Memo::new();
+///
+/// assert_eq!(memo.get(&rcu, |s| s.to_uppercase()), "FOO BAR");
+/// // Not computed again
+/// assert_eq!(memo.get(&rcu, |_| unreachable!()), "FOO BAR");
+///
+/// rcu.write(Arc::new("baz"));
+/// assert_eq!(memo.get(&rcu, |s| s.to_uppercase()), "BAZ");
+/// ```
+pub struct Memo<T, U> {
+    cached: Option<(Arc<T>, U)>,
+}
+
+impl<T, U> Memo<T, U> {
+    /// Creates an empty `Memo`.
+    pub const fn new() -> Self {
+        Self { cached: None }
+    }
+
+    /// Returns the value computed from the current version of `rcu`.
+    ///
+    /// `f` is only called if there is a new version since the last call, or if `rcu` is a
+    /// different `Rcu` than before.
+    pub fn get<S, F>(&mut self, rcu: &Rcu<T, S>, f: F) -> &U
+    where
+        S: Reclaim<T>,
+        F: FnOnce(&T) -> U,
+    {
+        let is_current = self
+            .cached
+            .as_ref()
+            .is_some_and(|(source, _)| Arc::as_ptr(source) == rcu.ptr.load(Ordering::Acquire));
+        if !is_current {
+            self.cached = None;
+        }
+
+        let (_, value) = self.cached.get_or_insert_with(|| {
+            let source = rcu.read();
+            let value = f(&source);
+            (source, value)
+        });
+        value
+    }
+
+    /// Drops the cached value and the source version it was computed from.
+    pub fn clear(&mut self) {
+        self.cached = None;
+    }
+}
+
+impl<T, U> Default for Memo<T, U> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, U: fmt::Debug> fmt::Debug for Memo<T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("Memo");
+        d.field("data", &self.cached.as_ref().map(|(_, value)| value));
+        d.finish_non_exhaustive()
+    }
+}
+
+impl<T, U: fmt::Debug, S: Reclaim<T>> fmt::Debug for DerivedRcu<'_, T, U, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("DerivedRcu");
+        d.field("data", &self.read());
+        d.finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,91 @@
+//! Comparing versions
+
+use alloc::collections::BTreeMap;
+
+use crate::{Rcu, Reclaim};
+
+/// A type whose values can be compared, returning what changed
+pub trait Diff {
+    /// The description of what changed
+    type Diff;
+
+    /// Returns what changed from `self` to `new`.
+    fn diff(&self, new: &Self) -> Self::Diff;
+}
+
+/// The entries which changed between two maps, returned by the [`Diff`] impl of `BTreeMap`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MapDiff<K, V> {
+    /// The entries which were added
+    pub added: BTreeMap<K, V>,
+    /// The entries which were removed, with their old values
+    pub removed: BTreeMap<K, V>,
+    /// The entries whose value changed, with their new values
+    pub changed: BTreeMap<K, V>,
+}
+
+impl<K, V> MapDiff<K, V> {
+    /// Returns `true` if nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl<K: Ord + Clone, V: PartialEq + Clone> Diff for BTreeMap<K, V> {
+    type Diff = MapDiff<K, V>;
+
+    fn diff(&self, new: &Self) -> MapDiff<K, V> {
+        let mut diff = MapDiff {
+            added: BTreeMap::new(),
+            removed: BTreeMap::new(),
+            changed: BTreeMap::new(),
+        };
+        for (key, value) in new {
+            match self.get(key) {
+                None => {
+                    diff.added.insert(key.clone(), value.clone());
+                }
+                Some(old) if old != value => {
+                    diff.changed.insert(key.clone(), value.clone());
+                }
+                Some(_) => {}
+            }
+        }
+        for (key, value) in self {
+            if !new.contains_key(key) {
+                diff.removed.insert(key.clone(), value.clone());
+            }
+        }
+        diff
+    }
+}
+
+impl<T: Diff, S: Reclaim<T>> Rcu<T, S> {
+    /// Returns what changed from `old` to the current version.
+    ///
+    /// This lets change logs record which parts of a version changed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// # use std::collections::BTreeMap;
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new(BTreeMap::from([("port", 80), ("workers", 4)])));
+    ///
+    /// let old = rcu.read();
+    /// rcu.update(|config| {
+    ///     config.insert("port", 8080);
+    ///     config.insert("timeout", 30);
+    /// });
+    ///
+    /// let diff = rcu.diff_with(&old);
+    /// assert_eq!(diff.added, BTreeMap::from([("timeout", 30)]));
+    /// assert_eq!(diff.changed, BTreeMap::from([("port", 8080)]));
+    /// assert!(diff.removed.is_empty());
+    /// ```
+    pub fn diff_with(&self, old: &T) -> T::Diff {
+        self.read_with(|current| old.diff(current))
+    }
+}
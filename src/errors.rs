@@ -0,0 +1,178 @@
+//! Errors returned by the fallible methods of [`Rcu`]
+//!
+//! These don't depend on `std`, and implement [`core::error::Error`].
+
+use core::fmt;
+
+#[cfg(doc)]
+use crate::Rcu;
+
+/// The error returned by [`Rcu::try_write`] when a new version can't be written
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WriteError {
+    /// The `Rcu` is [frozen](Rcu::freeze)
+    Frozen,
+    /// The [validator](Rcu::with_validator) rejected the new version
+    Rejected,
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Frozen => f.write_str("the Rcu is frozen"),
+            Self::Rejected => f.write_str("the new version was rejected by the validator"),
+        }
+    }
+}
+
+impl core::error::Error for WriteError {}
+
+/// The error returned by [`Rcu::try_read`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RcuStateError {
+    /// The current version is known to be stale, see [`Rcu::poison`]
+    Poisoned,
+}
+
+impl fmt::Display for RcuStateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Poisoned => f.write_str("the current version of the Rcu is poisoned"),
+        }
+    }
+}
+
+impl core::error::Error for RcuStateError {}
+
+/// The error returned by [`Rcu::update_checked`] when another version was written
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Conflict {
+    /// The generation at the time of the conflict
+    pub current: u64,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "another version was written, the current generation is {}",
+            self.current
+        )
+    }
+}
+
+impl core::error::Error for Conflict {}
+
+/// The error returned by [`Rcu::transition`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransitionError<E> {
+    /// The transition function refused the transition
+    Invalid(E),
+    /// The new version couldn't be written
+    Write(WriteError),
+    /// Another version was written by a method other than `transition` or
+    /// [`update_fair`](Rcu::update_fair) while the transition function was running
+    Conflict(Conflict),
+}
+
+impl<E: fmt::Display> fmt::Display for TransitionError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid(err) => write!(f, "invalid transition: {err}"),
+            Self::Write(err) => fmt::Display::fmt(err, f),
+            Self::Conflict(err) => fmt::Display::fmt(err, f),
+        }
+    }
+}
+
+impl<E: core::error::Error + 'static> core::error::Error for TransitionError<E> {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::Invalid(err) => Some(err),
+            Self::Write(err) => Some(err),
+            Self::Conflict(err) => Some(err),
+        }
+    }
+}
+
+/// An error that happened while refreshing an `Rcu`, passed to the error callbacks of
+/// `Rcu::spawn_refresher` and `Rcu::spawn_signal_reload`
+///
+/// The current version is kept when an error happens.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RefreshError<E> {
+    /// The new version couldn't be fetched
+    Fetch(E),
+    /// The fetched version was rejected by the [validator](Rcu::with_validator)
+    Rejected,
+}
+
+impl<E: fmt::Display> fmt::Display for RefreshError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fetch(err) => write!(f, "failed to fetch a new version: {err}"),
+            Self::Rejected => f.write_str("the fetched version was rejected by the validator"),
+        }
+    }
+}
+
+impl<E: core::error::Error + 'static> core::error::Error for RefreshError<E> {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::Fetch(err) => Some(err),
+            Self::Rejected => None,
+        }
+    }
+}
+
+/// The error returned when an [`Arena`](crate::Arena) has no free slot for a new version
+///
+/// Holds the value which couldn't be written.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct ArenaFull<T>(pub T);
+
+impl<T> fmt::Debug for ArenaFull<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArenaFull").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for ArenaFull<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the arena has no free slot")
+    }
+}
+
+impl<T> core::error::Error for ArenaFull<T> {}
+
+/// The error returned by [`Rcu::try_write_alloc`] and [`Rcu::try_update_alloc`]
+#[cfg(all(feature = "allocator_api", not(feature = "triomphe")))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AllocWriteError {
+    /// The new version couldn't be allocated
+    OutOfMemory,
+    /// The new version couldn't be written
+    Write(WriteError),
+}
+
+#[cfg(all(feature = "allocator_api", not(feature = "triomphe")))]
+impl fmt::Display for AllocWriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfMemory => f.write_str("out of memory for the new version"),
+            Self::Write(err) => fmt::Display::fmt(err, f),
+        }
+    }
+}
+
+#[cfg(all(feature = "allocator_api", not(feature = "triomphe")))]
+impl core::error::Error for AllocWriteError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::OutOfMemory => None,
+            Self::Write(err) => Some(err),
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! State which most `Rcu`s never use, allocated by its first use
+//!
+//! Keeping it behind a pointer keeps `Rcu` small, so `Rcu`s which don't use it only pay for a null
+//! pointer.
+
+use alloc::{boxed::Box, vec::Vec};
+use core::{
+    ptr,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+use crate::{lock::Lock, overlap::Updates, queue::WriteQueue, Arc};
+
+pub(crate) struct Extras<T> {
+    inner: AtomicPtr<Inner<T>>,
+}
+
+pub(crate) struct Inner<T> {
+    /// Serializes [`update_fair`](crate::Rcu::update_fair) calls
+    pub(crate) queue: WriteQueue,
+    /// The generations of the [pinned](crate::Rcu::pin_current) versions
+    pub(crate) pins: Lock<Vec<u64>>,
+    /// The running `update` closures, see
+    /// [`detect_overlapping_updates`](crate::Rcu::detect_overlapping_updates)
+    pub(crate) updates: Updates,
+    /// The version prepared by [`stage`](crate::Rcu::stage)
+    pub(crate) staged: Lock<Option<Arc<T>>>,
+}
+
+impl<T> Extras<T> {
+    pub(crate) const fn new() -> Self {
+        Self {
+            inner: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    /// Returns the state, or `None` if it wasn't used yet.
+    #[inline]
+    pub(crate) fn get(&self) -> Option<&Inner<T>> {
+        // SAFETY: The state is only freed when dropping `self`
+        unsafe { self.inner.load(Ordering::Acquire).as_ref() }
+    }
+
+    /// Returns the state, allocating it if it wasn't used yet.
+    pub(crate) fn get_or_alloc(&self) -> &Inner<T> {
+        if let Some(inner) = self.get() {
+            return inner;
+        }
+
+        let new_inner = Box::into_raw(Box::new(Inner {
+            queue: WriteQueue::new(),
+            pins: Lock::new(Vec::new()),
+            updates: Updates::new(),
+            staged: Lock::new(None),
+        }));
+        match self.inner.compare_exchange(
+            ptr::null_mut(),
+            new_inner,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            // SAFETY: The state is only freed when dropping `self`
+            Ok(_) => unsafe { &*new_inner },
+            Err(inner) => {
+                // SAFETY: The new state wasn't shared
+                drop(unsafe { Box::from_raw(new_inner) });
+                // SAFETY: The state is only freed when dropping `self`
+                unsafe { &*inner }
+            }
+        }
+    }
+
+    /// Returns the state mutably, allocating it if it wasn't used yet.
+    pub(crate) fn get_mut(&mut self) -> &mut Inner<T> {
+        self.get_or_alloc();
+        // SAFETY: The state was just allocated and `self` is borrowed mutably, so it isn't
+        // accessed by anyone else
+        unsafe { &mut **self.inner.get_mut() }
+    }
+}
+
+impl<T> Drop for Extras<T> {
+    fn drop(&mut self) {
+        let inner = *self.inner.get_mut();
+        if !inner.is_null() {
+            // SAFETY: The state was created by Box::into_raw and can't be accessed anymore
+            drop(unsafe { Box::from_raw(inner) });
+        }
+    }
+}
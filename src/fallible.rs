@@ -0,0 +1,79 @@
+//! Writing versions without aborting when they can't be allocated
+
+use std::sync::Arc;
+
+use crate::{
+    errors::{AllocWriteError, WriteError},
+    Rcu, Reclaim,
+};
+
+impl<T, S: Reclaim<T>> Rcu<T, S> {
+    /// Allocates a new version for `value` and writes it, returning an error instead of aborting
+    /// if it can't be allocated.
+    ///
+    /// Writing doesn't allocate otherwise, unless a feature keeping track of versions such as
+    /// `history` or `lineage` is enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AllocWriteError::OutOfMemory`] if the version can't be allocated, and
+    /// [`AllocWriteError::Write`] if it can't be written, see [`try_write`](Self::try_write).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::sync::Arc;
+    /// use axka_rcu::{errors::AllocWriteError, Rcu};
+    /// let rcu = Rcu::new(Arc::new([0u8; 4096]));
+    ///
+    /// match rcu.try_write_alloc([1; 4096]) {
+    ///     Ok(()) => assert_eq!(rcu.read()[0], 1),
+    ///     Err(AllocWriteError::OutOfMemory) => eprintln!("keeping the old buffer"),
+    ///     Err(AllocWriteError::Write(err)) => panic!("{err}"),
+    /// }
+    /// ```
+    pub fn try_write_alloc(&self, value: T) -> Result<(), AllocWriteError> {
+        if self.is_frozen() {
+            return Err(AllocWriteError::Write(WriteError::Frozen));
+        }
+        let new_value = Arc::try_new(value).map_err(|_| AllocWriteError::OutOfMemory)?;
+        self.try_write(new_value).map_err(AllocWriteError::Write)
+    }
+
+    /// Like [`try_update`](Self::try_update), but returns an error instead of aborting if the new
+    /// version can't be allocated.
+    ///
+    /// Only the allocation of the version itself is fallible: cloning `T` and `updater` may still
+    /// allocate infallibly.
+    ///
+    /// # Errors
+    ///
+    /// See [`try_write_alloc`](Self::try_write_alloc).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::sync::Arc;
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new([0u8; 4096]));
+    ///
+    /// rcu.try_update_alloc(|buffer| buffer[0] = 1).unwrap();
+    /// assert_eq!(rcu.read()[0], 1);
+    /// ```
+    pub fn try_update_alloc<F, R>(&self, updater: F) -> Result<(), AllocWriteError>
+    where
+        T: Clone,
+        F: FnOnce(&mut T) -> R,
+    {
+        if self.is_frozen() {
+            return Err(AllocWriteError::Write(WriteError::Frozen));
+        }
+
+        let current = self.read();
+        let mut value = (*current).clone();
+        updater(&mut value);
+        let new_value = Arc::try_new(value).map_err(|_| AllocWriteError::OutOfMemory)?;
+        self.try_write_from(new_value, Some(&current))
+            .map_err(AllocWriteError::Write)
+    }
+}
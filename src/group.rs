@@ -0,0 +1,144 @@
+//! Grace periods and reclamation across many `Rcu`s
+
+use alloc::{boxed::Box, vec::Vec};
+use core::fmt;
+
+use crate::{readers::GracePeriod, Rcu, Reclaim};
+
+/// The parts of an `Rcu` which don't depend on its type
+trait Member {
+    fn start_grace_period(&self) -> Box<dyn MemberGracePeriod + '_>;
+    fn flush(&self);
+}
+
+impl<T, S: Reclaim<T>> Member for Rcu<T, S> {
+    fn start_grace_period(&self) -> Box<dyn MemberGracePeriod + '_> {
+        Box::new(Synchronizing {
+            rcu: self,
+            grace_period: self.readers.start_grace_period(),
+        })
+    }
+
+    fn flush(&self) {
+        self.flush_deferred();
+    }
+}
+
+/// The [`GracePeriod`] of a member, without its type
+trait MemberGracePeriod {
+    fn poll(&mut self) -> bool;
+    fn finish(self: Box<Self>);
+}
+
+struct Synchronizing<'a, T, S> {
+    rcu: &'a Rcu<T, S>,
+    grace_period: GracePeriod<'a, T>,
+}
+
+impl<T, S: Reclaim<T>> MemberGracePeriod for Synchronizing<'_, T, S> {
+    fn poll(&mut self) -> bool {
+        self.grace_period.poll()
+    }
+
+    fn finish(self: Box<Self>) {
+        self.grace_period.finish(&self.rcu.reclaiming());
+    }
+}
+
+/// A set of [`Rcu`]s, possibly of different types, which are synchronized and flushed together
+///
+/// # Example
+///
+/// ```
+#[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+#[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+/// use axka_rcu::{Deferred, Rcu, RcuGroup};
+/// let names = Rcu::with_reclaim(Arc::new("foo"), Deferred::new());
+/// let ports = Rcu::with_reclaim(Arc::new(80), Deferred::new());
+///
+/// let mut group = RcuGroup::new();
+/// group.register(&names).register(&ports);
+///
+/// let name = names.read();
+/// names.write(Arc::new("bar"));
+/// ports.write(Arc::new(8080));
+///
+/// group.synchronize();
+/// group.flush_deferred();
+/// assert_eq!(Arc::strong_count(&name), 1);
+/// ```
+#[derive(Default)]
+pub struct RcuGroup<'a> {
+    members: Vec<&'a dyn Member>,
+}
+
+impl<'a> RcuGroup<'a> {
+    /// Creates an empty `RcuGroup`.
+    pub const fn new() -> Self {
+        Self {
+            members: Vec::new(),
+        }
+    }
+
+    /// Adds `rcu` to the group.
+    pub fn register<T, S: Reclaim<T>>(&mut self, rcu: &'a Rcu<T, S>) -> &mut Self {
+        self.members.push(rcu);
+        self
+    }
+
+    /// Returns the number of `Rcu`s in the group.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Returns `true` if no `Rcu` has been registered.
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// [Synchronizes](Rcu::synchronize) every `Rcu` in the group.
+    ///
+    /// The readers of all `Rcu`s are waited for at once, so this takes about as long as
+    /// synchronizing the slowest one, not the sum of them.
+    ///
+    /// # Deadlocks
+    ///
+    /// See [`Rcu::synchronize`].
+    pub fn synchronize(&self) {
+        let mut grace_periods: Vec<_> = self
+            .members
+            .iter()
+            .map(|member| member.start_grace_period())
+            .collect();
+        loop {
+            // Poll every member on each round, so their readers move to the other half together
+            let mut ended = true;
+            for grace_period in &mut grace_periods {
+                ended &= grace_period.poll();
+            }
+            if ended {
+                break;
+            }
+            crate::wait();
+        }
+        for grace_period in grace_periods {
+            grace_period.finish();
+        }
+    }
+
+    /// [Flushes](Rcu::flush_deferred) the [`Reclaim`] strategy of every `Rcu` in the group, without
+    /// waiting for readers.
+    pub fn flush_deferred(&self) {
+        for member in &self.members {
+            member.flush();
+        }
+    }
+}
+
+impl fmt::Debug for RcuGroup<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("RcuGroup");
+        d.field("len", &self.len());
+        d.finish_non_exhaustive()
+    }
+}
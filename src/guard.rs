@@ -0,0 +1,333 @@
+//! Read guards handed out by [`Rcu`](crate::Rcu)
+
+use core::{
+    fmt,
+    ops::Deref,
+    panic::{RefUnwindSafe, UnwindSafe},
+    ptr::NonNull,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{Arc, Rcu, Reclaim};
+
+/// A guard to a version, returned by [`Rcu::read_guard`](crate::Rcu::read_guard)
+///
+/// Unlike an [`Arc`] returned by [`Rcu::read`](crate::Rcu::read), this doesn't touch the reference
+/// count of the version.
+///
+/// # Thread safety
+///
+/// A guard can be turned into an owning [`Arc`] with [`to_arc`](Self::to_arc), so it can only be
+/// sent to another thread if `T` is both `Send` and `Sync`, like the `Arc` itself:
+///
+/// ```compile_fail
+#[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+#[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+/// use std::{sync::Mutex, thread};
+///
+/// use axka_rcu::Rcu;
+///
+/// static MUTEX: Mutex<i32> = Mutex::new(0);
+/// let rcu = Rcu::new(Arc::new(MUTEX.lock().unwrap()));
+///
+/// let guard = rcu.read_guard();
+/// thread::scope(|s| {
+///     s.spawn(move || drop(guard));
+/// });
+/// ```
+pub struct ReadGuard<'a, T> {
+    value: NonNull<T>,
+    /// The reader counter incremented for this guard, or `None` if the `Rcu` is frozen
+    counter: Option<&'a AtomicUsize>,
+}
+
+impl<'a, T> ReadGuard<'a, T> {
+    /// # Safety
+    ///
+    /// `value` must be created by `Arc::into_raw` and loaded after `counter` was incremented.
+    #[inline]
+    pub(crate) unsafe fn new(value: *const T, counter: &'a AtomicUsize) -> Self {
+        Self {
+            // SAFETY: Arc::into_raw never returns null
+            value: unsafe { NonNull::new_unchecked(value as *mut T) },
+            counter: Some(counter),
+        }
+    }
+
+    /// Creates a guard without a reader counter.
+    ///
+    /// # Safety
+    ///
+    /// `value` must be created by `Arc::into_raw` and must not be released for `'a`, e.g. because
+    /// the `Rcu` is frozen.
+    #[inline]
+    pub(crate) unsafe fn frozen(value: *const T) -> Self {
+        Self {
+            // SAFETY: Arc::into_raw never returns null
+            value: unsafe { NonNull::new_unchecked(value as *mut T) },
+            counter: None,
+        }
+    }
+
+    /// Clones the [`Arc`] of the version.
+    ///
+    /// This is an associated function so it doesn't shadow methods of `T`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::{ReadGuard, Rcu};
+    /// let rcu = Rcu::new(Arc::new("foo"));
+    ///
+    /// let guard = rcu.read_guard();
+    /// let arc = ReadGuard::to_arc(&guard);
+    /// drop(guard);
+    /// assert_eq!(*arc, "foo");
+    /// ```
+    #[inline]
+    pub fn to_arc(this: &Self) -> Arc<T> {
+        let ptr = this.value.as_ptr();
+        #[cfg(not(feature = "triomphe"))]
+        unsafe {
+            // Increment the reference count of the inner Arc<T>
+            // SAFETY:
+            // - The ptr was created by Arc::into_raw in either Rcu::new or Rcu::write
+            // - The guard or a frozen `Rcu` keeps one strong reference alive
+            Arc::increment_strong_count(ptr);
+            // SAFETY: The ptr was created by Arc::into_raw in either Rcu::new or Rcu::write
+            Arc::from_raw(ptr)
+        }
+        #[cfg(feature = "triomphe")]
+        unsafe {
+            let arc = Arc::from_raw(ptr);
+            let _ = core::mem::ManuallyDrop::new(Arc::clone(&arc));
+            arc
+        }
+    }
+}
+
+impl<T> Deref for ReadGuard<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        // SAFETY: The version isn't released while the counter is incremented
+        unsafe { self.value.as_ref() }
+    }
+}
+
+impl<T> Drop for ReadGuard<'_, T> {
+    #[inline]
+    fn drop(&mut self) {
+        if let Some(counter) = self.counter {
+            counter.fetch_sub(1, Ordering::Release);
+        }
+    }
+}
+
+// SAFETY: The guard gives out `&T` and `Arc<T>`, and decrementing the counter is thread-safe
+unsafe impl<T: Send + Sync> Send for ReadGuard<'_, T> {}
+unsafe impl<T: Send + Sync> Sync for ReadGuard<'_, T> {}
+
+// The guard only gives out `&T`
+impl<T: RefUnwindSafe> UnwindSafe for ReadGuard<'_, T> {}
+impl<T: RefUnwindSafe> RefUnwindSafe for ReadGuard<'_, T> {}
+
+impl<T: fmt::Debug> fmt::Debug for ReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for ReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+/// A pinned reader which can move to newer versions without pinning again, returned by
+/// [`Rcu::read_session`](crate::Rcu::read_session)
+///
+/// While the session exists, versions which are replaced are kept alive, like with a
+/// [`ReadGuard`]. Prefer short sessions, e.g. one per batch of work.
+pub struct ReadSession<'a, T, S> {
+    rcu: &'a Rcu<T, S>,
+    guard: ReadGuard<'a, T>,
+}
+
+impl<'a, T, S: Reclaim<T>> ReadSession<'a, T, S> {
+    pub(crate) fn new(rcu: &'a Rcu<T, S>) -> Self {
+        Self {
+            rcu,
+            guard: rcu.read_guard(),
+        }
+    }
+
+    /// Moves the session to the current version.
+    ///
+    /// Unlike creating a new guard, this is a plain load without read-modify-write operations.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new(0));
+    /// let mut session = rcu.read_session();
+    ///
+    /// for n in 1..=3 {
+    ///     rcu.write(Arc::new(n));
+    ///     session.refresh();
+    ///     assert_eq!(*session, n);
+    /// }
+    /// ```
+    #[inline]
+    pub fn refresh(&mut self) {
+        // Frozen versions never change, so they don't need a counter
+        if self.guard.counter.is_some() {
+            let ptr = self.rcu.ptr.load(Ordering::SeqCst);
+            // SAFETY: Arc::into_raw never returns null, and the version was loaded while the
+            // counter was incremented
+            self.guard.value = unsafe { NonNull::new_unchecked(ptr) };
+        }
+    }
+
+    /// Returns `true` if the session is at the current version.
+    pub fn is_current(&self) -> bool {
+        core::ptr::eq(
+            self.guard.value.as_ptr(),
+            self.rcu.ptr.load(Ordering::Acquire),
+        )
+    }
+}
+
+impl<T, S> Deref for ReadSession<'_, T, S> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: fmt::Debug, S> fmt::Debug for ReadSession<'_, T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// A projection into a version, returned by [`Rcu::map_read`](crate::Rcu::map_read)
+///
+/// The guard keeps the whole version alive, but only exposes the projected `U`. The type of the
+/// version is erased, so consumers don't have to know the layout of the whole value.
+///
+/// The guard can't be sent to other threads, since dropping it may drop the version.
+pub struct MappedGuard<U: ?Sized> {
+    value: NonNull<U>,
+    /// The pointer of the version `Arc`, created by `Arc::into_raw`
+    version: NonNull<()>,
+    /// Decrements the reference count of `version`
+    release: unsafe fn(NonNull<()>),
+}
+
+impl<U: ?Sized> MappedGuard<U> {
+    pub(crate) fn new<T, F>(version: Arc<T>, f: F) -> Self
+    where
+        F: FnOnce(&T) -> &U,
+    {
+        match Self::try_new(version, |version| Some(f(version))) {
+            Some(this) => this,
+            None => unreachable!(),
+        }
+    }
+
+    /// Like [`new`](Self::new), but drops the version if `f` returns `None`.
+    pub(crate) fn try_new<T, F>(version: Arc<T>, f: F) -> Option<Self>
+    where
+        F: FnOnce(&T) -> Option<&U>,
+    {
+        let value = NonNull::from(f(&version)?);
+        let version = Arc::into_raw(version);
+
+        Some(Self {
+            value,
+            // SAFETY: Arc::into_raw never returns null
+            version: unsafe { NonNull::new_unchecked(version as *mut ()) },
+            release: release::<T>,
+        })
+    }
+
+    /// Projects the guard further.
+    ///
+    /// This is an associated function so it doesn't shadow methods of `U`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::{MappedGuard, Rcu};
+    /// let rcu = Rcu::new(Arc::new((1, ("foo", 2))));
+    ///
+    /// let inner = rcu.map_read(|t| &t.1);
+    /// let name = MappedGuard::map(inner, |inner| &inner.0);
+    /// assert_eq!(*name, "foo");
+    /// ```
+    pub fn map<V: ?Sized, F>(this: Self, f: F) -> MappedGuard<V>
+    where
+        F: FnOnce(&U) -> &V,
+    {
+        let this = core::mem::ManuallyDrop::new(this);
+
+        MappedGuard {
+            value: NonNull::from(f(&this)),
+            version: this.version,
+            release: this.release,
+        }
+    }
+}
+
+unsafe fn release<T>(version: NonNull<()>) {
+    // SAFETY: The pointer was created by Arc::into_raw in MappedGuard::new
+    drop(unsafe { Arc::from_raw(version.cast::<T>().as_ptr()) });
+}
+
+impl<U: ?Sized> Deref for MappedGuard<U> {
+    type Target = U;
+
+    fn deref(&self) -> &U {
+        // SAFETY: The version the value points into is kept alive by the guard
+        unsafe { self.value.as_ref() }
+    }
+}
+
+impl<U: ?Sized> Drop for MappedGuard<U> {
+    fn drop(&mut self) {
+        unsafe {
+            // SAFETY: `release` matches the type of the version
+            (self.release)(self.version);
+        }
+    }
+}
+
+// SAFETY: Sharing the guard only shares `&U`
+unsafe impl<U: ?Sized + Sync> Sync for MappedGuard<U> {}
+
+// The guard only gives out `&U`
+impl<U: ?Sized + RefUnwindSafe> UnwindSafe for MappedGuard<U> {}
+impl<U: ?Sized + RefUnwindSafe> RefUnwindSafe for MappedGuard<U> {}
+
+impl<U: ?Sized + fmt::Debug> fmt::Debug for MappedGuard<U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<U: ?Sized + fmt::Display> fmt::Display for MappedGuard<U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
@@ -0,0 +1,292 @@
+//! Keeping replaced versions around, within a retention policy
+
+use alloc::{collections::VecDeque, vec::Vec};
+use core::{fmt, mem::ManuallyDrop, time::Duration};
+use std::{sync::Arc, time::Instant};
+
+use crate::{lock::Lock, Rcu, Reclaim};
+
+/// Limits on the replaced versions kept by [`Rcu::with_history`]
+///
+/// Every limit is unset by default, so every replaced version is kept. The oldest versions are
+/// evicted on each write until all limits hold.
+///
+/// # Example
+///
+/// ```
+/// # use std::time::Duration;
+/// use axka_rcu::Retention;
+/// let retention = Retention::<Vec<u8>>::new()
+///     .max_count(100)
+///     .max_age(Duration::from_secs(60 * 60))
+///     .max_bytes(1 << 20)
+///     .size_with(|bytes| bytes.capacity());
+/// ```
+pub struct Retention<T> {
+    max_count: Option<usize>,
+    max_age: Option<Duration>,
+    max_bytes: Option<usize>,
+    size_of: fn(&T) -> usize,
+}
+
+impl<T> Retention<T> {
+    /// Creates a policy without limits.
+    pub const fn new() -> Self {
+        Self {
+            max_count: None,
+            max_age: None,
+            max_bytes: None,
+            size_of: core::mem::size_of_val::<T>,
+        }
+    }
+
+    /// Keeps at most `count` replaced versions.
+    pub const fn max_count(mut self, count: usize) -> Self {
+        self.max_count = Some(count);
+        self
+    }
+
+    /// Evicts versions which were replaced longer than `age` ago.
+    ///
+    /// Versions are evicted by writes, and expired ones are skipped by [`Rcu::history`].
+    pub const fn max_age(mut self, age: Duration) -> Self {
+        self.max_age = Some(age);
+        self
+    }
+
+    /// Keeps replaced versions whose approximate size adds up to at most `bytes`.
+    ///
+    /// The size of a version is only the size of `T` itself, unless it's estimated by
+    /// [`size_with`](Self::size_with).
+    pub const fn max_bytes(mut self, bytes: usize) -> Self {
+        self.max_bytes = Some(bytes);
+        self
+    }
+
+    /// Estimates the size of a version with `size_of`, e.g. to include its heap allocations.
+    pub const fn size_with(mut self, size_of: fn(&T) -> usize) -> Self {
+        self.size_of = size_of;
+        self
+    }
+
+    /// Returns `true` if `version` has outlived the maximum age at `now`.
+    fn is_expired(&self, version: &PastVersion<T>, now: Instant) -> bool {
+        self.max_age
+            .is_some_and(|age| now.saturating_duration_since(version.replaced_at) > age)
+    }
+}
+
+impl<T> Clone for Retention<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Retention<T> {}
+
+impl<T> Default for Retention<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for Retention<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("Retention");
+        d.field("max_count", &self.max_count);
+        d.field("max_age", &self.max_age);
+        d.field("max_bytes", &self.max_bytes);
+        d.finish_non_exhaustive()
+    }
+}
+
+/// The replaced versions of an `Rcu`, oldest first
+pub(crate) struct History<T> {
+    retention: Retention<T>,
+    inner: Lock<Inner<T>>,
+}
+
+struct Inner<T> {
+    versions: VecDeque<PastVersion<T>>,
+    /// The sum of the sizes of `versions`
+    bytes: usize,
+}
+
+impl<T> History<T> {
+    pub(crate) const fn new(retention: Retention<T>) -> Self {
+        Self {
+            retention,
+            inner: Lock::new(Inner {
+                versions: VecDeque::new(),
+                bytes: 0,
+            }),
+        }
+    }
+
+    /// Keeps the replaced version at `ptr`, and evicts the versions outside of the policy.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been created by `Arc::into_raw`, and a reference must be held during this.
+    pub(crate) unsafe fn record(&self, ptr: *const T, replaced_in: u64) {
+        // SAFETY: Guaranteed by the caller
+        let version = Arc::clone(&ManuallyDrop::new(unsafe { Arc::from_raw(ptr) }));
+        let size = (self.retention.size_of)(&version);
+        let now = Instant::now();
+
+        let evicted = {
+            let mut inner = self.inner.lock();
+            inner.bytes += size;
+            inner.versions.push_back(PastVersion {
+                version,
+                replaced_in,
+                replaced_at: now,
+                size,
+            });
+            self.evict(&mut inner, now)
+        };
+        // Dropped outside of the lock
+        drop(evicted);
+    }
+
+    /// Removes the oldest versions until all limits hold, and returns them.
+    fn evict(&self, inner: &mut Inner<T>, now: Instant) -> Vec<PastVersion<T>> {
+        let Retention {
+            max_count,
+            max_bytes,
+            ..
+        } = self.retention;
+        let mut evicted = Vec::new();
+        while let Some(oldest) = inner.versions.front() {
+            let over = max_count.is_some_and(|count| inner.versions.len() > count)
+                || max_bytes.is_some_and(|bytes| inner.bytes > bytes)
+                || self.retention.is_expired(oldest, now);
+            if !over {
+                break;
+            }
+            inner.bytes -= oldest.size;
+            evicted.extend(inner.versions.pop_front());
+        }
+        evicted
+    }
+
+    /// Drops the versions which have outlived the maximum age, and returns how many are kept.
+    pub(crate) fn evict_expired(&self) -> usize {
+        let (evicted, kept) = {
+            let mut inner = self.inner.lock();
+            let evicted = self.evict(&mut inner, Instant::now());
+            (evicted, inner.versions.len())
+        };
+        drop(evicted);
+        kept
+    }
+
+    /// Drops every kept version.
+    pub(crate) fn clear(&self) {
+        let versions = {
+            let mut inner = self.inner.lock();
+            inner.bytes = 0;
+            core::mem::take(&mut inner.versions)
+        };
+        drop(versions);
+    }
+}
+
+impl<T, S: Reclaim<T>> Rcu<T, S> {
+    /// Keeps replaced versions within `retention`, see [`history`](Self::history).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::sync::Arc;
+    /// use axka_rcu::{Rcu, Retention};
+    /// let rcu = Rcu::new(Arc::new(0)).with_history(Retention::new().max_count(2));
+    ///
+    /// for i in 1..=5 {
+    ///     rcu.write(Arc::new(i));
+    /// }
+    /// let history = rcu.history();
+    /// assert_eq!(history.len(), 2);
+    /// assert_eq!(**history[0].version(), 3);
+    /// assert_eq!(history[1].replaced_in(), 5);
+    /// ```
+    pub fn with_history(mut self, retention: Retention<T>) -> Self {
+        self.history = Some(History::new(retention));
+        self
+    }
+
+    /// Returns the replaced versions kept by [`with_history`](Self::with_history), oldest first.
+    ///
+    /// Returns an empty `Vec` if no history is kept.
+    pub fn history(&self) -> Vec<PastVersion<T>> {
+        let Some(history) = &self.history else {
+            return Vec::new();
+        };
+        let now = Instant::now();
+        let inner = history.inner.lock();
+        inner
+            .versions
+            .iter()
+            .filter(|version| !history.retention.is_expired(version, now))
+            .cloned()
+            .collect()
+    }
+
+    /// Drops the replaced versions kept by [`with_history`](Self::with_history).
+    pub fn clear_history(&self) {
+        if let Some(history) = &self.history {
+            history.clear();
+        }
+    }
+}
+
+/// A replaced version kept by an [`Rcu`], returned by [`Rcu::history`]
+pub struct PastVersion<T> {
+    version: Arc<T>,
+    replaced_in: u64,
+    replaced_at: Instant,
+    size: usize,
+}
+
+impl<T> PastVersion<T> {
+    /// Returns the version.
+    pub fn version(&self) -> &Arc<T> {
+        &self.version
+    }
+
+    /// Returns the [generation](Rcu::generation) of the version which replaced this one.
+    pub fn replaced_in(&self) -> u64 {
+        self.replaced_in
+    }
+
+    /// Returns when the version was replaced.
+    pub fn replaced_at(&self) -> Instant {
+        self.replaced_at
+    }
+
+    /// Returns the approximate size of the version, see [`Retention::max_bytes`].
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+impl<T> Clone for PastVersion<T> {
+    fn clone(&self) -> Self {
+        Self {
+            version: Arc::clone(&self.version),
+            replaced_in: self.replaced_in,
+            replaced_at: self.replaced_at,
+            size: self.size,
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for PastVersion<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("PastVersion");
+        d.field("version", &self.version);
+        d.field("replaced_in", &self.replaced_in);
+        d.field("replaced_at", &self.replaced_at);
+        d.finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,383 @@
+//! Callbacks run when a new version is written, and the [`RcuHooks`] of an `Rcu`
+//!
+//! The hooks are allocated by the first registration, so writing to an `Rcu` without hooks only
+//! costs a null check.
+
+use alloc::{boxed::Box, vec::Vec};
+use core::{
+    fmt, ptr,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+#[cfg(feature = "atomic-waker")]
+use atomic_waker::AtomicWaker;
+
+use crate::{lock::Lock, Arc, Rcu, Reclaim};
+
+/// Called with each new version. Returning `false` unregisters the callback.
+type Callback<T> = alloc::sync::Arc<dyn Fn(&Arc<T>) -> bool + Send + Sync>;
+/// Replaced instead of modified, so writers can call the callbacks without holding the lock
+type Callbacks<T> = alloc::sync::Arc<Vec<(usize, Callback<T>)>>;
+/// Returns `false` for versions which must not be written
+type Validator<T> = Box<dyn Fn(&T) -> bool + Send + Sync>;
+
+/// Instrumentation callbacks for the reads, writes and reclamations of an [`Rcu`], installed with
+/// [`Rcu::with_hooks`]
+///
+/// This is an extension point for profiling, auditing and custom metrics. Every method does
+/// nothing by default. The callbacks run on the thread doing the operation, so they should be
+/// cheap.
+///
+/// # Example
+///
+/// ```
+#[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+#[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+/// # use std::sync::atomic::{AtomicUsize, Ordering};
+/// use axka_rcu::{Rcu, RcuHooks};
+///
+/// #[derive(Clone, Default)]
+/// struct Metrics {
+///     reads: Arc<AtomicUsize>,
+///     reclaimed: Arc<AtomicUsize>,
+/// }
+///
+/// impl<T> RcuHooks<T> for Metrics {
+///     fn on_read(&self, _version: &T) {
+///         self.reads.fetch_add(1, Ordering::Relaxed);
+///     }
+///
+///     fn on_reclaim(&self, _version: &Arc<T>) {
+///         self.reclaimed.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let metrics = Metrics::default();
+/// let rcu = Rcu::new(Arc::new("foo")).with_hooks(metrics.clone());
+///
+/// assert_eq!(*rcu.read(), "foo");
+/// rcu.write(Arc::new("bar"));
+/// assert_eq!(metrics.reads.load(Ordering::Relaxed), 1);
+/// assert_eq!(metrics.reclaimed.load(Ordering::Relaxed), 1);
+/// ```
+pub trait RcuHooks<T>: Send + Sync {
+    /// Called by every read, including [`read_guard`](Rcu::read_guard) and the methods based on
+    /// it, with the version being read.
+    fn on_read(&self, version: &T) {
+        let _ = version;
+    }
+
+    /// Called with every version after it's published, like the callbacks of
+    /// [`Rcu::on_write`].
+    fn on_write(&self, version: &Arc<T>) {
+        let _ = version;
+    }
+
+    /// Called with every replaced version when it's handed to the [`Reclaim`] strategy, i.e. once
+    /// no [`ReadGuard`](crate::ReadGuard) can reach it anymore.
+    fn on_reclaim(&self, version: &Arc<T>) {
+        let _ = version;
+    }
+}
+
+pub(crate) struct Hooks<T> {
+    inner: AtomicPtr<Inner<T>>,
+}
+
+struct Inner<T> {
+    callbacks: Lock<Callbacks<T>>,
+    next_id: Lock<usize>,
+    /// Only set while the `Rcu` is borrowed mutably
+    validator: Option<Validator<T>>,
+    /// Only set while the `Rcu` is borrowed mutably
+    custom: Option<Box<dyn RcuHooks<T>>>,
+    /// Woken by every write
+    #[cfg(feature = "atomic-waker")]
+    waker: AtomicWaker,
+}
+
+impl<T> Hooks<T> {
+    pub(crate) const fn new() -> Self {
+        Self {
+            inner: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    /// Returns `true` if writers should pass the new version to [`notify`](Self::notify).
+    #[inline]
+    pub(crate) fn is_active(&self) -> bool {
+        !self.inner.load(Ordering::Acquire).is_null()
+    }
+
+    fn inner(&self) -> &Inner<T> {
+        let inner = self.inner.load(Ordering::SeqCst);
+        if !inner.is_null() {
+            // SAFETY: The hooks are only freed when dropping `self`
+            return unsafe { &*inner };
+        }
+
+        let new_inner = Box::into_raw(Box::new(Inner {
+            callbacks: Lock::new(alloc::sync::Arc::new(Vec::new())),
+            next_id: Lock::new(0),
+            validator: None,
+            custom: None,
+            #[cfg(feature = "atomic-waker")]
+            waker: AtomicWaker::new(),
+        }));
+        match self.inner.compare_exchange(
+            ptr::null_mut(),
+            new_inner,
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        ) {
+            // SAFETY: The hooks are only freed when dropping `self`
+            Ok(_) => unsafe { &*new_inner },
+            Err(inner) => {
+                // SAFETY: The new hooks weren't shared
+                drop(unsafe { Box::from_raw(new_inner) });
+                // SAFETY: The hooks are only freed when dropping `self`
+                unsafe { &*inner }
+            }
+        }
+    }
+
+    /// Registers a callback for versions written after this, returning its ID.
+    pub(crate) fn add<F>(&self, f: F) -> usize
+    where
+        F: Fn(&Arc<T>) -> bool + Send + Sync + 'static,
+    {
+        let inner = self.inner();
+        let id = {
+            let mut next_id = inner.next_id.lock();
+            *next_id += 1;
+            *next_id
+        };
+
+        let callback: Callback<T> = alloc::sync::Arc::new(f);
+        let mut callbacks = inner.callbacks.lock();
+        let mut new_callbacks = Vec::clone(&callbacks);
+        new_callbacks.push((id, callback));
+        *callbacks = alloc::sync::Arc::new(new_callbacks);
+        id
+    }
+
+    /// Returns the waker woken by every write.
+    ///
+    /// Writers which publish a version after this is called are guaranteed to wake it.
+    #[cfg(feature = "atomic-waker")]
+    pub(crate) fn waker(&self) -> &AtomicWaker {
+        &self.inner().waker
+    }
+
+    pub(crate) fn set_validator<F>(&mut self, validator: F)
+    where
+        F: Fn(&T) -> bool + Send + Sync + 'static,
+    {
+        self.inner();
+        // SAFETY: The hooks were just allocated and `self` is borrowed mutably, so they aren't
+        // accessed by anyone else
+        let inner = unsafe { &mut **self.inner.get_mut() };
+        inner.validator = Some(Box::new(validator));
+    }
+
+    pub(crate) fn set_custom<H>(&mut self, hooks: H)
+    where
+        H: RcuHooks<T> + 'static,
+    {
+        self.inner();
+        // SAFETY: See `set_validator`
+        let inner = unsafe { &mut **self.inner.get_mut() };
+        inner.custom = Some(Box::new(hooks));
+    }
+
+    /// Runs [`RcuHooks::on_read`].
+    #[inline]
+    pub(crate) fn read(&self, version: &T) {
+        let inner = self.inner.load(Ordering::Acquire);
+        if inner.is_null() {
+            return;
+        }
+        // SAFETY: The hooks are only freed when dropping `self`
+        Self::read_slow(unsafe { &*inner }, version);
+    }
+
+    /// Kept out of line, so reads from `Rcu`s without hooks stay small
+    #[cold]
+    #[inline(never)]
+    fn read_slow(inner: &Inner<T>, version: &T) {
+        if let Some(custom) = &inner.custom {
+            custom.on_read(version);
+        }
+    }
+
+    /// Returns `false` if the validator rejects `value`.
+    #[inline]
+    pub(crate) fn validate(&self, value: &T) -> bool {
+        let inner = self.inner.load(Ordering::Acquire);
+        if inner.is_null() {
+            return true;
+        }
+        // SAFETY: The hooks are only freed when dropping `self`
+        Self::validate_slow(unsafe { &*inner }, value)
+    }
+
+    /// Kept out of line, so writes to `Rcu`s without hooks stay small
+    #[cold]
+    #[inline(never)]
+    fn validate_slow(inner: &Inner<T>, value: &T) -> bool {
+        inner
+            .validator
+            .as_ref()
+            .is_none_or(|validator| validator(value))
+    }
+
+    /// Unregisters the callback with the given ID.
+    pub(crate) fn remove(&self, id: usize) {
+        let inner = self.inner.load(Ordering::Acquire);
+        if inner.is_null() {
+            return;
+        }
+        // SAFETY: The hooks are only freed when dropping `self`
+        let inner = unsafe { &*inner };
+
+        let mut callbacks = inner.callbacks.lock();
+        if callbacks.iter().any(|(other, _)| *other == id) {
+            let mut new_callbacks = Vec::clone(&callbacks);
+            new_callbacks.retain(|(other, _)| *other != id);
+            *callbacks = alloc::sync::Arc::new(new_callbacks);
+        }
+    }
+
+    /// Runs the hooks after a version was published with a `SeqCst` operation.
+    ///
+    /// `version` is `None` if the hooks weren't [active](Self::is_active) before publishing, in
+    /// which case only the waker is woken.
+    #[inline]
+    pub(crate) fn notify(&self, version: Option<&Arc<T>>) {
+        // Pairs with registering the waker, which happens after allocating the hooks
+        let inner = self.inner.load(Ordering::SeqCst);
+        if inner.is_null() {
+            return;
+        }
+        // SAFETY: The hooks are only freed when dropping `self`
+        self.notify_slow(unsafe { &*inner }, version);
+    }
+
+    /// Kept out of line, so writes to `Rcu`s without hooks stay small
+    #[cold]
+    #[inline(never)]
+    fn notify_slow(&self, inner: &Inner<T>, version: Option<&Arc<T>>) {
+        #[cfg(feature = "atomic-waker")]
+        inner.waker.wake();
+
+        let Some(version) = version else {
+            return;
+        };
+        if let Some(custom) = &inner.custom {
+            custom.on_write(version);
+        }
+        let callbacks = alloc::sync::Arc::clone(&inner.callbacks.lock());
+        for (id, callback) in callbacks.iter() {
+            if !callback(version) {
+                self.remove(*id);
+            }
+        }
+    }
+}
+
+/// Runs [`RcuHooks::on_reclaim`] before handing versions to the strategy
+pub(crate) struct Reclaiming<'a, T, S> {
+    pub(crate) hooks: &'a Hooks<T>,
+    pub(crate) strategy: &'a S,
+}
+
+impl<T, S: Reclaim<T>> Reclaim<T> for Reclaiming<'_, T, S> {
+    #[inline]
+    fn reclaim(&self, version: Arc<T>) {
+        let inner = self.hooks.inner.load(Ordering::Acquire);
+        // SAFETY: The hooks are only freed when dropping them
+        if let Some(custom) = unsafe { inner.as_ref() }.and_then(|inner| inner.custom.as_ref()) {
+            custom.on_reclaim(&version);
+        }
+        self.strategy.reclaim(version);
+    }
+
+    fn flush(&self) {
+        self.strategy.flush();
+    }
+}
+
+impl<T> Drop for Hooks<T> {
+    fn drop(&mut self) {
+        let inner = *self.inner.get_mut();
+        if !inner.is_null() {
+            // SAFETY: The hooks were created by Box::into_raw and can't be accessed anymore
+            drop(unsafe { Box::from_raw(inner) });
+        }
+    }
+}
+
+impl<T, S: Reclaim<T>> Rcu<T, S> {
+    /// Registers a callback which is called with every version written after this.
+    ///
+    /// The callback is called synchronously by the writer after the version is published, so it
+    /// observes writes from one thread in order. Writes from different threads may be observed in
+    /// any order. The callback is unregistered when the returned handle is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// # use std::sync::Mutex;
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new("foo"));
+    /// let log = Arc::new(Mutex::new(Vec::new()));
+    ///
+    /// let log2 = log.clone();
+    /// let handle = rcu.on_write(move |version| log2.lock().unwrap().push(**version));
+    /// rcu.write(Arc::new("bar"));
+    /// drop(handle);
+    /// rcu.write(Arc::new("baz"));
+    ///
+    /// assert_eq!(*log.lock().unwrap(), ["bar"]);
+    /// ```
+    pub fn on_write<F>(&self, callback: F) -> SubscriptionHandle<'_, T, S>
+    where
+        F: Fn(&Arc<T>) + Send + Sync + 'static,
+    {
+        let id = self.hooks.add(move |version| {
+            callback(version);
+            true
+        });
+        SubscriptionHandle { rcu: self, id }
+    }
+}
+
+/// Unregisters a callback registered with [`Rcu::on_write`] when dropped
+#[must_use = "the callback is unregistered when the handle is dropped"]
+pub struct SubscriptionHandle<'a, T, S> {
+    rcu: &'a Rcu<T, S>,
+    id: usize,
+}
+
+impl<T, S> SubscriptionHandle<'_, T, S> {
+    /// Keeps the callback registered for as long as the `Rcu` exists.
+    pub fn detach(self) {
+        core::mem::forget(self);
+    }
+}
+
+impl<T, S> Drop for SubscriptionHandle<'_, T, S> {
+    fn drop(&mut self) {
+        self.rcu.hooks.remove(self.id);
+    }
+}
+
+impl<T, S> fmt::Debug for SubscriptionHandle<'_, T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("SubscriptionHandle");
+        d.field("id", &self.id);
+        d.finish_non_exhaustive()
+    }
+}
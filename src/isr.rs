@@ -0,0 +1,138 @@
+//! An `Rcu` which can be read from interrupt handlers
+
+use core::fmt;
+
+use crate::{errors::WriteError, Arc, Rcu, ReadGuard, Reclaim, RefCount};
+
+/// An [`Rcu`] whose reads are safe in interrupt handlers, e.g. on single-core microcontrollers
+///
+/// [`read`](Self::read) never allocates, frees memory, takes a lock, waits or touches a reference
+/// count. It only increments and decrements a reader counter. Hence a write in thread context
+/// which is interrupted can't deadlock a reader in the interrupt handler, and the handler never
+/// drops a version.
+///
+/// Writes may allocate and drop replaced versions, so they must only happen in thread context.
+/// The methods of `Rcu` which could break this, like installing [hooks](crate::RcuHooks) which
+/// run on reads, aren't available.
+///
+/// # Example
+///
+/// ```
+#[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+#[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+/// use axka_rcu::IsrRcu;
+///
+/// struct Calibration {
+///     offset: i16,
+/// }
+///
+/// let calibration = IsrRcu::new(Arc::new(Calibration { offset: 0 }));
+///
+/// // In the ADC interrupt handler
+/// let on_sample = |raw: i16| raw + calibration.read().offset;
+///
+/// // In thread context
+/// calibration.write(Arc::new(Calibration { offset: -3 }));
+/// assert_eq!(on_sample(100), 97);
+/// ```
+pub struct IsrRcu<T, S = RefCount> {
+    rcu: Rcu<T, S>,
+}
+
+impl<T> IsrRcu<T> {
+    /// Creates a new `IsrRcu` containing the given value.
+    pub fn new(value: Arc<T>) -> Self {
+        Self::from_rcu(Rcu::new(value))
+    }
+}
+
+impl<T, S: Reclaim<T>> IsrRcu<T, S> {
+    /// Wraps `rcu`, e.g. one created with [`Rcu::with_reclaim`].
+    ///
+    /// Hooks installed on `rcu` before this call are kept, so they must be safe in interrupt
+    /// handlers too.
+    pub fn from_rcu(rcu: Rcu<T, S>) -> Self {
+        // Allocated up front, since the first read would allocate it otherwise
+        rcu.readers.prepare();
+        Self { rcu }
+    }
+
+    /// Returns a guard to the current version.
+    ///
+    /// This is safe in interrupt handlers, see [`IsrRcu`].
+    #[inline]
+    pub fn read(&self) -> ReadGuard<'_, T> {
+        self.rcu.read_guard()
+    }
+
+    /// Runs `f` on a reference to the current version.
+    ///
+    /// This is safe in interrupt handlers, see [`IsrRcu`].
+    #[inline]
+    pub fn read_with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        f(&self.read())
+    }
+
+    /// Returns the generation of the current version, see [`Rcu::generation`].
+    ///
+    /// This is safe in interrupt handlers.
+    #[inline]
+    pub fn generation(&self) -> u64 {
+        self.rcu.generation()
+    }
+
+    /// Writes a new version.
+    ///
+    /// This must not be called from an interrupt handler.
+    ///
+    /// # Panics
+    ///
+    /// See [`Rcu::write`].
+    pub fn write(&self, new_value: Arc<T>) {
+        self.rcu.write(new_value);
+    }
+
+    /// Writes a new version, see [`Rcu::try_write`].
+    ///
+    /// This must not be called from an interrupt handler.
+    ///
+    /// # Errors
+    ///
+    /// See [`Rcu::try_write`].
+    pub fn try_write(&self, new_value: Arc<T>) -> Result<(), WriteError> {
+        self.rcu.try_write(new_value)
+    }
+
+    /// Clones the current version, runs `updater` on it and writes it, see
+    /// [`Rcu::update_retry`].
+    ///
+    /// This must not be called from an interrupt handler.
+    ///
+    /// # Panics
+    ///
+    /// See [`Rcu::update_retry`].
+    pub fn update<F, R>(&self, updater: F) -> R
+    where
+        T: Clone,
+        F: FnMut(&mut T) -> R,
+    {
+        self.rcu.update_retry(updater)
+    }
+
+    /// Returns the wrapped `Rcu`.
+    pub fn into_rcu(self) -> Rcu<T, S> {
+        self.rcu
+    }
+}
+
+/// Formats the current version.
+impl<T: fmt::Debug, S: Reclaim<T>> fmt::Debug for IsrRcu<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("IsrRcu");
+        d.field("data", &&*self.read());
+        d.finish_non_exhaustive()
+    }
+}
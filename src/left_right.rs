@@ -0,0 +1,200 @@
+//! Two copies of a value, which writers modify in place by replaying an operation log
+//!
+//! Readers use the active copy, while the writer applies operations to the standby copy and then
+//! swaps the copies. The operations are then kept in a log, and replayed on the new standby copy
+//! before the next swap, once its readers are gone.
+
+use alloc::{boxed::Box, vec::Vec};
+use core::{
+    cell::UnsafeCell,
+    fmt,
+    ops::Deref,
+    ptr::NonNull,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::lock::Lock;
+
+type Apply<T, Op> = Box<dyn Fn(&mut T, &Op) + Send + Sync>;
+
+/// A value which is modified in place instead of being cloned, by keeping two copies of it
+///
+/// This is an alternative to [`Rcu`](crate::Rcu) inspired by the left-right pattern: readers never
+/// block and never clone, and writes only apply small operations to the copies. For a large `T`
+/// with frequent small modifications, this is much cheaper than cloning `T` for every write. The
+/// cost is twice the memory, and that every operation is applied twice.
+///
+/// Operations are [appended](Self::append) to a log and become visible to readers when they're
+/// [published](Self::publish). Publishing waits for the readers which still use the standby copy
+/// from before the previous publish.
+///
+/// # Example
+///
+/// ```
+/// use axka_rcu::LeftRight;
+///
+/// enum Op {
+///     Push(u32),
+///     Clear,
+/// }
+///
+/// let list = LeftRight::new(Vec::new(), |list: &mut Vec<u32>, op: &Op| match op {
+///     Op::Push(n) => list.push(*n),
+///     Op::Clear => list.clear(),
+/// });
+///
+/// let guard = list.read();
+/// list.append(Op::Push(1));
+/// list.append(Op::Push(2));
+/// list.publish();
+/// assert!(guard.is_empty());
+/// drop(guard);
+///
+/// assert_eq!(*list.read(), [1, 2]);
+///
+/// list.write(Op::Clear);
+/// assert!(list.read().is_empty());
+/// ```
+pub struct LeftRight<T, Op> {
+    copies: [UnsafeCell<T>; 2],
+    /// The index of the copy used by new readers
+    active: AtomicUsize,
+    /// The number of readers of each copy
+    readers: [AtomicUsize; 2],
+    /// Operations which weren't applied to either copy yet
+    pending: Lock<Vec<Op>>,
+    /// Operations which were only applied to the active copy, locked while publishing
+    stale: Lock<Vec<Op>>,
+    apply: Apply<T, Op>,
+}
+
+// SAFETY: The copies are only modified by the writer while they have no readers
+unsafe impl<T: Send, Op: Send> Send for LeftRight<T, Op> {}
+unsafe impl<T: Send + Sync, Op: Send> Sync for LeftRight<T, Op> {}
+
+impl<T: Clone, Op> LeftRight<T, Op> {
+    /// Creates a new `LeftRight` containing `value`, which applies operations with `apply`.
+    ///
+    /// `apply` must modify both copies the same way, i.e. it must be deterministic. If it panics,
+    /// the copies may no longer be the same.
+    pub fn new<F>(value: T, apply: F) -> Self
+    where
+        F: Fn(&mut T, &Op) + Send + Sync + 'static,
+    {
+        Self {
+            copies: [UnsafeCell::new(value.clone()), UnsafeCell::new(value)],
+            active: AtomicUsize::new(0),
+            readers: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            pending: Lock::new(Vec::new()),
+            stale: Lock::new(Vec::new()),
+            apply: Box::new(apply),
+        }
+    }
+}
+
+impl<T, Op> LeftRight<T, Op> {
+    /// Returns a guard to the active copy.
+    ///
+    /// This never blocks, but publishing blocks while a guard from before the previous publish is
+    /// held.
+    pub fn read(&self) -> LeftRightGuard<'_, T> {
+        loop {
+            let active = self.active.load(Ordering::SeqCst);
+            let readers = &self.readers[active];
+            readers.fetch_add(1, Ordering::SeqCst);
+            // The writer may have swapped the copies and started modifying this one before the
+            // reader was counted
+            if self.active.load(Ordering::SeqCst) == active {
+                return LeftRightGuard {
+                    // SAFETY: UnsafeCell::get never returns null
+                    value: unsafe { NonNull::new_unchecked(self.copies[active].get()) },
+                    readers,
+                };
+            }
+            readers.fetch_sub(1, Ordering::Release);
+        }
+    }
+
+    /// Adds `op` to the log, without making it visible to readers.
+    pub fn append(&self, op: Op) {
+        self.pending.lock().push(op);
+    }
+
+    /// Makes the operations appended before this call visible to readers.
+    ///
+    /// # Deadlocks
+    ///
+    /// This blocks while a guard which was created before the previous publish exists, so it
+    /// deadlocks if the current thread holds one.
+    pub fn publish(&self) {
+        let mut stale = self.stale.lock();
+        let pending = core::mem::take(&mut *self.pending.lock());
+        if pending.is_empty() {
+            return;
+        }
+
+        let standby = 1 - self.active.load(Ordering::SeqCst);
+        while self.readers[standby].load(Ordering::SeqCst) != 0 {
+            crate::wait();
+        }
+
+        // SAFETY: The standby copy has no readers, and new readers back off until it's active
+        let copy = unsafe { &mut *self.copies[standby].get() };
+        for op in stale.iter().chain(&pending) {
+            (self.apply)(copy, op);
+        }
+
+        self.active.store(standby, Ordering::SeqCst);
+        *stale = pending;
+    }
+
+    /// [Appends](Self::append) `op` and [publishes](Self::publish) it.
+    ///
+    /// # Deadlocks
+    ///
+    /// See [`publish`](Self::publish).
+    pub fn write(&self, op: Op) {
+        self.append(op);
+        self.publish();
+    }
+}
+
+impl<T: fmt::Debug, Op> fmt::Debug for LeftRight<T, Op> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("LeftRight");
+        d.field("data", &*self.read());
+        d.finish_non_exhaustive()
+    }
+}
+
+/// A guard to the active copy of a [`LeftRight`], returned by [`LeftRight::read`]
+pub struct LeftRightGuard<'a, T> {
+    value: NonNull<T>,
+    /// The reader counter of the copy
+    readers: &'a AtomicUsize,
+}
+
+impl<T> Deref for LeftRightGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: The writer doesn't modify the copy while it has readers
+        unsafe { self.value.as_ref() }
+    }
+}
+
+impl<T> Drop for LeftRightGuard<'_, T> {
+    fn drop(&mut self) {
+        self.readers.fetch_sub(1, Ordering::Release);
+    }
+}
+
+// SAFETY: The guard only gives out `&T`, and decrementing the counter is thread-safe
+unsafe impl<T: Sync> Send for LeftRightGuard<'_, T> {}
+unsafe impl<T: Sync> Sync for LeftRightGuard<'_, T> {}
+
+impl<T: fmt::Debug> fmt::Debug for LeftRightGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
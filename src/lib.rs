@@ -45,10 +45,18 @@
 //! ## Feature flags
 #![doc = document_features::document_features!()]
 #![cfg_attr(all(feature = "triomphe", not(test)), no_std)]
+#![cfg_attr(
+    all(feature = "allocator_api", not(feature = "triomphe")),
+    feature(allocator_api)
+)]
+
+extern crate alloc;
 
 use core::{
     fmt,
-    sync::atomic::{AtomicPtr, Ordering},
+    mem::ManuallyDrop,
+    panic::{RefUnwindSafe, UnwindSafe},
+    sync::atomic::{AtomicPtr, AtomicUsize, Ordering},
 };
 
 // Pick the correct Arc
@@ -58,8 +66,223 @@ use std::sync::Arc;
 use triomphe::Arc;
 
 // Re-export the library
+#[cfg(all(feature = "serde_json", not(feature = "triomphe")))]
+pub use json_patch;
+#[cfg(feature = "rkyv")]
+pub use rkyv;
+#[cfg(all(feature = "rope", not(feature = "triomphe")))]
+pub use ropey;
+#[cfg(feature = "rtic")]
+pub use rtic_core;
+#[cfg(all(feature = "signal", unix, not(feature = "triomphe")))]
+pub use signal_hook;
+#[cfg(feature = "tower")]
+pub use tower_service;
 #[cfg(feature = "triomphe")]
 pub use triomphe;
+#[cfg(feature = "yoke")]
+pub use yoke;
+#[cfg(feature = "zeroize")]
+pub use zeroize;
+
+#[cfg(not(feature = "triomphe"))]
+mod adaptive;
+#[cfg(not(feature = "triomphe"))]
+mod any;
+#[cfg(feature = "rkyv")]
+mod archived;
+mod arena;
+#[cfg(all(feature = "audit", not(feature = "triomphe")))]
+mod audit;
+#[cfg(all(feature = "bench", not(feature = "triomphe")))]
+pub mod bench;
+#[cfg(feature = "bevy")]
+mod bevy;
+#[cfg(feature = "borsh")]
+mod borsh;
+#[cfg(feature = "tokio")]
+mod broadcast;
+mod callback;
+mod chain;
+#[cfg(feature = "atomic-waker")]
+mod changed;
+mod collections;
+mod cow;
+#[cfg(feature = "crdt")]
+mod crdt;
+mod delta;
+mod deque;
+mod derived;
+#[cfg(feature = "diff")]
+mod diff;
+pub mod errors;
+mod extras;
+#[cfg(all(feature = "allocator_api", not(feature = "triomphe")))]
+mod fallible;
+mod group;
+mod guard;
+#[cfg(all(feature = "history", not(feature = "triomphe")))]
+mod history;
+mod hooks;
+mod isr;
+mod left_right;
+#[cfg(all(feature = "lineage", not(feature = "triomphe")))]
+mod lineage;
+mod lock;
+#[cfg(all(feature = "debug-meta", not(feature = "triomphe")))]
+mod meta;
+#[cfg(feature = "test-util")]
+mod mock;
+#[cfg(feature = "tokio")]
+mod offload;
+mod overlap;
+#[cfg(not(feature = "triomphe"))]
+mod overlay;
+#[cfg(all(feature = "serde_json", not(feature = "triomphe")))]
+mod patch;
+#[cfg(all(feature = "serde_json", not(feature = "triomphe")))]
+mod persist;
+mod pin;
+mod queue;
+mod rcu_like;
+mod readers;
+mod reclaim;
+#[cfg(all(feature = "tokio", not(feature = "triomphe")))]
+mod refresher;
+#[cfg(not(feature = "triomphe"))]
+mod refreshing;
+#[cfg(all(feature = "registry", not(feature = "triomphe")))]
+pub mod registry;
+#[cfg(all(feature = "notify", not(feature = "triomphe")))]
+mod reload;
+#[cfg(all(feature = "serde_json", not(feature = "triomphe")))]
+mod replicate;
+#[cfg(all(feature = "rope", not(feature = "triomphe")))]
+mod rope;
+#[cfg(all(feature = "rt", not(feature = "triomphe")))]
+mod rt;
+#[cfg(feature = "rtic")]
+mod rtic;
+#[cfg(all(feature = "safe-fallback", not(feature = "triomphe")))]
+mod safe;
+#[cfg(feature = "serde")]
+mod serde;
+#[cfg(feature = "serde")]
+pub mod serde_arc;
+#[cfg(feature = "serde")]
+pub mod serde_versioned;
+#[cfg(feature = "lock_api")]
+mod serialized;
+#[cfg(not(feature = "triomphe"))]
+mod services;
+#[cfg(all(feature = "shm", unix, not(feature = "triomphe")))]
+mod shm;
+#[cfg(all(feature = "signal", unix, not(feature = "triomphe")))]
+mod signal;
+#[cfg(all(feature = "futures-signals", not(feature = "triomphe")))]
+mod signals;
+#[cfg(feature = "futures")]
+mod sink;
+mod staged;
+#[cfg(feature = "stats")]
+mod stats;
+mod token;
+#[cfg(feature = "tower")]
+mod tower;
+#[cfg(kani)]
+mod verification;
+#[cfg(feature = "zeroize")]
+mod zeroized;
+
+#[cfg(not(feature = "triomphe"))]
+pub use adaptive::{Adaptive, AdaptiveGuard};
+#[cfg(feature = "rkyv")]
+pub use archived::{ArchiveBuffer, ArchivedBytes};
+pub use arena::{Arena, ArenaGuard, ArenaRcu};
+#[cfg(all(feature = "audit", not(feature = "triomphe")))]
+pub use audit::{AuditPrincipal, AuditRecord};
+#[cfg(feature = "bevy")]
+pub use bevy::{RcuPlugin, RcuRes};
+#[cfg(feature = "tokio")]
+pub use broadcast::Broadcast;
+pub use callback::RcuFn;
+pub use chain::{ChainSnapshot, RcuChain};
+#[cfg(feature = "atomic-waker")]
+pub use changed::{Changed, WaitFor};
+#[cfg(not(feature = "triomphe"))]
+pub use collections::RcuMapExt;
+pub use collections::{RcuVecExt, SliceEditor};
+pub use cow::{CowRcu, CowVersion};
+#[cfg(feature = "crdt")]
+pub use crdt::Merge;
+pub use delta::DeltaSender;
+pub use deque::{DequeSnapshot, RcuVecDeque};
+pub use derived::{DerivedRcu, Memo};
+#[cfg(feature = "diff")]
+pub use diff::{Diff, MapDiff};
+use errors::{Conflict, RcuStateError, WriteError};
+pub use group::RcuGroup;
+pub use guard::{MappedGuard, ReadGuard, ReadSession};
+#[cfg(all(feature = "history", not(feature = "triomphe")))]
+pub use history::{PastVersion, Retention};
+pub use hooks::{RcuHooks, SubscriptionHandle};
+pub use isr::IsrRcu;
+pub use left_right::{LeftRight, LeftRightGuard};
+#[cfg(all(feature = "lineage", not(feature = "triomphe")))]
+pub use lineage::{Lineage, LineageVersion};
+#[cfg(all(feature = "debug-meta", not(feature = "triomphe")))]
+pub use meta::VersionMeta;
+#[cfg(feature = "test-util")]
+pub use mock::MockRcu;
+#[cfg(feature = "tokio")]
+pub use offload::SpawnBlocking;
+pub use overlap::OnOverlap;
+#[cfg(not(feature = "triomphe"))]
+pub use overlay::OverlayMap;
+#[cfg(all(feature = "serde_json", not(feature = "triomphe")))]
+pub use patch::JsonPatchError;
+#[cfg(all(feature = "serde_json", not(feature = "triomphe")))]
+pub use persist::PersistError;
+pub use pin::VersionPin;
+pub use rcu_like::RcuLike;
+pub use reclaim::{Deferred, Reclaim, ReclaimReport, RefCount};
+#[cfg(all(feature = "tokio", not(feature = "triomphe")))]
+pub use refresher::Refresher;
+#[cfg(not(feature = "triomphe"))]
+pub use refreshing::RefreshingRcu;
+#[cfg(all(feature = "notify", not(feature = "triomphe")))]
+pub use reload::{FileReload, ReloadError};
+#[cfg(all(feature = "serde_json", not(feature = "triomphe")))]
+pub use replicate::VersionExport;
+#[cfg(all(feature = "rope", not(feature = "triomphe")))]
+pub use rope::RcuString;
+#[cfg(all(feature = "rt", not(feature = "triomphe")))]
+pub use rt::{RtAllocator, RtSection};
+#[cfg(feature = "rtic")]
+pub use rtic::{IsrReader, IsrWriter};
+#[cfg(all(feature = "safe-fallback", not(feature = "triomphe")))]
+pub use safe::SafeRcu;
+#[cfg(feature = "lock_api")]
+pub use serialized::SerializedWriter;
+#[cfg(feature = "spin")]
+pub use serialized::SpinWriter;
+#[cfg(not(feature = "triomphe"))]
+pub use services::RcuRegistry;
+#[cfg(all(feature = "shm", unix, not(feature = "triomphe")))]
+pub use shm::ShmRcu;
+#[cfg(all(feature = "signal", unix, not(feature = "triomphe")))]
+pub use signal::SignalReload;
+#[cfg(all(feature = "futures-signals", not(feature = "triomphe")))]
+pub use signals::MutableMirror;
+#[cfg(feature = "futures")]
+pub use sink::RcuSink;
+#[cfg(feature = "stats")]
+pub use stats::{ContentionStats, OutstandingReaders};
+pub use token::{VersionToken, VersionedGuard};
+#[cfg(feature = "tower")]
+pub use tower::RcuService;
+#[cfg(feature = "zeroize")]
+pub use zeroized::Zeroized;
 
 #[cfg(doctest)]
 #[cfg(not(feature = "triomphe"))]
@@ -68,8 +291,15 @@ extern "C" {}
 
 // TODO: lists & reference block as in the video https://www.youtube.com/watch?v=rxQ5K9lo034
 
-impl<T> Drop for Rcu<T> {
+impl<T, S> Drop for Rcu<T, S> {
     fn drop(&mut self) {
+        // Release the replaced versions first, since they're older
+        #[cfg(all(feature = "history", not(feature = "triomphe")))]
+        if let Some(history) = &self.history {
+            history.clear();
+        }
+        (self.release_retired)(self);
+
         let ptr = self.ptr.load(Ordering::Acquire);
 
         // Decrement the reference count of the inner Arc<T> when all references to the Rcu are lost
@@ -128,13 +358,87 @@ impl<T> Drop for Rcu<T> {
 /// ```
 ///
 /// \*With a possibility of unintended overwriting, see [`update`](Self::update)
-pub struct Rcu<T> {
+///
+/// What happens to replaced versions is decided by a [`Reclaim`] strategy `S`. By default they're
+/// dropped as soon as no [`ReadGuard`] can reach them, see [`with_reclaim`](Self::with_reclaim).
+///
+/// # Unwind safety
+///
+/// A version is only published after it's complete, so a panic in e.g. the closure passed to
+/// [`update`](Self::update) leaves the current version untouched. Hence `Rcu` is `UnwindSafe` and
+/// `RefUnwindSafe` as long as `T` can be shared across an unwind boundary.
+///
+/// # Variance
+///
+/// Like [`Cell`](core::cell::Cell) and `Mutex`, `Rcu` is invariant in `T`, since new versions
+/// can be written through a shared reference. If `&Rcu<&'static str>` coerced to
+/// `&Rcu<&'a str>`, a `&'a str` could be written to it and read after `'a` ends:
+///
+/// ```compile_fail
+#[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+#[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+/// use axka_rcu::Rcu;
+/// fn shorten<'a>(rcu: &'a Rcu<&'static str>) -> &'a Rcu<&'a str> {
+///     rcu
+/// }
+/// ```
+///
+/// Dropping an `Rcu` drops its versions, so borrows in `T` must outlive the `Rcu`.
+pub struct Rcu<T, S = RefCount> {
     /// The "inner [`Arc`]" or the current version Arc
     ///
     /// Around the `T` of `AtomicPtr<T>`, is `ArcInner`. It is what defines a "version".
     /// Its strong count is the number of `Arc`s lent out by [`Rcu::read`], plus one if it's the
-    /// current version.
+    /// current version or a replaced version which may still be read through a [`ReadGuard`].
+    ///
+    /// It must be loaded and replaced with `SeqCst`, see [`readers`].
     ptr: AtomicPtr<T>,
+    /// Keeps replaced versions alive while they may be read
+    readers: readers::Readers<T>,
+    /// The number of versions written after the first one
+    ///
+    /// It's incremented *before* a new version is published, so the generation of a version read
+    /// from `ptr` is never newer than what this holds.
+    ///
+    /// `AtomicU64` isn't available on all targets, so this wraps around on 32-bit targets.
+    generation: AtomicUsize,
+    /// The [`FROZEN`] and [`POISONED`] flags, plus [`WRITER`] for each write in progress
+    ///
+    /// Readers may only skip pinning when no write is in progress, since a write which began
+    /// before freezing may still be replacing the version.
+    state: AtomicUsize,
+    /// Receives replaced versions once they're released by `readers`
+    reclaim: S,
+    /// Called with new versions after they're written
+    hooks: hooks::Hooks<T>,
+    #[cfg(feature = "stats")]
+    stats: stats::Stats,
+    /// The metadata attached by [`write_labeled`](Self::write_labeled)
+    #[cfg(all(feature = "debug-meta", not(feature = "triomphe")))]
+    metas: meta::Metas<T>,
+    /// Where the versions were written, see [`version_origin`](Self::version_origin)
+    #[cfg(all(feature = "debug-meta", not(feature = "triomphe")))]
+    origins: meta::Origins<T>,
+    /// Where the `Rcu` was poisoned, see [`poisoned_at`](Self::poisoned_at)
+    #[cfg(all(feature = "debug-meta", not(feature = "triomphe")))]
+    poisoned_at: lock::Lock<Option<&'static core::panic::Location<'static>>>,
+    /// When the current version was published, in nanoseconds since [`epoch`]
+    #[cfg(not(feature = "triomphe"))]
+    last_updated: core::sync::atomic::AtomicU64,
+    /// The versions which are alive, see [`lineage`](Self::lineage)
+    #[cfg(all(feature = "lineage", not(feature = "triomphe")))]
+    lineage: lineage::Tracker<T>,
+    /// The last writes, see [`audit_trail`](Self::audit_trail)
+    #[cfg(all(feature = "audit", not(feature = "triomphe")))]
+    audit: audit::Trail,
+    /// The replaced versions which are kept, see [`with_history`](Self::with_history)
+    #[cfg(all(feature = "history", not(feature = "triomphe")))]
+    history: Option<history::History<T>>,
+    /// The write queue, pins, staged version and overlap detection, which most `Rcu`s never use
+    extras: extras::Extras<T>,
+    /// [`Rcu::release_retired`], which needs `S: Reclaim<T>` and so can't be called by `drop`
+    /// directly
+    release_retired: fn(&mut Self),
 }
 
 impl<T> Rcu<T> {
@@ -153,10 +457,230 @@ impl<T> Rcu<T> {
     /// assert_eq!(*rcu2.read(), "bar");
     /// ```
     pub fn new(value: Arc<T>) -> Self {
+        Self::with_reclaim(value, RefCount)
+    }
+
+    /// Creates a new `Rcu` containing a value which holds a [`Weak`](std::sync::Weak) pointer to
+    /// itself, like [`Arc::new_cyclic`].
+    ///
+    /// The `Weak` passed to `f` can't be upgraded until this returns.
+    ///
+    /// This is only available without the `triomphe` feature, since `triomphe` has no weak
+    /// pointers.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::{Arc, Weak};
+    ///
+    /// use axka_rcu::Rcu;
+    ///
+    /// struct Node {
+    ///     this: Weak<Node>,
+    /// }
+    ///
+    /// let rcu = Rcu::new_cyclic(|this| Node { this: this.clone() });
+    /// let node = rcu.read();
+    /// assert!(Arc::ptr_eq(&node.this.upgrade().unwrap(), &node));
+    /// ```
+    #[cfg(not(feature = "triomphe"))]
+    pub fn new_cyclic<F>(f: F) -> Self
+    where
+        F: FnOnce(&std::sync::Weak<T>) -> T,
+    {
+        Self::new(Arc::new_cyclic(f))
+    }
+}
+
+impl<T, S: Reclaim<T>> Rcu<T, S> {
+    /// Creates a new `Rcu` containing the given value, which hands replaced versions to `reclaim`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::{Deferred, Rcu};
+    /// let rcu = Rcu::with_reclaim(Arc::new("foo"), Deferred::new());
+    ///
+    /// rcu.write(Arc::new("bar"));
+    /// assert!(!rcu.reclaimer().is_empty());
+    /// ```
+    pub fn with_reclaim(value: Arc<T>, reclaim: S) -> Self {
+        #[cfg(all(feature = "lineage", not(feature = "triomphe")))]
+        let lineage = lineage::Tracker::new(&value);
         let ptr = Arc::into_raw(value) as *mut _;
 
         Self {
             ptr: AtomicPtr::new(ptr),
+            generation: AtomicUsize::new(0),
+            state: AtomicUsize::new(0),
+            readers: readers::Readers::new(),
+            reclaim,
+            hooks: hooks::Hooks::new(),
+            #[cfg(feature = "stats")]
+            stats: stats::Stats::new(),
+            #[cfg(all(feature = "debug-meta", not(feature = "triomphe")))]
+            metas: lock::Lock::new(alloc::vec::Vec::new()),
+            #[cfg(all(feature = "debug-meta", not(feature = "triomphe")))]
+            origins: lock::Lock::new(alloc::vec::Vec::new()),
+            #[cfg(all(feature = "debug-meta", not(feature = "triomphe")))]
+            poisoned_at: lock::Lock::new(None),
+            #[cfg(not(feature = "triomphe"))]
+            last_updated: core::sync::atomic::AtomicU64::new(nanos_since_epoch()),
+            #[cfg(all(feature = "lineage", not(feature = "triomphe")))]
+            lineage,
+            #[cfg(all(feature = "audit", not(feature = "triomphe")))]
+            audit: audit::Trail::new(),
+            #[cfg(all(feature = "history", not(feature = "triomphe")))]
+            history: None,
+            extras: extras::Extras::new(),
+            release_retired: Self::release_retired,
+        }
+    }
+
+    /// Hands the versions still waiting for their readers to the [`Reclaim`] strategy when the
+    /// `Rcu` is dropped.
+    fn release_retired(&mut self) {
+        let reclaiming = hooks::Reclaiming {
+            hooks: &self.hooks,
+            strategy: &self.reclaim,
+        };
+        self.readers.clear(&reclaiming);
+        reclaiming.flush();
+    }
+
+    /// Returns the [`Reclaim`] strategy of this `Rcu`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::{Deferred, Rcu};
+    /// let rcu = Rcu::with_reclaim(Arc::new("foo"), Deferred::new());
+    ///
+    /// rcu.write(Arc::new("bar"));
+    /// rcu.reclaimer().flush();
+    /// assert!(rcu.reclaimer().is_empty());
+    /// ```
+    pub fn reclaimer(&self) -> &S {
+        &self.reclaim
+    }
+
+    /// Waits until no reader may still be using the versions replaced before this call, and hands
+    /// them to the [`Reclaim`] strategy.
+    ///
+    /// Replaced versions are otherwise only released by later writes, so this is useful before
+    /// [flushing](Deferred::flush) or when the last write was a while ago. Only [`ReadGuard`]s
+    /// are waited for, `Arc`s returned by [`read`](Self::read) may still keep the versions alive.
+    ///
+    /// [Pinned](Self::pin_current) versions aren't waited for either, but can be listed with
+    /// [`pinned_generations`](Self::pinned_generations).
+    ///
+    /// Use an [`RcuGroup`] to synchronize many `Rcu`s at once.
+    ///
+    /// # Deadlocks
+    ///
+    /// This blocks while a `ReadGuard` which was created before this call exists, so it deadlocks
+    /// if the current thread holds one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::{Deferred, Rcu};
+    /// let rcu = Rcu::with_reclaim(Arc::new("foo"), Deferred::new());
+    ///
+    /// let guard = rcu.read_guard();
+    /// rcu.write(Arc::new("bar"));
+    /// drop(guard);
+    /// assert!(rcu.reclaimer().is_empty());
+    ///
+    /// rcu.synchronize();
+    /// assert!(!rcu.reclaimer().is_empty());
+    /// ```
+    pub fn synchronize(&self) {
+        self.readers.synchronize(&self.reclaiming());
+    }
+
+    /// Hands the replaced versions which no reader is using anymore to the [`Reclaim`] strategy,
+    /// and [flushes](Reclaim::flush) it.
+    ///
+    /// Unlike [`synchronize`](Self::synchronize), this never blocks: versions which are still
+    /// read stay around until a later write or flush. With [`Deferred`], this lets
+    /// latency-sensitive applications choose when replaced versions are dropped, e.g. between
+    /// frames.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::{Deferred, Rcu};
+    /// let rcu = Rcu::with_reclaim(Arc::new("foo"), Deferred::new());
+    ///
+    /// let first = rcu.read();
+    /// let guard = rcu.read_guard();
+    /// rcu.write(Arc::new("bar"));
+    ///
+    /// rcu.flush_deferred();
+    /// assert_eq!(Arc::strong_count(&first), 2);
+    ///
+    /// drop(guard);
+    /// rcu.flush_deferred();
+    /// assert_eq!(Arc::strong_count(&first), 1);
+    /// ```
+    pub fn flush_deferred(&self) {
+        self.readers.advance(&self.reclaiming());
+        self.reclaim.flush();
+    }
+
+    /// Makes every write run the new version through `validator`, and refuse to publish it if
+    /// `validator` returns `false`.
+    ///
+    /// [`write`](Self::write) and [`update`](Self::update) panic on rejected versions, while
+    /// [`try_write`](Self::try_write) and [`try_update`](Self::try_update) return
+    /// [`WriteError::Rejected`]. The current version isn't validated.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::{errors::WriteError, Rcu};
+    /// let port = Rcu::new(Arc::new(80u16)).with_validator(|port| *port != 0);
+    ///
+    /// assert_eq!(port.try_write(Arc::new(0)), Err(WriteError::Rejected));
+    /// assert_eq!(port.try_write(Arc::new(8080)), Ok(()));
+    /// assert_eq!(*port.read(), 8080);
+    /// ```
+    pub fn with_validator<F>(mut self, validator: F) -> Self
+    where
+        F: Fn(&T) -> bool + Send + Sync + 'static,
+    {
+        self.hooks.set_validator(validator);
+        self
+    }
+
+    /// Installs [`RcuHooks`], which are called on every read, write and reclamation.
+    ///
+    /// This replaces hooks installed before. See [`RcuHooks`] for an example.
+    pub fn with_hooks<H>(mut self, hooks: H) -> Self
+    where
+        H: RcuHooks<T> + 'static,
+    {
+        self.hooks.set_custom(hooks);
+        self
+    }
+
+    /// Hands replaced versions to the [`Reclaim`] strategy, after running
+    /// [`RcuHooks::on_reclaim`].
+    fn reclaiming(&self) -> hooks::Reclaiming<'_, T, S> {
+        hooks::Reclaiming {
+            hooks: &self.hooks,
+            strategy: &self.reclaim,
         }
     }
 
@@ -171,32 +695,51 @@ impl<T> Rcu<T> {
     /// let rcu = Rcu::new(Arc::new("foo bar"));
     /// assert_eq!(*rcu.read(), "foo bar");
     /// ```
+    #[inline]
     pub fn read(&self) -> Arc<T> {
-        let ptr = self.ptr.load(Ordering::Acquire);
-        #[cfg(not(feature = "triomphe"))]
-        unsafe {
-            // Increment the reference count of the inner Arc<T>
-            // SAFETY:
-            // - The ptr was created by Arc::into_raw in either Rcu::new or Rcu::write
-            // - RcuInner counts as one strong reference
-            Arc::increment_strong_count(ptr);
-            // SAFETY: The ptr was created by Arc::into_raw in either Rcu::new or Rcu::write
-            Arc::from_raw(ptr)
-        }
-        #[cfg(feature = "triomphe")]
-        unsafe {
-            let arc = Arc::from_raw(ptr);
-            let _ = core::mem::ManuallyDrop::new(Arc::clone(&arc));
-            arc
+        // The version can't be released while the reference count is incremented
+        ReadGuard::to_arc(&self.read_guard())
+    }
+
+    /// Clones the [`Arc`] of the current version, unless the `Rcu` is [poisoned](Self::poison).
+    ///
+    /// This lets callers tell a valid but old version apart from one which is known to be stale,
+    /// e.g. because reloading it failed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RcuStateError::Poisoned`] if the `Rcu` is poisoned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::{errors::RcuStateError, Rcu};
+    /// let rcu = Rcu::new(Arc::new("foo"));
+    /// assert_eq!(*rcu.try_read().unwrap(), "foo");
+    ///
+    /// rcu.poison();
+    /// assert_eq!(rcu.try_read(), Err(RcuStateError::Poisoned));
+    ///
+    /// rcu.write(Arc::new("bar"));
+    /// assert_eq!(*rcu.try_read().unwrap(), "bar");
+    /// ```
+    #[inline]
+    pub fn try_read(&self) -> Result<Arc<T>, RcuStateError> {
+        if self.is_poisoned() {
+            return Err(RcuStateError::Poisoned);
         }
+        Ok(self.read())
     }
 
-    /// Returns a reference to the current version.
+    /// Returns a guard to the current version, without touching its reference count.
     ///
-    /// # Safety
+    /// Instead of the reference count, one of several counters shared by the readers of this `Rcu`
+    /// is incremented, which is cheaper when many threads read at the same time.
     ///
-    /// - This function and the returned reference are only safe when there is no writer.
-    /// - If the RCU gets written to at any time, the returned reference is undefined behaviour.
+    /// While any guard is held, replaced versions are kept alive until a later write sees that the
+    /// guards are gone. Prefer [`read`](Self::read) for long-lived references.
     ///
     /// # Example
     ///
@@ -204,31 +747,79 @@ impl<T> Rcu<T> {
     #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
     #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
     /// use axka_rcu::Rcu;
-    /// let rcu = Rcu::new(Arc::new("foo bar"));
-    /// assert_eq!(unsafe { rcu.read_ref() }, &"foo bar");
+    /// let rcu = Rcu::new(Arc::new("foo"));
+    ///
+    /// let guard = rcu.read_guard();
+    /// rcu.write(Arc::new("bar"));
+    /// assert_eq!(*guard, "foo");
+    /// ```
+    #[inline]
+    pub fn read_guard(&self) -> ReadGuard<'_, T> {
+        #[cfg(all(feature = "rt", not(feature = "triomphe")))]
+        let _realtime = self.forbid(rt::READ);
+        let guard = if let Some(ptr) = self.frozen_ptr() {
+            // SAFETY: The version of a frozen `Rcu` is only released when dropping it
+            unsafe { ReadGuard::frozen(ptr) }
+        } else {
+            let counter = self.readers.pin();
+            let ptr = self.ptr.load(Ordering::SeqCst);
+
+            // SAFETY: The version was loaded after pinning
+            unsafe { ReadGuard::new(ptr, counter) }
+        };
+        self.hooks.read(&guard);
+        guard
+    }
+
+    /// Returns a [`ReadSession`] at the current version, which can be
+    /// [refreshed](ReadSession::refresh) to newer versions without pinning again.
+    ///
+    /// This is cheaper than calling [`read`](Self::read) or [`read_guard`](Self::read_guard) in a
+    /// tight loop, since those increment a counter each time.
+    ///
+    /// # Example
+    ///
     /// ```
-    /// # UB Example
-    /// ```no_run
     #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
     #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
     /// use axka_rcu::Rcu;
-    /// let rcu = Rcu::new(Arc::new([42u8; 1024]));
+    /// let rcu = Rcu::new(Arc::new(vec![1, 2, 3]));
     ///
-    /// let r = unsafe { rcu.read_ref() };
-    /// assert_eq!(r[0], 42);
+    /// let session = rcu.read_session();
+    /// let sum: i32 = (0..3).map(|i| session[i]).sum();
+    /// assert_eq!(sum, 6);
+    /// ```
+    pub fn read_session(&self) -> ReadSession<'_, T, S> {
+        ReadSession::new(self)
+    }
+
+    /// Runs `f` on a reference to the current version.
     ///
-    /// rcu.write(Arc::new([1; 1024]));
+    /// The version is kept alive only for the duration of `f`. Unlike [`read_ref`](Self::read_ref),
+    /// this is safe even when there are concurrent writers.
+    ///
+    /// # Example
     ///
-    /// assert_ne!(r[0], 42);
     /// ```
-    pub unsafe fn read_ref(&self) -> &T {
-        unsafe { &**self.ptr.as_ptr() }
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new("foo bar"));
+    /// assert_eq!(rcu.read_with(|s| s.len()), 7);
+    /// ```
+    #[inline]
+    pub fn read_with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        f(&self.read_guard())
     }
 
-    /// Clones `T`, runs `updater` on `T` and [`write`](Self::write)s `T`.
+    /// Leaks a strong reference to the current version, and returns a `'static` reference to it.
     ///
-    /// If you want to guarantee no **data loss** or unintended overwriting, use a semaphore on
-    /// writes.
+    /// The version is never dropped, even after it's replaced. This is meant for versions which
+    /// live for the rest of the program anyway, e.g. to pass them to APIs which require
+    /// `&'static T`. Each call leaks another reference, but no more memory.
     ///
     /// # Example
     ///
@@ -237,26 +828,25 @@ impl<T> Rcu<T> {
     #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
     /// use axka_rcu::Rcu;
     /// let rcu = Rcu::new(Arc::new("foo".to_owned()));
+    /// let name: &'static str = rcu.leak();
     ///
-    /// rcu.update(|s| s.push_str(" bar"));
-    /// assert_eq!(*rcu.read(), "foo bar");
+    /// rcu.write(Arc::new("bar".to_owned()));
+    /// drop(rcu);
+    /// assert_eq!(name, "foo");
     /// ```
-    pub fn update<F, R>(&self, updater: F)
+    pub fn leak(&self) -> &'static T
     where
-        T: Clone,
-        F: FnOnce(&mut T) -> R,
+        T: 'static,
     {
-        // TODO: If there *is* a semaphore on Rcu::update and Rcu::write, it's guaranteed that the
-        // internal pointer will not be updated during `updater` and it can be cloned without
-        // atomic operations:
-        // unsafe { &**self.ptr.as_ptr() }.clone()
-
-        let mut value = (*self.read()).clone();
-        updater(&mut value);
-        self.write(Arc::new(value))
+        let version = Arc::into_raw(self.read());
+        // SAFETY: The leaked strong reference keeps the version alive forever
+        unsafe { &*version }
     }
 
-    /// Writes a new version.
+    /// Consumes the `Rcu`, returning the [`Arc`] of the current version.
+    ///
+    /// Replaced versions are released like when dropping the `Rcu`. This is also available as
+    /// `Arc::from(rcu)`.
     ///
     /// # Example
     ///
@@ -265,43 +855,1113 @@ impl<T> Rcu<T> {
     #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
     /// use axka_rcu::Rcu;
     /// let rcu = Rcu::new(Arc::new("foo"));
+    /// let reader = rcu.read();
     ///
-    /// rcu.write(Arc::new("bar"));
-    /// assert_eq!(*rcu.read(), "bar");
+    /// let current = rcu.into_arc();
+    /// assert!(Arc::ptr_eq(&current, &reader));
     /// ```
-    pub fn write(&self, new_value: Arc<T>) {
-        let new_ptr = Arc::into_raw(new_value) as *mut _;
-        let old_ptr = self.ptr.swap(new_ptr, Ordering::Release);
+    pub fn into_arc(mut self) -> Arc<T> {
+        let ptr = *self.ptr.get_mut();
+        // SAFETY: The ptr was created by Arc::into_raw, and the `Rcu` owns a reference until it's
+        // dropped at the end of this function
+        let current = ManuallyDrop::new(unsafe { Arc::from_raw(ptr) });
+        Arc::clone(&current)
+    }
 
-        // Decrement the reference count of the inner Arc<T>
-        unsafe {
-            drop(Arc::from_raw(old_ptr));
+    /// Consumes the `Rcu`, returning the current version if no `Arc` returned by
+    /// [`read`](Self::read) still references it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the `Rcu` back if the current version is still referenced.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new(vec![1, 2, 3]));
+    /// let reader = rcu.read();
+    ///
+    /// let rcu = rcu.try_unwrap().unwrap_err();
+    /// drop(reader);
+    /// assert_eq!(rcu.try_unwrap().unwrap(), [1, 2, 3]);
+    /// ```
+    // Returning the `Rcu` by value mirrors `Arc::try_unwrap`
+    #[allow(clippy::result_large_err)]
+    pub fn try_unwrap(mut self) -> Result<T, Self> {
+        let ptr = *self.ptr.get_mut();
+        // SAFETY: The ptr was created by Arc::into_raw, and the `Rcu` owns a reference
+        let current = ManuallyDrop::new(unsafe { Arc::from_raw(ptr) });
+        // No other reference can be created, since `self` is owned
+        if Arc::strong_count(&current) != 1 {
+            return Err(self);
+        }
+        match Arc::try_unwrap(self.into_arc()) {
+            Ok(value) => Ok(value),
+            Err(_) => unreachable!("the version was referenced by only the `Rcu`"),
         }
     }
-}
 
-impl<T: Default> Default for Rcu<T> {
-    /// Creates a new `Rcu<T>`, with the `Default` value for T.
-    fn default() -> Self {
-        Self::new(Arc::new(T::default()))
+    /// Returns a guard to a part of the current version.
+    ///
+    /// The guard keeps the whole version alive, while only exposing what `f` projects it to.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// struct Config {
+    ///     name: String,
+    ///     port: u16,
+    /// }
+    ///
+    /// let rcu = Rcu::new(Arc::new(Config { name: "foo".to_owned(), port: 80 }));
+    /// let name = rcu.map_read(|config| config.name.as_str());
+    ///
+    /// rcu.write(Arc::new(Config { name: "bar".to_owned(), port: 8080 }));
+    /// assert_eq!(&*name, "foo");
+    /// ```
+    pub fn map_read<U, F>(&self, f: F) -> MappedGuard<U>
+    where
+        U: ?Sized,
+        F: FnOnce(&T) -> &U,
+    {
+        MappedGuard::new(self.read(), f)
     }
-}
 
-impl<T> From<T> for Rcu<T> {
-    /// Creates a new `Rcu<T>` from T.
-    fn from(value: T) -> Self {
-        Self::new(Arc::new(value))
+    /// Returns a part of the current version, yoked to the version's [`Arc`].
+    ///
+    /// Unlike [`map_read`](Self::map_read), the returned [`Yoke`](yoke::Yoke) can be stored in
+    /// structs and sent to other threads.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// struct Config {
+    ///     name: String,
+    ///     port: u16,
+    /// }
+    ///
+    /// let rcu = Rcu::new(Arc::new(Config { name: "foo".to_owned(), port: 80 }));
+    /// let name = rcu.read_yoked(|config| config.name.as_str());
+    ///
+    /// rcu.write(Arc::new(Config { name: "bar".to_owned(), port: 8080 }));
+    /// std::thread::spawn(move || {
+    ///     assert_eq!(*name.get(), "foo");
+    /// }).join().unwrap();
+    /// ```
+    #[cfg(feature = "yoke")]
+    pub fn read_yoked<U, F>(&self, projection: F) -> yoke::Yoke<&'static U, Arc<T>>
+    where
+        T: 'static,
+        U: ?Sized + 'static,
+        F: for<'a> FnOnce(&'a T) -> &'a U,
+    {
+        yoke::Yoke::<&'static U, Arc<T>>::attach_to_cart(self.read(), projection)
     }
-}
 
-impl<T: fmt::Debug> fmt::Debug for Rcu<T> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut d = f.debug_struct("Rcu");
-        d.field("data", &self.read());
+    /// Returns a reference to the current version.
+    ///
+    /// # Safety
+    ///
+    /// - This function and the returned reference are only safe when there is no writer.
+    /// - If the RCU gets written to at any time, the returned reference is undefined behaviour.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new("foo bar"));
+    /// assert_eq!(unsafe { rcu.read_ref() }, &"foo bar");
+    /// ```
+    /// # UB Example
+    /// ```no_run
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new([42u8; 1024]));
+    ///
+    /// let r = unsafe { rcu.read_ref() };
+    /// assert_eq!(r[0], 42);
+    ///
+    /// rcu.write(Arc::new([1; 1024]));
+    ///
+    /// assert_ne!(r[0], 42);
+    /// ```
+    pub unsafe fn read_ref(&self) -> &T {
+        unsafe { &**self.ptr.as_ptr() }
+    }
+
+    /// Clones the [`Arc`] of the current version, like [`read`](Self::read), but with a `Relaxed`
+    /// load and without registering as a reader.
+    ///
+    /// This skips the fences of [`read`](Self::read), which are measurable on weakly ordered
+    /// targets like ARM.
+    ///
+    /// # Safety
+    ///
+    /// - No version may be written while this is called, since the current version could be
+    ///   released before its reference count is incremented.
+    /// - The last write must happen before this call, e.g. because it was done by the same thread
+    ///   or the threads synchronize through a channel or a lock. Otherwise the contents of the
+    ///   version may not be visible yet.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new("foo"));
+    /// rcu.write(Arc::new("bar"));
+    ///
+    /// // SAFETY: This thread is the only writer
+    /// assert_eq!(*unsafe { rcu.read_unchecked() }, "bar");
+    /// ```
+    pub unsafe fn read_unchecked(&self) -> Arc<T> {
+        let ptr = self.ptr.load(Ordering::Relaxed);
+        // SAFETY: The caller guarantees that the version isn't released during this call
+        let guard = unsafe { ReadGuard::frozen(ptr) };
+        self.hooks.read(&guard);
+        ReadGuard::to_arc(&guard)
+    }
+
+    /// Clones `T`, runs `updater` on `T` and [`write`](Self::write)s `T`.
+    ///
+    /// If you want to guarantee no **data loss** or unintended overwriting, use a semaphore on
+    /// writes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `Rcu` is [frozen](Self::freeze) or the [validator](Self::with_validator)
+    /// rejects the new version.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new("foo".to_owned()));
+    ///
+    /// rcu.update(|s| s.push_str(" bar"));
+    /// assert_eq!(*rcu.read(), "foo bar");
+    /// ```
+    #[track_caller]
+    pub fn update<F, R>(&self, updater: F)
+    where
+        T: Clone,
+        F: FnOnce(&mut T) -> R,
+    {
+        // TODO: If there *is* a semaphore on Rcu::update and Rcu::write, it's guaranteed that the
+        // internal pointer will not be updated during `updater` and it can be cloned without
+        // atomic operations:
+        // unsafe { &**self.ptr.as_ptr() }.clone()
+
+        let _running = self.enter_update();
+        let current = self.read();
+        let mut value = (*current).clone();
+        updater(&mut value);
+        if let Err(err) = self.try_write_from(Arc::new(value), Some(&current)) {
+            panic!("{err}");
+        }
+    }
+
+    /// Like [`update`](Self::update), but returns an error instead of panicking if the new version
+    /// can't be written.
+    ///
+    /// `updater` isn't called if the `Rcu` is already frozen.
+    ///
+    /// # Errors
+    ///
+    /// See [`try_write`](Self::try_write).
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new("foo".to_owned()));
+    ///
+    /// rcu.freeze();
+    /// assert!(rcu.try_update(|s| s.push_str(" bar")).is_err());
+    /// assert_eq!(*rcu.read(), "foo");
+    /// ```
+    #[track_caller]
+    pub fn try_update<F, R>(&self, updater: F) -> Result<(), WriteError>
+    where
+        T: Clone,
+        F: FnOnce(&mut T) -> R,
+    {
+        if self.is_frozen() {
+            return Err(WriteError::Frozen);
+        }
+
+        let _running = self.enter_update();
+        let current = self.read();
+        let mut value = (*current).clone();
+        updater(&mut value);
+        self.try_write_from(Arc::new(value), Some(&current))
+    }
+
+    /// Runs `updater` on the [`Arc`] of the current version and [`write`](Self::write)s the
+    /// version it returns.
+    ///
+    /// Unlike [`update`](Self::update), `T` isn't cloned up front, so `updater` can build the new
+    /// version from scratch, share parts of the old one or return an existing `Arc`. Like
+    /// `update`, versions written while `updater` runs are overwritten.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `Rcu` is [frozen](Self::freeze) or the [validator](Self::with_validator)
+    /// rejects the new version.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    ///
+    /// struct Config {
+    ///     name: Arc<str>,
+    ///     port: u16,
+    /// }
+    ///
+    /// let rcu = Rcu::new(Arc::new(Config { name: "foo".into(), port: 80 }));
+    ///
+    /// // The name is shared instead of being cloned
+    /// rcu.update_with_old(|old| Config { name: old.name.clone(), port: 8080 });
+    /// assert_eq!(rcu.read().port, 8080);
+    ///
+    /// let old = rcu.read();
+    /// rcu.update_with_old(|_| Arc::clone(&old));
+    /// assert!(Arc::ptr_eq(&rcu.read(), &old));
+    /// ```
+    #[track_caller]
+    pub fn update_with_old<F, N>(&self, updater: F)
+    where
+        F: FnOnce(&Arc<T>) -> N,
+        N: Into<Arc<T>>,
+    {
+        let _running = self.enter_update();
+        let new_value = updater(&self.read()).into();
+        self.write(new_value);
+    }
+
+    /// Clones `T`, runs `updater` on `T` and [`write`](Self::write)s `T`, retrying if another
+    /// version was written concurrently.
+    ///
+    /// Unlike [`update`](Self::update), no writes are lost, but `updater` may be called multiple
+    /// times. The return value of the call that got written is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Arc::new(Rcu::new(Arc::new(0)));
+    ///
+    /// let threads: Vec<_> = (0..4).map(|_| {
+    ///     let rcu = rcu.clone();
+    ///     std::thread::spawn(move || {
+    ///         for _ in 0..100 {
+    ///             rcu.update_retry(|n| *n += 1);
+    ///         }
+    ///     })
+    /// }).collect();
+    /// for thread in threads {
+    ///     thread.join().unwrap();
+    /// }
+    ///
+    /// assert_eq!(*rcu.read(), 400);
+    /// ```
+    #[track_caller]
+    pub fn update_retry<F, R>(&self, mut updater: F) -> R
+    where
+        T: Clone,
+        F: FnMut(&mut T) -> R,
+    {
+        let mut current = self.read();
+        loop {
+            let mut value = (*current).clone();
+            let ret = updater(&mut value);
+            match self.compare_exchange(&current, Arc::new(value)) {
+                Ok(()) => return ret,
+                Err(_) => {
+                    #[cfg(feature = "stats")]
+                    self.stats.retry();
+                    current = self.read();
+                }
+            }
+        }
+    }
+
+    /// Writes a new version.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new("foo"));
+    ///
+    /// rcu.write(Arc::new("bar"));
+    /// assert_eq!(*rcu.read(), "bar");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `Rcu` is [frozen](Self::freeze) or the [validator](Self::with_validator)
+    /// rejects the new version. See [`try_write`](Self::try_write) for a non-panicking version.
+    #[track_caller]
+    pub fn write(&self, new_value: Arc<T>) {
+        if let Err(err) = self.try_write(new_value) {
+            panic!("{err}");
+        }
+    }
+
+    /// Writes a new version, unless the `Rcu` is [frozen](Self::freeze) or the
+    /// [validator](Self::with_validator) rejects it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`WriteError::Frozen`] if the `Rcu` is frozen and [`WriteError::Rejected`] if the
+    /// validator rejects the new version.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new("foo"));
+    ///
+    /// assert!(rcu.try_write(Arc::new("bar")).is_ok());
+    /// rcu.freeze();
+    /// assert!(rcu.try_write(Arc::new("baz")).is_err());
+    /// assert_eq!(*rcu.read(), "bar");
+    /// ```
+    #[track_caller]
+    pub fn try_write(&self, new_value: Arc<T>) -> Result<(), WriteError> {
+        self.try_write_from(new_value, None)
+    }
+
+    /// Like [`try_write`](Self::try_write), but `base` is the version `new_value` was updated
+    /// from, if any.
+    #[cfg_attr(
+        not(all(feature = "lineage", not(feature = "triomphe"))),
+        allow(unused_variables)
+    )]
+    #[track_caller]
+    fn try_write_from(&self, new_value: Arc<T>, base: Option<&Arc<T>>) -> Result<(), WriteError> {
+        let _writing = self.begin_write(&new_value)?;
+        #[cfg(all(feature = "debug-meta", not(feature = "triomphe")))]
+        self.record_origin(&new_value);
+        #[cfg(all(feature = "rt", not(feature = "triomphe")))]
+        let _realtime = self.forbid(rt::WRITE);
+
+        // The version may be replaced and released as soon as it's published
+        let published = self.hooks.is_active().then(|| Arc::clone(&new_value));
+        #[cfg(all(feature = "lineage", not(feature = "triomphe")))]
+        let version = Arc::downgrade(&new_value);
+        let new_ptr = Arc::into_raw(new_value) as *mut _;
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        let old_ptr = self.ptr.swap(new_ptr, Ordering::SeqCst);
+        #[cfg(all(feature = "lineage", not(feature = "triomphe")))]
+        self.lineage
+            .record(version, self.generation(), old_ptr, base.map(Arc::as_ptr));
+        #[cfg(all(feature = "audit", not(feature = "triomphe")))]
+        self.audit.record(self.generation());
+        #[cfg(all(feature = "history", not(feature = "triomphe")))]
+        if let Some(history) = &self.history {
+            // SAFETY: The replaced version isn't retired yet
+            unsafe { history.record(old_ptr, self.generation()) };
+        }
+
+        // Decrement the reference count of the inner Arc<T> once it's not being read
+        unsafe {
+            self.readers.retire(old_ptr, &self.reclaiming());
+        }
+        #[cfg(not(feature = "triomphe"))]
+        self.touch();
+        self.clear_poison();
+        self.hooks.notify(published.as_ref());
+        Ok(())
+    }
+
+    /// Writes a new version if `current` is still the current version.
+    ///
+    /// On failure, `new_value` is returned back.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new("foo"));
+    ///
+    /// let old = rcu.read();
+    /// assert!(rcu.compare_exchange(&old, Arc::new("bar")).is_ok());
+    /// assert_eq!(*rcu.compare_exchange(&old, Arc::new("baz")).unwrap_err(), "baz");
+    /// assert_eq!(*rcu.read(), "bar");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `Rcu` is [frozen](Self::freeze) or the [validator](Self::with_validator)
+    /// rejects the new version.
+    #[track_caller]
+    pub fn compare_exchange(&self, current: &Arc<T>, new_value: Arc<T>) -> Result<(), Arc<T>> {
+        let _writing = self
+            .begin_write(&new_value)
+            .unwrap_or_else(|err| panic!("{err}"));
+
+        if !self.claim_generation(current) {
+            return Err(new_value);
+        }
+        self.compare_exchange_ptr(current, new_value)
+    }
+
+    /// Writes `new_value` if `predicate` returns `true` for the current version, returning whether
+    /// it was written.
+    ///
+    /// Conditional writes wait in line like [`update_fair`](Self::update_fair), so the check and
+    /// the write are atomic with respect to other `write_if`, `update_fair` and
+    /// [`transition`](Self::transition) calls. This is useful for logic like "only upgrade if the
+    /// new epoch is greater". If another method writes a version while `predicate` runs,
+    /// `new_value` isn't written and `false` is returned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `Rcu` is [frozen](Self::freeze) or the [validator](Self::with_validator)
+    /// rejects the new version.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new(2));
+    ///
+    /// assert!(!rcu.write_if(|epoch| *epoch < 1, Arc::new(1)));
+    /// assert!(rcu.write_if(|epoch| *epoch < 3, Arc::new(3)));
+    /// assert_eq!(*rcu.read(), 3);
+    /// ```
+    #[track_caller]
+    pub fn write_if<P>(&self, predicate: P, new_value: Arc<T>) -> bool
+    where
+        P: FnOnce(&T) -> bool,
+    {
+        let _ticket = self.extras.get_or_alloc().queue.enter();
+        let current = self.read();
+        predicate(&current) && self.compare_exchange(&current, new_value).is_ok()
+    }
+
+    /// Increments the generation for an exchange from `current`, or counts a conflict and returns
+    /// `false` if `current` was already replaced.
+    ///
+    /// The generation is incremented before the exchange, so a version is never published with an
+    /// older generation. Hence it's only skipped if another version is written in between.
+    fn claim_generation(&self, current: &Arc<T>) -> bool {
+        if !core::ptr::eq(self.ptr.load(Ordering::Acquire), Arc::as_ptr(current)) {
+            #[cfg(feature = "stats")]
+            self.stats.conflict();
+            return false;
+        }
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        true
+    }
+
+    /// Like [`compare_exchange`](Self::compare_exchange), but the caller is responsible for
+    /// incrementing the generation and calling [`begin_write`](Self::begin_write).
+    #[track_caller]
+    fn compare_exchange_ptr(&self, current: &Arc<T>, new_value: Arc<T>) -> Result<(), Arc<T>> {
+        // `current` can't be dropped during this, so its address can't be reused by another
+        // version
+        let current_ptr = Arc::as_ptr(current) as *mut _;
+        #[cfg(all(feature = "debug-meta", not(feature = "triomphe")))]
+        self.record_origin(&new_value);
+        #[cfg(all(feature = "rt", not(feature = "triomphe")))]
+        let _realtime = self.forbid(rt::WRITE);
+        // The version may be replaced and released as soon as it's published
+        let published = self.hooks.is_active().then(|| Arc::clone(&new_value));
+        #[cfg(all(feature = "lineage", not(feature = "triomphe")))]
+        let version = Arc::downgrade(&new_value);
+        let new_ptr = Arc::into_raw(new_value) as *mut _;
+
+        match self
+            .ptr
+            .compare_exchange(current_ptr, new_ptr, Ordering::SeqCst, Ordering::Acquire)
+        {
+            Ok(old_ptr) => {
+                #[cfg(all(feature = "lineage", not(feature = "triomphe")))]
+                self.lineage
+                    .record(version, self.generation(), old_ptr, Some(current_ptr));
+                #[cfg(all(feature = "audit", not(feature = "triomphe")))]
+                self.audit.record(self.generation());
+                #[cfg(all(feature = "history", not(feature = "triomphe")))]
+                if let Some(history) = &self.history {
+                    // SAFETY: The replaced version isn't retired yet
+                    unsafe { history.record(old_ptr, self.generation()) };
+                }
+                // Decrement the reference count of the inner Arc<T> once it's not being read
+                unsafe {
+                    self.readers.retire(old_ptr, &self.reclaiming());
+                }
+                #[cfg(not(feature = "triomphe"))]
+                self.touch();
+                self.clear_poison();
+                self.hooks.notify(published.as_ref());
+                Ok(())
+            }
+            Err(_) => {
+                #[cfg(feature = "stats")]
+                self.stats.conflict();
+                #[cfg(all(feature = "debug-meta", not(feature = "triomphe")))]
+                self.forget_origin(new_ptr);
+                // SAFETY: The ptr was created by Arc::into_raw above and wasn't published
+                Err(unsafe { Arc::from_raw(new_ptr) })
+            }
+        }
+    }
+
+    /// Returns the generation of the current version.
+    ///
+    /// The generation starts at zero and is incremented by every write. It may skip values when
+    /// writes happen concurrently.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new("foo"));
+    /// assert_eq!(rcu.generation(), 0);
+    ///
+    /// rcu.write(Arc::new("bar"));
+    /// assert_eq!(rcu.generation(), 1);
+    /// ```
+    #[inline]
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire) as u64
+    }
+
+    /// Clones the [`Arc`] of the current version, along with its [generation](Self::generation).
+    ///
+    /// If a new version is being written concurrently, the returned generation may be newer than
+    /// the version, but never older.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new("foo"));
+    /// rcu.write(Arc::new("bar"));
+    ///
+    /// let (value, generation) = rcu.read_versioned();
+    /// assert_eq!((*value, generation), ("bar", 1));
+    /// ```
+    pub fn read_versioned(&self) -> (Arc<T>, u64) {
+        let value = self.read();
+        (value, self.generation())
+    }
+
+    /// Returns when the current version was published, or when the `Rcu` was created if no
+    /// version was written since.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::sync::Arc;
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new("foo"));
+    ///
+    /// let before = std::time::Instant::now();
+    /// rcu.write(Arc::new("bar"));
+    /// assert!(rcu.last_updated() >= before);
+    /// ```
+    #[cfg(not(feature = "triomphe"))]
+    pub fn last_updated(&self) -> std::time::Instant {
+        let nanos = self.last_updated.load(Ordering::Relaxed);
+        epoch() + std::time::Duration::from_nanos(nanos)
+    }
+
+    /// Returns how long ago the current version was [published](Self::last_updated).
+    ///
+    /// This is useful for health checks, e.g. alerting when a configuration wasn't reloaded for
+    /// a while.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::{sync::Arc, time::Duration};
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new("foo"));
+    ///
+    /// if rcu.age() > Duration::from_secs(10 * 60) {
+    ///     eprintln!("config not refreshed in 10 minutes");
+    /// }
+    /// ```
+    #[cfg(not(feature = "triomphe"))]
+    pub fn age(&self) -> std::time::Duration {
+        self.last_updated().elapsed()
+    }
+
+    /// Records that a version was just published.
+    #[cfg(not(feature = "triomphe"))]
+    fn touch(&self) {
+        // Concurrent writers may get here out of order
+        self.last_updated
+            .fetch_max(nanos_since_epoch(), Ordering::Relaxed);
+    }
+
+    /// Clones `T`, runs `updater` on `T` and [`write`](Self::write)s `T` if the current
+    /// [generation](Self::generation) is `expected`.
+    ///
+    /// This is useful for optimistic concurrency, where the generation was handed out earlier,
+    /// e.g. as an HTTP `ETag`. Unlike [`update`](Self::update), no writes are lost.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Conflict`] if another version was written after `expected`, including while
+    /// `updater` was running.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `Rcu` is [frozen](Self::freeze) or the [validator](Self::with_validator)
+    /// rejects the new version.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new("foo".to_owned()));
+    /// let (_, generation) = rcu.read_versioned();
+    ///
+    /// assert!(rcu.update_checked(generation, |s| s.push_str(" bar")).is_ok());
+    /// assert!(rcu.update_checked(generation, |s| s.push_str(" baz")).is_err());
+    /// assert_eq!(*rcu.read(), "foo bar");
+    /// ```
+    #[track_caller]
+    pub fn update_checked<F, R>(&self, expected: u64, updater: F) -> Result<(), Conflict>
+    where
+        T: Clone,
+        F: FnOnce(&mut T) -> R,
+    {
+        let conflict = || {
+            #[cfg(feature = "stats")]
+            self.stats.conflict();
+            Conflict {
+                current: self.generation(),
+            }
+        };
+
+        // The version is read before the generation, so it's never newer than `expected`
+        let current = self.read();
+        if self.generation() != expected {
+            return Err(conflict());
+        }
+
+        let mut value = (*current).clone();
+        updater(&mut value);
+
+        let _writing = self
+            .begin_write(&value)
+            .unwrap_or_else(|err| panic!("{err}"));
+
+        // Claim the next generation, so no other checked update for `expected` can succeed
+        let expected = expected as usize;
+        if self
+            .generation
+            .compare_exchange(
+                expected,
+                expected.wrapping_add(1),
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .is_err()
+        {
+            return Err(conflict());
+        }
+
+        // An unchecked write may have happened after the generation was checked, which is already
+        // counted as a conflict
+        self.compare_exchange_ptr(&current, Arc::new(value))
+            .map_err(|_| Conflict {
+                current: self.generation(),
+            })
+    }
+
+    /// Clones `T`, runs `updater` on `T` and [`write`](Self::write)s `T`, merging it with
+    /// versions written concurrently.
+    ///
+    /// If another version was written while `updater` was running, `merge` is called with the
+    /// version `updater` started from (base), the updated version (mine) and the newly written
+    /// version (theirs). The merged value is then written in the same way, so `merge` may be called
+    /// multiple times.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// #[derive(Clone, Debug, PartialEq)]
+    /// struct Counters {
+    ///     a: u32,
+    ///     b: u32,
+    /// }
+    ///
+    /// let rcu = Rcu::new(Arc::new(Counters { a: 0, b: 0 }));
+    ///
+    /// rcu.update_merge(
+    ///     |counters| {
+    ///         // Another writer updates `b` while this one updates `a`
+    ///         rcu.update(|counters| counters.b += 1);
+    ///         counters.a += 1;
+    ///     },
+    ///     |base, mine, theirs| Counters {
+    ///         a: theirs.a + mine.a - base.a,
+    ///         b: theirs.b + mine.b - base.b,
+    ///     },
+    /// );
+    /// assert_eq!(*rcu.read(), Counters { a: 1, b: 1 });
+    /// ```
+    #[track_caller]
+    pub fn update_merge<F, M, R>(&self, updater: F, mut merge: M)
+    where
+        T: Clone,
+        F: FnOnce(&mut T) -> R,
+        M: FnMut(&T, &T, &T) -> T,
+    {
+        let mut base = self.read();
+        let mut value = (*base).clone();
+        updater(&mut value);
+
+        let mut new_value = Arc::new(value);
+        while let Err(mine) = self.compare_exchange(&base, new_value) {
+            let theirs = self.read();
+            new_value = Arc::new(merge(&base, &mine, &theirs));
+            base = theirs;
+        }
+    }
+
+    /// Seals the `Rcu` against further writes.
+    ///
+    /// Afterwards, [`write`](Self::write) and the other writing methods panic, while
+    /// [`try_write`](Self::try_write) and [`try_update`](Self::try_update) return
+    /// [`WriteError::Frozen`]. A
+    /// write which began before this call may still complete.
+    ///
+    /// Reading a frozen `Rcu` doesn't need to keep track of readers, so it's faster. See also
+    /// [`frozen`](Self::frozen).
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new("foo"));
+    /// assert!(!rcu.is_frozen());
+    ///
+    /// rcu.freeze();
+    /// assert!(rcu.is_frozen());
+    /// ```
+    pub fn freeze(&self) {
+        self.state.fetch_or(FROZEN, Ordering::SeqCst);
+    }
+
+    /// Returns `true` if the `Rcu` is [frozen](Self::freeze).
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new("foo"));
+    /// rcu.freeze();
+    /// assert!(rcu.is_frozen());
+    /// ```
+    #[inline]
+    pub fn is_frozen(&self) -> bool {
+        self.state.load(Ordering::Acquire) & FROZEN != 0
+    }
+
+    /// Returns a reference to the version, if the `Rcu` is [frozen](Self::freeze) and no write is
+    /// still in progress.
+    ///
+    /// The version can't change anymore, so the reference lives as long as the `Rcu`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new("foo"));
+    /// assert_eq!(rcu.frozen(), None);
+    ///
+    /// rcu.freeze();
+    /// assert_eq!(rcu.frozen(), Some(&"foo"));
+    /// ```
+    pub fn frozen(&self) -> Option<&T> {
+        // SAFETY: The version is only released when dropping `self`
+        self.frozen_ptr().map(|ptr| unsafe { &*ptr })
+    }
+
+    /// Returns the pointer of the version, if the `Rcu` is frozen and no write is in progress.
+    ///
+    /// No new write can begin then, so the version is only released when dropping `self`.
+    #[inline]
+    fn frozen_ptr(&self) -> Option<*const T> {
+        if self.state.load(Ordering::SeqCst) & !POISONED != FROZEN {
+            return None;
+        }
+        Some(self.ptr.load(Ordering::SeqCst))
+    }
+
+    /// Marks the current version as known to be stale, e.g. because reloading it failed.
+    ///
+    /// [`try_read`](Self::try_read) returns an error until a new version is written or
+    /// [`clear_poison`](Self::clear_poison) is called. Other reads aren't affected.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new("foo"));
+    ///
+    /// rcu.poison();
+    /// assert!(rcu.is_poisoned());
+    /// assert_eq!(*rcu.read(), "foo");
+    /// ```
+    #[track_caller]
+    pub fn poison(&self) {
+        #[cfg(all(feature = "debug-meta", not(feature = "triomphe")))]
+        let mut poisoned_at = self.poisoned_at.lock();
+        self.state.fetch_or(POISONED, Ordering::SeqCst);
+        #[cfg(all(feature = "debug-meta", not(feature = "triomphe")))]
+        {
+            *poisoned_at = Some(core::panic::Location::caller());
+        }
+    }
+
+    /// Returns `true` if the `Rcu` is [poisoned](Self::poison).
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new("foo"));
+    /// assert!(!rcu.is_poisoned());
+    /// ```
+    #[inline]
+    pub fn is_poisoned(&self) -> bool {
+        self.state.load(Ordering::Acquire) & POISONED != 0
+    }
+
+    /// Marks the current version as valid again after [`poison`](Self::poison).
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new("foo"));
+    ///
+    /// rcu.poison();
+    /// rcu.clear_poison();
+    /// assert!(rcu.try_read().is_ok());
+    /// ```
+    #[cfg_attr(
+        not(all(feature = "debug-meta", not(feature = "triomphe"))),
+        allow(unused_variables)
+    )]
+    pub fn clear_poison(&self) {
+        let state = self.state.fetch_and(!POISONED, Ordering::SeqCst);
+        #[cfg(all(feature = "debug-meta", not(feature = "triomphe")))]
+        if state & POISONED != 0 {
+            let mut poisoned_at = self.poisoned_at.lock();
+            // Unless it was poisoned again in the meantime
+            if !self.is_poisoned() {
+                *poisoned_at = None;
+            }
+        }
+    }
+
+    /// Registers a write of `new_value`, unless the `Rcu` is frozen or the validator rejects it.
+    fn begin_write(&self, new_value: &T) -> Result<Writing<'_>, WriteError> {
+        if !self.hooks.validate(new_value) {
+            return Err(WriteError::Rejected);
+        }
+
+        let state = self.state.fetch_add(WRITER, Ordering::SeqCst);
+        let writing = Writing(&self.state);
+        if state & FROZEN != 0 {
+            return Err(WriteError::Frozen);
+        }
+        Ok(writing)
+    }
+}
+
+/// Bit of [`Rcu::state`] which is set when the `Rcu` is frozen
+const FROZEN: usize = 1;
+/// Bit of [`Rcu::state`] which is set when the `Rcu` is poisoned
+const POISONED: usize = 2;
+/// Added to [`Rcu::state`] for each write in progress
+const WRITER: usize = 4;
+
+/// Unregisters a write when dropped
+struct Writing<'a>(&'a AtomicUsize);
+
+impl Drop for Writing<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(WRITER, Ordering::SeqCst);
+    }
+}
+
+/// Returns the instant which [`Rcu::last_updated`] is stored relative to.
+///
+/// It's shared by all `Rcu`s, so each one only stores an offset.
+#[cfg(not(feature = "triomphe"))]
+fn epoch() -> std::time::Instant {
+    static EPOCH: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+    *EPOCH.get_or_init(std::time::Instant::now)
+}
+
+/// Returns the nanoseconds elapsed since [`epoch`].
+#[cfg(not(feature = "triomphe"))]
+fn nanos_since_epoch() -> u64 {
+    // Only saturates after centuries
+    u64::try_from(epoch().elapsed().as_nanos()).unwrap_or(u64::MAX)
+}
+
+/// Lets other threads run while waiting for them.
+#[cfg(not(feature = "triomphe"))]
+fn wait() {
+    std::thread::yield_now();
+}
+
+/// Lets other threads run while waiting for them.
+#[cfg(feature = "triomphe")]
+fn wait() {
+    core::hint::spin_loop();
+}
+
+impl<T: Default> Default for Rcu<T> {
+    /// Creates a new `Rcu<T>`, with the `Default` value for T.
+    fn default() -> Self {
+        Self::new(Arc::new(T::default()))
+    }
+}
+
+impl<T> From<T> for Rcu<T> {
+    /// Creates a new `Rcu<T>` from T.
+    fn from(value: T) -> Self {
+        Self::new(Arc::new(value))
+    }
+}
+
+impl<T, S: Reclaim<T>> From<Rcu<T, S>> for Arc<T> {
+    /// Consumes the `Rcu`, returning the [`Arc`] of the current version, see [`Rcu::into_arc`].
+    fn from(rcu: Rcu<T, S>) -> Self {
+        rcu.into_arc()
+    }
+}
+
+/// Formats the current version.
+///
+/// The alternate mode (`{:#?}`) also includes the [generation](Rcu::generation), the address of the
+/// version, the number of active readers if the `Rcu` has been read, and the
+/// [pinned](Rcu::pin_current) generations if there are any.
+///
+/// # Example
+///
+/// ```
+#[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+#[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+/// use axka_rcu::Rcu;
+/// let rcu = Rcu::new(Arc::new("foo"));
+/// rcu.write(Arc::new("bar"));
+///
+/// assert_eq!(format!("{rcu:?}"), r#"Rcu { data: "bar", .. }"#);
+/// assert!(format!("{rcu:#?}").contains("generation: 1,"));
+/// ```
+impl<T: fmt::Debug, S: Reclaim<T>> fmt::Debug for Rcu<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let alternate = f.alternate();
+        let (data, generation) = self.read_versioned();
+
+        let mut d = f.debug_struct("Rcu");
+        d.field("data", &data);
+        if alternate {
+            d.field("generation", &generation);
+            d.field("ptr", &Arc::as_ptr(&data));
+            if let Some(readers) = self.readers.count() {
+                d.field("readers", &readers);
+            }
+            #[cfg(all(feature = "debug-meta", not(feature = "triomphe")))]
+            if let Some(meta) = self.version_meta(&data) {
+                d.field("meta", &meta);
+            }
+            #[cfg(all(feature = "debug-meta", not(feature = "triomphe")))]
+            if let Some(origin) = self.version_origin(&data) {
+                d.field("origin", &origin);
+            }
+            #[cfg(all(feature = "debug-meta", not(feature = "triomphe")))]
+            if let Some(poisoned_at) = self.poisoned_at() {
+                d.field("poisoned_at", &poisoned_at);
+            }
+            let pinned = self.pinned_generations();
+            if !pinned.is_empty() {
+                d.field("pinned", &pinned);
+            }
+            if let Some(staged) = self.read_staged() {
+                d.field("staged", &staged);
+            }
+        }
         d.finish_non_exhaustive()
     }
 }
 
+// SAFETY: The versions are shared like `Arc<T>`s, which are `Send` if `T` is `Send` and `Sync`,
+// and `S` is moved and dropped along with the `Rcu`
+unsafe impl<T: Send + Sync, S: Send> Send for Rcu<T, S> {}
+// SAFETY: The versions are shared like `Arc<T>`s, and any thread which writes hands the replaced
+// versions to `S` through a shared reference
+unsafe impl<T: Send + Sync, S: Sync> Sync for Rcu<T, S> {}
+
+// Writes never leave a partially updated version behind, see "Unwind safety" above
+impl<T: RefUnwindSafe, S: UnwindSafe> UnwindSafe for Rcu<T, S> {}
+impl<T: RefUnwindSafe, S: RefUnwindSafe> RefUnwindSafe for Rcu<T, S> {}
+
 /// These tests make sure dropping is predictable and that all versions get dropped
 #[cfg(test)]
 mod tests {
@@ -316,97 +1976,491 @@ mod tests {
         Drop(usize),
     }
 
-    #[derive(Clone, Default, Debug)]
-    struct Events(Arc<Mutex<(Vec<Event>, usize)>>);
-    impl Events {
-        fn next_version_number(&self) -> usize {
-            let mut inner = self.0.lock().unwrap();
-            let version_number = inner.1;
-            inner.1 += 1;
-            version_number
-        }
-        fn push(&self, event: Event) {
-            self.0.lock().unwrap().0.push(event)
-        }
-        #[track_caller]
-        fn assert_all_are_dropped(&self) {
-            let mut living_versions = HashSet::new();
+    #[derive(Clone, Default, Debug)]
+    struct Events(Arc<Mutex<(Vec<Event>, usize)>>);
+    impl Events {
+        fn next_version_number(&self) -> usize {
+            let mut inner = self.0.lock().unwrap();
+            let version_number = inner.1;
+            inner.1 += 1;
+            version_number
+        }
+        fn push(&self, event: Event) {
+            self.0.lock().unwrap().0.push(event)
+        }
+        #[track_caller]
+        fn assert_all_are_dropped(&self) {
+            let mut living_versions = HashSet::new();
+
+            let inner = self.0.lock().unwrap();
+            for event in inner.0.iter() {
+                match event {
+                    Event::Initialize(version) => living_versions.insert(version),
+                    Event::Clone { from: _, to } => living_versions.insert(to),
+                    Event::Drop(version) => living_versions.remove(&version),
+                };
+            }
+
+            assert!(
+                living_versions.is_empty(),
+                "Still living: {:?}",
+                living_versions
+            );
+        }
+    }
+
+    struct Version {
+        events: Events,
+        version_number: usize,
+        data: &'static str,
+    }
+    impl Version {
+        fn new(events: Events, data: &'static str) -> Self {
+            let version_number = events.next_version_number();
+            events.push(Event::Initialize(version_number));
+
+            Self {
+                events,
+                version_number,
+                data,
+            }
+        }
+    }
+    impl Drop for Version {
+        fn drop(&mut self) {
+            if std::thread::panicking() {
+                // Would otherwise also panic about a poisoning error
+                return;
+            }
+            let mut events = self.events.0.lock().unwrap();
+
+            events.0.push(Event::Drop(self.version_number));
+        }
+    }
+    impl Clone for Version {
+        fn clone(&self) -> Self {
+            let new_version_number = self.events.next_version_number();
+            self.events.push(Event::Clone {
+                from: self.version_number,
+                to: new_version_number,
+            });
+            Self {
+                events: self.events.clone(),
+                version_number: new_version_number,
+                data: self.data,
+            }
+        }
+    }
+
+    /// Counts how often each [`Counted`] value was dropped
+    #[derive(Default)]
+    struct DropCounts(Mutex<Vec<usize>>);
+    impl DropCounts {
+        #[track_caller]
+        fn assert_dropped_once(&self) {
+            let counts = self.0.lock().unwrap();
+            for (id, &count) in counts.iter().enumerate() {
+                assert_eq!(count, 1, "value {id} was dropped {count} times");
+            }
+        }
+    }
+
+    /// A value which records its drops in [`DropCounts`]
+    struct Counted {
+        counts: Arc<DropCounts>,
+        id: usize,
+        n: usize,
+    }
+    impl Counted {
+        fn new(counts: &Arc<DropCounts>, n: usize) -> Self {
+            let mut ids = counts.0.lock().unwrap();
+            ids.push(0);
+            Self {
+                counts: counts.clone(),
+                id: ids.len() - 1,
+                n,
+            }
+        }
+        /// Must be called while the value is read, so a use after free is caught
+        #[track_caller]
+        fn assert_alive(&self) {
+            let count = self.counts.0.lock().unwrap()[self.id];
+            assert_eq!(count, 0, "value {} was dropped while it was read", self.id);
+        }
+    }
+    impl Clone for Counted {
+        fn clone(&self) -> Self {
+            Self::new(&self.counts, self.n)
+        }
+    }
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            let mut counts = self.counts.0.lock().unwrap_or_else(|err| err.into_inner());
+            counts[self.id] += 1;
+        }
+    }
+
+    /// The number of iterations each thread of a stress test runs
+    const ITERATIONS: usize = if cfg!(miri) { 10 } else { 1000 };
+
+    #[test]
+    fn test_empty_events() {
+        let events = Events::default();
+
+        assert_eq!(events.0.lock().unwrap().0, vec![]);
+    }
+
+    #[test]
+    fn test_simple() {
+        let events = Events::default();
+
+        let rcu = Rcu::new(Arc::new(Version::new(events.clone(), "first version")));
+
+        rcu.write(Arc::new(Version::new(events.clone(), "second version")));
+
+        drop(rcu);
+
+        assert_eq!(
+            events.0.lock().unwrap().0,
+            vec![
+                Event::Initialize(0),
+                Event::Initialize(1),
+                Event::Drop(0),
+                Event::Drop(1)
+            ]
+        );
+        events.assert_all_are_dropped();
+    }
+
+    #[test]
+    fn test_read() {
+        let events = Events::default();
+
+        let rcu = Rcu::new(Arc::new(Version::new(events.clone(), "first version")));
+
+        let first_ver = rcu.read();
+
+        rcu.write(Arc::new(Version::new(events.clone(), "second version")));
+
+        drop(rcu);
+        drop(first_ver);
+
+        assert_eq!(
+            events.0.lock().unwrap().0,
+            vec![
+                Event::Initialize(0),
+                Event::Initialize(1),
+                Event::Drop(1),
+                Event::Drop(0),
+            ]
+        );
+        events.assert_all_are_dropped();
+    }
+
+    #[test]
+    fn test_map_read() {
+        let events = Events::default();
+
+        let rcu = Rcu::new(Arc::new(Version::new(events.clone(), "first version")));
+
+        let data = rcu.map_read(|version| &version.data);
+
+        rcu.write(Arc::new(Version::new(events.clone(), "second version")));
+
+        assert_eq!(*data, "first version");
+        drop(data);
+        drop(rcu);
+
+        assert_eq!(
+            events.0.lock().unwrap().0,
+            vec![
+                Event::Initialize(0),
+                Event::Initialize(1),
+                Event::Drop(0),
+                Event::Drop(1),
+            ]
+        );
+        events.assert_all_are_dropped();
+    }
+
+    #[test]
+    fn test_read_guard() {
+        let events = Events::default();
+
+        let rcu = Rcu::new(Arc::new(Version::new(events.clone(), "first version")));
+
+        let guard = rcu.read_guard();
+        rcu.write(Arc::new(Version::new(events.clone(), "second version")));
+        rcu.write(Arc::new(Version::new(events.clone(), "third version")));
+        assert_eq!(guard.data, "first version");
+
+        drop(guard);
+        rcu.write(Arc::new(Version::new(events.clone(), "fourth version")));
+
+        drop(rcu);
+
+        assert_eq!(
+            events.0.lock().unwrap().0,
+            vec![
+                Event::Initialize(0),
+                Event::Initialize(1),
+                Event::Initialize(2),
+                Event::Initialize(3),
+                Event::Drop(0),
+                Event::Drop(1),
+                Event::Drop(2),
+                Event::Drop(3),
+            ]
+        );
+        events.assert_all_are_dropped();
+    }
+
+    #[test]
+    fn test_update() {
+        let events = Events::default();
+
+        let rcu = Rcu::new(Arc::new(Version::new(events.clone(), "first version")));
+
+        rcu.update(|version| version.data = "modified first version");
+
+        drop(rcu);
+
+        assert_eq!(
+            events.0.lock().unwrap().0,
+            vec![
+                Event::Initialize(0),
+                Event::Clone { from: 0, to: 1 },
+                Event::Drop(0),
+                Event::Drop(1),
+            ]
+        );
+        events.assert_all_are_dropped();
+    }
+
+    #[test]
+    fn test_compare_exchange() {
+        let events = Events::default();
+
+        let rcu = Rcu::new(Arc::new(Version::new(events.clone(), "first version")));
+
+        let first_ver = rcu.read();
+        assert!(rcu
+            .compare_exchange(
+                &first_ver,
+                Arc::new(Version::new(events.clone(), "second version"))
+            )
+            .is_ok());
+        let rejected = rcu
+            .compare_exchange(
+                &first_ver,
+                Arc::new(Version::new(events.clone(), "third version")),
+            )
+            .unwrap_err();
+
+        drop(rejected);
+        drop(first_ver);
+        drop(rcu);
+
+        assert_eq!(
+            events.0.lock().unwrap().0,
+            vec![
+                Event::Initialize(0),
+                Event::Initialize(1),
+                Event::Initialize(2),
+                Event::Drop(2),
+                Event::Drop(0),
+                Event::Drop(1),
+            ]
+        );
+        events.assert_all_are_dropped();
+    }
+
+    #[test]
+    fn test_failed_compare_exchange_keeps_generation() {
+        let rcu = Rcu::new(Arc::new(0));
+
+        let old = rcu.read();
+        rcu.write(Arc::new(1));
+        assert_eq!(rcu.generation(), 1);
 
-            let inner = self.0.lock().unwrap();
-            for event in inner.0.iter() {
-                match event {
-                    Event::Initialize(version) => living_versions.insert(version),
-                    Event::Clone { from: _, to } => living_versions.insert(to),
-                    Event::Drop(version) => living_versions.remove(&version),
-                };
-            }
+        assert!(rcu.compare_exchange(&old, Arc::new(2)).is_err());
+        assert_eq!(rcu.generation(), 1);
 
-            assert!(
-                living_versions.is_empty(),
-                "Still living: {:?}",
-                living_versions
-            );
-        }
+        assert!(rcu.compare_exchange(&rcu.read(), Arc::new(3)).is_ok());
+        assert_eq!(rcu.generation(), 2);
     }
 
-    struct Version {
-        events: Events,
-        version_number: usize,
-        data: &'static str,
+    #[test]
+    fn test_deferred() {
+        let events = Events::default();
+
+        let rcu = Rcu::with_reclaim(
+            Arc::new(Version::new(events.clone(), "first version")),
+            Deferred::new(),
+        );
+
+        rcu.write(Arc::new(Version::new(events.clone(), "second version")));
+        rcu.write(Arc::new(Version::new(events.clone(), "third version")));
+
+        assert_eq!(
+            events.0.lock().unwrap().0,
+            vec![
+                Event::Initialize(0),
+                Event::Initialize(1),
+                Event::Initialize(2),
+            ]
+        );
+
+        rcu.reclaimer().flush();
+        drop(rcu);
+
+        assert_eq!(
+            events.0.lock().unwrap().0,
+            vec![
+                Event::Initialize(0),
+                Event::Initialize(1),
+                Event::Initialize(2),
+                Event::Drop(0),
+                Event::Drop(1),
+                Event::Drop(2),
+            ]
+        );
+        events.assert_all_are_dropped();
     }
-    impl Version {
-        fn new(events: Events, data: &'static str) -> Self {
-            let version_number = events.next_version_number();
-            events.push(Event::Initialize(version_number));
 
-            Self {
-                events,
-                version_number,
-                data,
+    #[test]
+    fn test_drop_with_read_guard_across_write() {
+        #[derive(Clone, Default)]
+        struct Reclaimed(Arc<Mutex<Vec<&'static str>>>);
+        impl RcuHooks<Version> for Reclaimed {
+            fn on_reclaim(&self, version: &Arc<Version>) {
+                self.0.lock().unwrap().push(version.data);
             }
         }
+
+        let events = Events::default();
+        let reclaimed = Reclaimed::default();
+
+        let rcu = Rcu::with_reclaim(
+            Arc::new(Version::new(events.clone(), "first version")),
+            Deferred::new(),
+        )
+        .with_hooks(reclaimed.clone());
+
+        let guard = rcu.read_guard();
+        rcu.write(Arc::new(Version::new(events.clone(), "second version")));
+        drop(guard);
+        assert!(reclaimed.0.lock().unwrap().is_empty());
+
+        // The replaced version is handed to the strategy instead of being dropped directly
+        drop(rcu);
+
+        assert_eq!(*reclaimed.0.lock().unwrap(), vec!["first version"]);
+        assert_eq!(
+            events.0.lock().unwrap().0,
+            vec![
+                Event::Initialize(0),
+                Event::Initialize(1),
+                Event::Drop(0),
+                Event::Drop(1),
+            ]
+        );
+        events.assert_all_are_dropped();
     }
-    impl Drop for Version {
-        fn drop(&mut self) {
-            if std::thread::panicking() {
-                // Would otherwise also panic about a poisoning error
-                return;
-            }
-            let mut events = self.events.0.lock().unwrap();
 
-            events.0.push(Event::Drop(self.version_number));
+    #[test]
+    #[cfg(feature = "zeroize")]
+    fn test_zeroized_drop_with_read_guard_across_write() {
+        use zeroize::Zeroize;
+
+        struct Secret {
+            zeroized: Arc<Mutex<Vec<&'static str>>>,
+            data: &'static str,
         }
-    }
-    impl Clone for Version {
-        fn clone(&self) -> Self {
-            let new_version_number = self.events.next_version_number();
-            self.events.push(Event::Clone {
-                from: self.version_number,
-                to: new_version_number,
-            });
-            Self {
-                events: self.events.clone(),
-                version_number: new_version_number,
-                data: self.data,
+        impl Zeroize for Secret {
+            fn zeroize(&mut self) {
+                self.zeroized.lock().unwrap().push(self.data);
+                self.data = "";
             }
         }
+
+        let zeroized = Arc::new(Mutex::new(Vec::new()));
+        let secret = |data| {
+            Arc::new(Secret {
+                zeroized: zeroized.clone(),
+                data,
+            })
+        };
+
+        let rcu = Rcu::with_reclaim(secret("hunter2"), Zeroized::new());
+        let guard = rcu.read_guard();
+        rcu.write(secret("correct horse"));
+        drop(guard);
+        assert!(zeroized.lock().unwrap().is_empty());
+
+        drop(rcu);
+
+        assert_eq!(*zeroized.lock().unwrap(), vec!["hunter2"]);
     }
 
     #[test]
-    fn test_empty_events() {
+    #[cfg(all(feature = "history", not(feature = "triomphe")))]
+    fn test_history() {
         let events = Events::default();
 
-        assert_eq!(events.0.lock().unwrap().0, vec![]);
+        let rcu = Rcu::new(Arc::new(Version::new(events.clone(), "first version")))
+            .with_history(Retention::new().max_count(1));
+
+        rcu.write(Arc::new(Version::new(events.clone(), "second version")));
+        rcu.write(Arc::new(Version::new(events.clone(), "third version")));
+
+        // The first version was evicted by the second write
+        assert_eq!(
+            events.0.lock().unwrap().0,
+            vec![
+                Event::Initialize(0),
+                Event::Initialize(1),
+                Event::Initialize(2),
+                Event::Drop(0),
+            ]
+        );
+        assert_eq!(rcu.history()[0].version().data, "second version");
+
+        drop(rcu);
+
+        assert_eq!(
+            events.0.lock().unwrap().0,
+            vec![
+                Event::Initialize(0),
+                Event::Initialize(1),
+                Event::Initialize(2),
+                Event::Drop(0),
+                Event::Drop(1),
+                Event::Drop(2),
+            ]
+        );
     }
 
     #[test]
-    fn test_simple() {
+    #[cfg(all(feature = "rt", not(feature = "triomphe")))]
+    fn test_realtime() {
         let events = Events::default();
 
-        let rcu = Rcu::new(Arc::new(Version::new(events.clone(), "first version")));
+        let rcu = Rcu::new(Arc::new(Version::new(events.clone(), "first version"))).realtime();
 
         rcu.write(Arc::new(Version::new(events.clone(), "second version")));
+        rcu.write(Arc::new(Version::new(events.clone(), "third version")));
+
+        assert_eq!(
+            events.0.lock().unwrap().0,
+            vec![
+                Event::Initialize(0),
+                Event::Initialize(1),
+                Event::Initialize(2),
+            ]
+        );
 
+        rcu.flush_deferred();
         drop(rcu);
 
         assert_eq!(
@@ -414,25 +2468,29 @@ mod tests {
             vec![
                 Event::Initialize(0),
                 Event::Initialize(1),
+                Event::Initialize(2),
                 Event::Drop(0),
-                Event::Drop(1)
+                Event::Drop(1),
+                Event::Drop(2),
             ]
         );
         events.assert_all_are_dropped();
     }
 
     #[test]
-    fn test_read() {
+    fn test_freeze() {
         let events = Events::default();
 
         let rcu = Rcu::new(Arc::new(Version::new(events.clone(), "first version")));
+        rcu.freeze();
 
-        let first_ver = rcu.read();
-
-        rcu.write(Arc::new(Version::new(events.clone(), "second version")));
+        let guard = rcu.read_guard();
+        let result = rcu.try_write(Arc::new(Version::new(events.clone(), "second version")));
+        assert_eq!(result, Err(WriteError::Frozen));
+        assert_eq!(guard.data, "first version");
+        drop(guard);
 
         drop(rcu);
-        drop(first_ver);
 
         assert_eq!(
             events.0.lock().unwrap().0,
@@ -447,27 +2505,67 @@ mod tests {
     }
 
     #[test]
-    fn test_update() {
+    fn test_on_write() {
         let events = Events::default();
 
         let rcu = Rcu::new(Arc::new(Version::new(events.clone(), "first version")));
+        let seen = Arc::new(Mutex::new(Vec::new()));
 
-        rcu.update(|version| version.data = "modified first version");
+        let seen2 = Arc::clone(&seen);
+        let handle = rcu.on_write(move |version| seen2.lock().unwrap().push(version.data));
+        rcu.write(Arc::new(Version::new(events.clone(), "second version")));
+        drop(handle);
+        rcu.write(Arc::new(Version::new(events.clone(), "third version")));
 
         drop(rcu);
 
+        assert_eq!(*seen.lock().unwrap(), ["second version"]);
         assert_eq!(
             events.0.lock().unwrap().0,
             vec![
                 Event::Initialize(0),
-                Event::Clone { from: 0, to: 1 },
+                Event::Initialize(1),
                 Event::Drop(0),
+                Event::Initialize(2),
                 Event::Drop(1),
+                Event::Drop(2),
             ]
         );
         events.assert_all_are_dropped();
     }
 
+    #[test]
+    fn test_unwind_safe() {
+        fn assert_unwind_safe<T: UnwindSafe + RefUnwindSafe>() {}
+        assert_unwind_safe::<Rcu<Version>>();
+        assert_unwind_safe::<ReadGuard<'_, Version>>();
+        assert_unwind_safe::<MappedGuard<str>>();
+
+        let events = Events::default();
+
+        let rcu = Rcu::new(Arc::new(Version::new(events.clone(), "first version")));
+        let result = std::panic::catch_unwind(|| {
+            rcu.update(|version| {
+                version.data = "modified first version";
+                panic!("updater panicked");
+            })
+        });
+        assert!(result.is_err());
+        assert_eq!(rcu.read().data, "first version");
+
+        drop(rcu);
+
+        // The clone is dropped while unwinding, which isn't recorded
+        assert_eq!(
+            events.0.lock().unwrap().0,
+            vec![
+                Event::Initialize(0),
+                Event::Clone { from: 0, to: 1 },
+                Event::Drop(0),
+            ]
+        );
+    }
+
     #[test]
     fn test_multiple() {
         let events = Events::default();
@@ -534,4 +2632,298 @@ mod tests {
         );
         events.assert_all_are_dropped();
     }
+
+    #[test]
+    fn test_concurrent_reads_and_writes() {
+        let events = Events::default();
+
+        let rcu = Arc::new(Rcu::new(Arc::new(Version::new(
+            events.clone(),
+            "first version",
+        ))));
+
+        let threads: Vec<_> = (0..4)
+            .map(|i| {
+                let events = events.clone();
+                let rcu = rcu.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..if cfg!(miri) { 10 } else { 1000 } {
+                        if i % 2 == 0 {
+                            rcu.write(Arc::new(Version::new(events.clone(), "new version")));
+                        } else {
+                            assert!(!rcu.read().data.is_empty());
+                            assert!(!rcu.read_guard().data.is_empty());
+                        }
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        drop(rcu);
+        events.assert_all_are_dropped();
+    }
+
+    #[test]
+    fn test_arena_concurrent_reads_and_writes() {
+        let events = Events::default();
+        let arena: &'static Arena<Version, 8> = Box::leak(Box::new(Arena::new()));
+
+        let rcu = Arc::new(
+            Rcu::new_in_arena(arena, Version::new(events.clone(), "first version"))
+                .unwrap_or_else(|_| unreachable!()),
+        );
+
+        let threads: Vec<_> = (0..4)
+            .map(|i| {
+                let events = events.clone();
+                let rcu = rcu.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..if cfg!(miri) { 10 } else { 1000 } {
+                        if i % 2 == 0 {
+                            let mut version = Version::new(events.clone(), "new version");
+                            while let Err(errors::ArenaFull(rejected)) = rcu.write(version) {
+                                version = rejected;
+                            }
+                        } else {
+                            assert!(!rcu.read().data.is_empty());
+                        }
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        drop(rcu);
+        events.assert_all_are_dropped();
+        assert_eq!(arena.available(), 8);
+    }
+
+    #[test]
+    fn test_readers_stress() {
+        let counts = Arc::new(DropCounts::default());
+        let rcu = Arc::new(Rcu::new(Arc::new(Counted::new(&counts, 0))));
+
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                let counts = counts.clone();
+                let rcu = rcu.clone();
+                std::thread::spawn(move || {
+                    for n in 0..ITERATIONS {
+                        match i % 4 {
+                            0 | 1 => rcu.write(Arc::new(Counted::new(&counts, n))),
+                            2 => {
+                                let guard = rcu.read_guard();
+                                guard.assert_alive();
+                                std::thread::yield_now();
+                                guard.assert_alive();
+                            }
+                            _ => {
+                                rcu.read().assert_alive();
+                                if n % 16 == 0 {
+                                    rcu.synchronize();
+                                }
+                            }
+                        }
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        rcu.synchronize();
+        assert_eq!(rcu.readers.pending(), 0);
+        drop(rcu);
+        counts.assert_dropped_once();
+    }
+
+    #[test]
+    fn test_group_stress() {
+        let counts = Arc::new(DropCounts::default());
+        let rcus: Arc<[Rcu<Counted>; 2]> = Arc::new(core::array::from_fn(|i| {
+            Rcu::new(Arc::new(Counted::new(&counts, i)))
+        }));
+
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                let counts = counts.clone();
+                let rcus = rcus.clone();
+                std::thread::spawn(move || {
+                    let rcu = &rcus[i % 2];
+                    for n in 0..ITERATIONS {
+                        match i % 4 {
+                            0 | 1 => rcu.write(Arc::new(Counted::new(&counts, n))),
+                            2 => {
+                                let guard = rcu.read_guard();
+                                guard.assert_alive();
+                                std::thread::yield_now();
+                                guard.assert_alive();
+                            }
+                            _ => {
+                                rcu.read().assert_alive();
+                                if n % 16 == 0 {
+                                    let mut group = RcuGroup::new();
+                                    group.register(&rcus[0]).register(&rcus[1]);
+                                    group.synchronize();
+                                }
+                            }
+                        }
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let mut group = RcuGroup::new();
+        group.register(&rcus[0]).register(&rcus[1]);
+        group.synchronize();
+        for rcu in rcus.iter() {
+            assert_eq!(rcu.readers.pending(), 0);
+        }
+        drop(group);
+        drop(rcus);
+        counts.assert_dropped_once();
+    }
+
+    #[test]
+    fn test_queue_stress() {
+        let counts = Arc::new(DropCounts::default());
+        let rcu = Arc::new(Rcu::new(Arc::new(Counted::new(&counts, 0))));
+
+        let threads: Vec<_> = (0..4)
+            .map(|i| {
+                let rcu = rcu.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..ITERATIONS {
+                        match i % 4 {
+                            0 | 1 => rcu.update_fair(|value| value.n += 1),
+                            2 => rcu.update_priority(|value| value.n += 1),
+                            _ => rcu.read_guard().assert_alive(),
+                        }
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(rcu.read().n, 3 * ITERATIONS);
+        assert_eq!(rcu.queued_updates(), 0);
+        drop(rcu);
+        counts.assert_dropped_once();
+    }
+
+    #[test]
+    fn test_left_right_stress() {
+        let counts = Arc::new(DropCounts::default());
+        let left_right = Arc::new(LeftRight::new(
+            Vec::new(),
+            |copy: &mut Vec<_>, op: &Counted| {
+                copy.push(op.clone());
+            },
+        ));
+
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                let counts = counts.clone();
+                let left_right = left_right.clone();
+                std::thread::spawn(move || {
+                    for n in 0..ITERATIONS {
+                        if i % 4 == 0 {
+                            left_right.write(Counted::new(&counts, n));
+                        } else {
+                            let guard = left_right.read();
+                            if let (Some(first), Some(last)) = (guard.first(), guard.last()) {
+                                first.assert_alive();
+                                last.assert_alive();
+                            }
+                        }
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(left_right.read().len(), 2 * ITERATIONS);
+        drop(left_right);
+        counts.assert_dropped_once();
+    }
+
+    #[test]
+    #[cfg(not(feature = "triomphe"))]
+    fn test_adaptive_stress() {
+        let counts = Arc::new(DropCounts::default());
+        let adaptive = Arc::new(Adaptive::new(Counted::new(&counts, 0)));
+
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                let adaptive = adaptive.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..ITERATIONS {
+                        if i % 2 == 0 {
+                            adaptive.update(|value| value.n += 1);
+                        } else {
+                            adaptive.read().assert_alive();
+                        }
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(adaptive.read().n, 4 * ITERATIONS);
+        drop(adaptive);
+        counts.assert_dropped_once();
+    }
+
+    #[test]
+    fn test_arena_stress() {
+        let counts = Arc::new(DropCounts::default());
+        let arena: &'static Arena<Counted, 8> = Box::leak(Box::new(Arena::new()));
+        let rcu = Arc::new(
+            Rcu::new_in_arena(arena, Counted::new(&counts, 0)).unwrap_or_else(|_| unreachable!()),
+        );
+
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                let counts = counts.clone();
+                let rcu = rcu.clone();
+                std::thread::spawn(move || {
+                    for n in 0..ITERATIONS {
+                        if i % 2 == 0 {
+                            let mut value = Counted::new(&counts, n);
+                            while let Err(errors::ArenaFull(rejected)) = rcu.write(value) {
+                                value = rejected;
+                            }
+                        } else {
+                            let guard = rcu.read();
+                            guard.assert_alive();
+                            std::thread::yield_now();
+                            guard.assert_alive();
+                        }
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        drop(rcu);
+        counts.assert_dropped_once();
+        assert_eq!(arena.available(), 8);
+    }
 }
@@ -0,0 +1,254 @@
+//! Tracking which versions are alive and where they came from
+
+use alloc::{string::String, vec::Vec};
+use core::fmt::{self, Write};
+use std::sync::{Arc, Weak};
+
+use crate::{lock::Lock, Rcu, Reclaim};
+
+/// Records every version published by an `Rcu`, until it's dropped
+pub(crate) struct Tracker<T> {
+    records: Lock<Vec<Record<T>>>,
+}
+
+/// A published version
+///
+/// The `Weak` keeps the address of the version from being reused while the record exists.
+struct Record<T> {
+    version: Weak<T>,
+    generation: u64,
+    base: Option<u64>,
+    replaced: Option<u64>,
+}
+
+// SAFETY: The `Weak` is never upgraded, so no `T` is accessed or dropped through it
+unsafe impl<T> Send for Record<T> {}
+unsafe impl<T> Sync for Record<T> {}
+
+impl<T> Tracker<T> {
+    pub(crate) fn new(initial: &Arc<T>) -> Self {
+        Self {
+            records: Lock::new(alloc::vec![Record {
+                version: Arc::downgrade(initial),
+                generation: 0,
+                base: None,
+                replaced: None,
+            }]),
+        }
+    }
+
+    /// Records that `version` replaced the version at `replaced`, and was derived from the
+    /// version at `base` if it was updated.
+    pub(crate) fn record(
+        &self,
+        version: Weak<T>,
+        generation: u64,
+        replaced: *const T,
+        base: Option<*const T>,
+    ) {
+        let mut records = self.records.lock();
+        // Looked up before pruning, since the replaced version may already be dropped
+        let generation_of = |ptr: *const T| {
+            records
+                .iter()
+                .rfind(|record| core::ptr::eq(record.version.as_ptr(), ptr))
+                .map(|record| record.generation)
+        };
+        let replaced = generation_of(replaced);
+        let base = base.and_then(generation_of);
+
+        records.retain(|record| record.version.strong_count() != 0);
+        records.push(Record {
+            version,
+            generation,
+            base,
+            replaced,
+        });
+    }
+}
+
+impl<T, S: Reclaim<T>> Rcu<T, S> {
+    /// Returns a [`Lineage`] listing the versions of this `Rcu` which are still alive, and where
+    /// they came from.
+    ///
+    /// When memory usage grows, this shows which old versions are still kept alive, e.g. by `Arc`s
+    /// returned by [`read`](Self::read). The generations are the same as
+    /// [`generation`](Self::generation) returns, but may be off while writes happen concurrently.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::sync::Arc;
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new(String::from("foo")));
+    ///
+    /// let first = rcu.read();
+    /// rcu.update(|s| s.push_str(" bar"));
+    /// rcu.write(Arc::new(String::from("baz")));
+    ///
+    /// assert_eq!(
+    ///     rcu.lineage().to_string(),
+    ///     "v0 (1 reference): initial\n\
+    ///      v2 (current, 1 reference): written, replaced v1\n"
+    /// );
+    /// assert!(rcu.lineage().to_dot().starts_with("digraph lineage {"));
+    /// # drop(first);
+    /// ```
+    pub fn lineage(&self) -> Lineage {
+        let current = self.read_guard();
+        let current: *const T = &*current;
+
+        let records = self.lineage.records.lock();
+        let versions = records
+            .iter()
+            .filter(|record| record.version.strong_count() != 0)
+            .map(|record| LineageVersion {
+                generation: record.generation,
+                base: record.base,
+                replaced: record.replaced,
+                strong_count: record.version.strong_count(),
+                is_current: core::ptr::eq(record.version.as_ptr(), current),
+            })
+            .collect();
+        Lineage { versions }
+    }
+}
+
+/// The versions of an [`Rcu`] which are alive, returned by [`Rcu::lineage`]
+///
+/// `Display` lists one version per line, and [`to_dot`](Self::to_dot) returns a Graphviz graph.
+#[derive(Clone, Debug)]
+pub struct Lineage {
+    versions: Vec<LineageVersion>,
+}
+
+impl Lineage {
+    /// Returns the versions, oldest first.
+    pub fn versions(&self) -> &[LineageVersion] {
+        &self.versions
+    }
+
+    /// Returns the lineage as a graph in the DOT language of Graphviz.
+    ///
+    /// Solid edges point from the version an update started from to the updated version, while
+    /// dashed edges point from a version to the version which replaced it. Versions which are no
+    /// longer alive are drawn grey.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph lineage {\n");
+        let is_alive = |generation| self.versions.iter().any(|v| v.generation == generation);
+        let mut dropped = Vec::new();
+
+        for version in &self.versions {
+            let style = if version.is_current {
+                ", style=bold"
+            } else {
+                ""
+            };
+            // Writing to a `String` never fails
+            let _ = writeln!(
+                dot,
+                "    v{0} [label=\"v{0}\\n{1}\"{style}];",
+                version.generation,
+                References(version.strong_count),
+            );
+            if let Some(base) = version.base {
+                let _ = writeln!(dot, "    v{base} -> v{};", version.generation);
+                dropped.push(base);
+            }
+            if let Some(replaced) = version.replaced.filter(|&r| Some(r) != version.base) {
+                let _ = writeln!(
+                    dot,
+                    "    v{replaced} -> v{} [style=dashed];",
+                    version.generation
+                );
+                dropped.push(replaced);
+            }
+        }
+
+        dropped.sort_unstable();
+        dropped.dedup();
+        for generation in dropped.into_iter().filter(|&g| !is_alive(g)) {
+            let _ = writeln!(dot, "    v{generation} [color=grey, fontcolor=grey];");
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl fmt::Display for Lineage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for version in &self.versions {
+            writeln!(f, "{version}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A version listed in a [`Lineage`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LineageVersion {
+    generation: u64,
+    base: Option<u64>,
+    replaced: Option<u64>,
+    strong_count: usize,
+    is_current: bool,
+}
+
+impl LineageVersion {
+    /// Returns the [generation](Rcu::generation) of the version.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Returns the generation of the version this was updated from, or `None` if it was written
+    /// without an update or is the first version.
+    pub fn base(&self) -> Option<u64> {
+        self.base
+    }
+
+    /// Returns the generation of the version this replaced, or `None` if it's the first version.
+    pub fn replaced(&self) -> Option<u64> {
+        self.replaced
+    }
+
+    /// Returns the number of `Arc`s keeping the version alive, including the one of the `Rcu`.
+    pub fn strong_count(&self) -> usize {
+        self.strong_count
+    }
+
+    /// Returns `true` if this was the current version.
+    pub fn is_current(&self) -> bool {
+        self.is_current
+    }
+}
+
+impl fmt::Display for LineageVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "v{} (", self.generation)?;
+        if self.is_current {
+            write!(f, "current, ")?;
+        }
+        write!(f, "{}): ", References(self.strong_count))?;
+
+        match (self.base, self.replaced) {
+            (None, None) => write!(f, "initial"),
+            (None, Some(replaced)) => write!(f, "written, replaced v{replaced}"),
+            (Some(base), Some(replaced)) if base == replaced => write!(f, "updated from v{base}"),
+            (Some(base), Some(replaced)) => {
+                write!(f, "updated from v{base}, replaced v{replaced}")
+            }
+            (Some(base), None) => write!(f, "updated from v{base}"),
+        }
+    }
+}
+
+struct References(usize);
+
+impl fmt::Display for References {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            1 => write!(f, "1 reference"),
+            n => write!(f, "{n} references"),
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! Internal locks
+//!
+//! With the `parking_lot` feature, these are replaced by the locks of `parking_lot`.
+
+#[cfg(not(feature = "parking_lot"))]
+use core::{
+    cell::UnsafeCell,
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+#[cfg(all(feature = "parking_lot", not(feature = "triomphe")))]
+pub(crate) use parking_lot::{Mutex, RwLock, RwLockReadGuard};
+#[cfg(all(not(feature = "parking_lot"), not(feature = "triomphe")))]
+pub(crate) use std_sync::{Mutex, RwLock, RwLockReadGuard};
+
+/// The lock for short critical sections, which works without `std`
+#[cfg(not(feature = "parking_lot"))]
+pub(crate) type Lock<T> = SpinLock<T>;
+/// The lock for short critical sections
+#[cfg(feature = "parking_lot")]
+pub(crate) type Lock<T> = parking_lot::Mutex<T>;
+
+/// A minimal lock which works without `std`, for short critical sections
+#[cfg(not(feature = "parking_lot"))]
+pub(crate) struct SpinLock<T> {
+    locked: AtomicBool,
+    value: UnsafeCell<T>,
+}
+
+// SAFETY: The value is only accessed while holding the lock
+#[cfg(not(feature = "parking_lot"))]
+unsafe impl<T: Send> Send for SpinLock<T> {}
+#[cfg(not(feature = "parking_lot"))]
+unsafe impl<T: Send> Sync for SpinLock<T> {}
+
+#[cfg(not(feature = "parking_lot"))]
+impl<T> SpinLock<T> {
+    pub(crate) const fn new(value: T) -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    pub(crate) fn lock(&self) -> SpinLockGuard<'_, T> {
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+        SpinLockGuard { lock: self }
+    }
+}
+
+#[cfg(not(feature = "parking_lot"))]
+pub(crate) struct SpinLockGuard<'a, T> {
+    lock: &'a SpinLock<T>,
+}
+
+#[cfg(not(feature = "parking_lot"))]
+impl<T> Deref for SpinLockGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: The lock is held
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+#[cfg(not(feature = "parking_lot"))]
+impl<T> DerefMut for SpinLockGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: The lock is held
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+#[cfg(not(feature = "parking_lot"))]
+impl<T> Drop for SpinLockGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.locked.store(false, Ordering::Release);
+    }
+}
+
+/// `std` locks which ignore poisoning, with the same API as the ones of `parking_lot`
+///
+/// Poisoning is ignored since the locked data is only modified in ways which can't be left
+/// half-done.
+#[cfg(all(not(feature = "parking_lot"), not(feature = "triomphe")))]
+mod std_sync {
+    pub(crate) use std::sync::RwLockReadGuard;
+    use std::sync::{MutexGuard, PoisonError, RwLockWriteGuard};
+
+    pub(crate) struct Mutex<T>(std::sync::Mutex<T>);
+
+    impl<T> Mutex<T> {
+        pub(crate) const fn new(value: T) -> Self {
+            Self(std::sync::Mutex::new(value))
+        }
+
+        pub(crate) fn lock(&self) -> MutexGuard<'_, T> {
+            self.0.lock().unwrap_or_else(PoisonError::into_inner)
+        }
+    }
+
+    pub(crate) struct RwLock<T>(std::sync::RwLock<T>);
+
+    impl<T> RwLock<T> {
+        pub(crate) const fn new(value: T) -> Self {
+            Self(std::sync::RwLock::new(value))
+        }
+
+        pub(crate) fn read(&self) -> RwLockReadGuard<'_, T> {
+            self.0.read().unwrap_or_else(PoisonError::into_inner)
+        }
+
+        pub(crate) fn write(&self) -> RwLockWriteGuard<'_, T> {
+            self.0.write().unwrap_or_else(PoisonError::into_inner)
+        }
+    }
+}
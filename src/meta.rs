@@ -0,0 +1,218 @@
+//! Labels, creation times and origins attached to versions for logging
+
+use alloc::{borrow::Cow, vec::Vec};
+use core::{fmt, panic::Location};
+use std::{
+    sync::{Arc, Weak},
+    time::SystemTime,
+};
+
+use crate::{errors::WriteError, lock::Lock, Rcu, Reclaim};
+
+/// The metadata of the versions written by [`Rcu::write_labeled`]
+pub(crate) type Metas<T> = Lock<Vec<Entry<T, Arc<VersionMeta>>>>;
+
+/// Where the versions were written, see [`Rcu::version_origin`]
+pub(crate) type Origins<T> = Lock<Vec<Entry<T, &'static Location<'static>>>>;
+
+/// The metadata of one version
+///
+/// The `Weak` keeps the address of the version from being reused while the entry exists.
+pub(crate) struct Entry<T, M> {
+    version: Weak<T>,
+    meta: M,
+}
+
+// SAFETY: The `Weak` is never upgraded, so no `T` is accessed or dropped through it
+unsafe impl<T, M: Send> Send for Entry<T, M> {}
+unsafe impl<T, M: Sync> Sync for Entry<T, M> {}
+
+/// Metadata attached to a version by [`Rcu::write_labeled`]
+#[derive(Clone, Debug)]
+pub struct VersionMeta {
+    label: Cow<'static, str>,
+    created: SystemTime,
+    origin: &'static Location<'static>,
+}
+
+impl VersionMeta {
+    /// Returns the label of the version.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// Returns when the version was written.
+    pub fn created(&self) -> SystemTime {
+        self.created
+    }
+
+    /// Returns where in the source code the version was written.
+    pub fn origin(&self) -> &'static Location<'static> {
+        self.origin
+    }
+}
+
+impl fmt::Display for VersionMeta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}' written at {}", self.label, self.origin)
+    }
+}
+
+impl<T, S: Reclaim<T>> Rcu<T, S> {
+    /// Like [`write`](Self::write), but attaches a [`VersionMeta`] with `label`, the current time
+    /// and the caller's location to the new version.
+    ///
+    /// The metadata can be looked up with [`version_meta`](Self::version_meta), e.g. so logs can
+    /// say which configuration is being served.
+    ///
+    /// # Panics
+    ///
+    /// See [`write`](Self::write).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::sync::Arc;
+    /// use axka_rcu::Rcu;
+    /// let config = Rcu::new(Arc::new("debug = false"));
+    ///
+    /// config.write_labeled(Arc::new("debug = true"), "canary-2024-06-01");
+    /// let guard = config.read_guard();
+    /// let meta = config.version_meta(&guard).unwrap();
+    /// assert_eq!(meta.label(), "canary-2024-06-01");
+    /// assert_eq!(meta.origin().file(), file!());
+    /// ```
+    #[track_caller]
+    pub fn write_labeled(&self, new_value: Arc<T>, label: impl Into<Cow<'static, str>>) {
+        if let Err(err) = self.try_write_labeled(new_value, label) {
+            panic!("{err}");
+        }
+    }
+
+    /// Like [`try_write`](Self::try_write), but attaches a [`VersionMeta`], see
+    /// [`write_labeled`](Self::write_labeled).
+    ///
+    /// # Errors
+    ///
+    /// See [`try_write`](Self::try_write).
+    #[track_caller]
+    pub fn try_write_labeled(
+        &self,
+        new_value: Arc<T>,
+        label: impl Into<Cow<'static, str>>,
+    ) -> Result<(), WriteError> {
+        let meta = Arc::new(VersionMeta {
+            label: label.into(),
+            created: SystemTime::now(),
+            origin: Location::caller(),
+        });
+
+        // Attached before publishing, so readers of the version always find it
+        let mut metas = self.metas.lock();
+        metas.retain(|entry| entry.version.strong_count() != 0);
+        metas.push(Entry {
+            version: Arc::downgrade(&new_value),
+            meta: Arc::clone(&meta),
+        });
+        drop(metas);
+
+        let written = self.try_write(new_value);
+        if written.is_err() {
+            self.metas
+                .lock()
+                .retain(|entry| !Arc::ptr_eq(&entry.meta, &meta));
+        }
+        written
+    }
+
+    /// Returns the metadata attached to `version` by [`write_labeled`](Self::write_labeled), if
+    /// any.
+    ///
+    /// `version` may be a [`ReadGuard`](crate::ReadGuard), an `Arc` returned by
+    /// [`read`](Self::read) or any other reference to a version of this `Rcu`.
+    pub fn version_meta(&self, version: &T) -> Option<Arc<VersionMeta>> {
+        let metas = self.metas.lock();
+        metas
+            .iter()
+            // The same `Arc` may have been written more than once
+            .rfind(|entry| core::ptr::eq(entry.version.as_ptr(), version))
+            .map(|entry| Arc::clone(&entry.meta))
+    }
+
+    /// Returns the metadata attached to the current version, if any.
+    pub fn current_meta(&self) -> Option<Arc<VersionMeta>> {
+        self.version_meta(&self.read_guard())
+    }
+
+    /// Records that `version` is written by the caller, before it's published.
+    #[track_caller]
+    pub(crate) fn record_origin(&self, version: &Arc<T>) {
+        let mut origins = self.origins.lock();
+        origins.retain(|entry| entry.version.strong_count() != 0);
+        origins.push(Entry {
+            version: Arc::downgrade(version),
+            meta: Location::caller(),
+        });
+    }
+
+    /// Forgets the origin recorded for `version`, which wasn't published after all.
+    pub(crate) fn forget_origin(&self, version: *const T) {
+        let mut origins = self.origins.lock();
+        if let Some(i) = origins
+            .iter()
+            .rposition(|entry| core::ptr::eq(entry.version.as_ptr(), version))
+        {
+            origins.remove(i);
+        }
+    }
+
+    /// Returns where in the source code `version` was written, e.g. by [`write`](Self::write) or
+    /// [`update`](Self::update).
+    ///
+    /// Returns `None` for the first version and for versions which aren't from this `Rcu`. Like
+    /// [`version_meta`](Self::version_meta), `version` may be any reference to a version.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::sync::Arc;
+    /// use axka_rcu::Rcu;
+    /// let config = Rcu::new(Arc::new("debug = false"));
+    /// assert!(config.current_origin().is_none());
+    ///
+    /// config.update(|config| *config = "debug = true");
+    /// let origin = config.current_origin().unwrap();
+    /// assert_eq!((origin.file(), origin.line()), (file!(), line!() - 2));
+    /// ```
+    pub fn version_origin(&self, version: &T) -> Option<&'static Location<'static>> {
+        let origins = self.origins.lock();
+        origins
+            .iter()
+            .rfind(|entry| core::ptr::eq(entry.version.as_ptr(), version))
+            .map(|entry| entry.meta)
+    }
+
+    /// Returns where in the source code the current version was written, see
+    /// [`version_origin`](Self::version_origin).
+    pub fn current_origin(&self) -> Option<&'static Location<'static>> {
+        self.version_origin(&self.read_guard())
+    }
+
+    /// Returns where in the source code the `Rcu` was [poisoned](Self::poison), if it's poisoned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::sync::Arc;
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new("foo"));
+    ///
+    /// rcu.poison();
+    /// assert_eq!(rcu.poisoned_at().unwrap().line(), line!() - 1);
+    /// rcu.write(Arc::new("bar"));
+    /// assert!(rcu.poisoned_at().is_none());
+    /// ```
+    pub fn poisoned_at(&self) -> Option<&'static Location<'static>> {
+        *self.poisoned_at.lock()
+    }
+}
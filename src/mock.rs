@@ -0,0 +1,106 @@
+//! A test double for code which is generic over [`RcuLike`]
+
+use alloc::{collections::VecDeque, vec::Vec};
+use core::fmt;
+
+use crate::{lock::Lock, Arc, RcuLike};
+
+/// An [`RcuLike`] whose reads return a scripted sequence of versions, and which records every
+/// write
+///
+/// Each read moves on to the next scripted version, if there is one. Once the script is used up,
+/// reads return the last scripted or written version. This lets components which react to new
+/// versions be tested without threads and sleeps.
+///
+/// # Example
+///
+/// ```
+#[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+#[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+/// use axka_rcu::{MockRcu, RcuLike};
+///
+/// fn poll_limit(config: &impl RcuLike<u32>) -> u32 {
+///     let limit = *config.read();
+///     if limit > 100 {
+///         config.write(Arc::new(100));
+///     }
+///     limit
+/// }
+///
+/// let config = MockRcu::new(Arc::new(10)).with_script([Arc::new(20), Arc::new(500)]);
+/// assert_eq!(poll_limit(&config), 20);
+/// assert_eq!(poll_limit(&config), 500);
+/// assert_eq!(poll_limit(&config), 100);
+///
+/// assert_eq!(config.reads(), 3);
+/// assert_eq!(config.writes(), [Arc::new(100)]);
+/// ```
+pub struct MockRcu<T> {
+    current: Lock<Arc<T>>,
+    script: Lock<VecDeque<Arc<T>>>,
+    writes: Lock<Vec<Arc<T>>>,
+    reads: Lock<usize>,
+}
+
+impl<T> MockRcu<T> {
+    /// Creates a new `MockRcu` whose reads return `initial` until a version is scripted or
+    /// written.
+    pub fn new(initial: Arc<T>) -> Self {
+        Self {
+            current: Lock::new(initial),
+            script: Lock::new(VecDeque::new()),
+            writes: Lock::new(Vec::new()),
+            reads: Lock::new(0),
+        }
+    }
+
+    /// Scripts `versions` to be returned by the next reads, in order.
+    pub fn with_script<I>(self, versions: I) -> Self
+    where
+        I: IntoIterator<Item = Arc<T>>,
+    {
+        self.script.lock().extend(versions);
+        self
+    }
+
+    /// Scripts `version` to be returned by a read after the already scripted versions.
+    pub fn push_version(&self, version: Arc<T>) {
+        self.script.lock().push_back(version);
+    }
+
+    /// Returns the versions written so far, oldest first.
+    pub fn writes(&self) -> Vec<Arc<T>> {
+        self.writes.lock().clone()
+    }
+
+    /// Returns the number of reads so far.
+    pub fn reads(&self) -> usize {
+        *self.reads.lock()
+    }
+}
+
+impl<T> RcuLike<T> for MockRcu<T> {
+    fn read(&self) -> Arc<T> {
+        *self.reads.lock() += 1;
+        let mut current = self.current.lock();
+        if let Some(next) = self.script.lock().pop_front() {
+            *current = next;
+        }
+        Arc::clone(&current)
+    }
+
+    fn write(&self, new_value: Arc<T>) {
+        self.writes.lock().push(Arc::clone(&new_value));
+        *self.current.lock() = new_value;
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for MockRcu<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("MockRcu");
+        d.field("current", &*self.current.lock());
+        d.field("script", &*self.script.lock());
+        d.field("writes", &*self.writes.lock());
+        d.finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,62 @@
+//! Dropping replaced versions on the blocking pool of a Tokio runtime
+
+use tokio::runtime::Handle;
+
+use crate::{Arc, Reclaim};
+
+/// Drops replaced versions with [`spawn_blocking`](Handle::spawn_blocking) instead of in the
+/// task which replaced them
+///
+/// Dropping a large version, e.g. a big map, can take long enough to stall other tasks on the
+/// same worker thread. Outside of a runtime, versions are dropped right away.
+///
+/// # Example
+///
+/// ```
+#[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+#[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+/// use axka_rcu::{Rcu, SpawnBlocking};
+/// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+///
+/// let rcu = Rcu::with_reclaim(Arc::new(vec![0u8; 1 << 20]), SpawnBlocking::new());
+/// let first = rcu.read();
+/// runtime.block_on(async {
+///     rcu.write(Arc::new(Vec::new()));
+/// });
+///
+/// // Waits for the blocking pool
+/// drop(runtime);
+/// assert_eq!(Arc::strong_count(&first), 1);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SpawnBlocking {
+    handle: Option<Handle>,
+}
+
+impl SpawnBlocking {
+    /// Creates a `SpawnBlocking` which uses the runtime the version is replaced in.
+    pub const fn new() -> Self {
+        Self { handle: None }
+    }
+
+    /// Creates a `SpawnBlocking` which always uses the runtime of `handle`.
+    pub const fn with_handle(handle: Handle) -> Self {
+        Self {
+            handle: Some(handle),
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> Reclaim<T> for SpawnBlocking {
+    fn reclaim(&self, version: Arc<T>) {
+        let handle = match &self.handle {
+            Some(handle) => handle.clone(),
+            None => match Handle::try_current() {
+                Ok(handle) => handle,
+                Err(_) => return drop(version),
+            },
+        };
+        // The version is dropped even if the task is cancelled because the runtime shuts down
+        drop(handle.spawn_blocking(move || drop(version)));
+    }
+}
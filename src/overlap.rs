@@ -0,0 +1,131 @@
+//! Detecting `update` closures which overlap, and thus lose updates
+
+use core::{
+    panic::Location,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{Rcu, Reclaim};
+
+/// What happens when the closures of two [`update`](Rcu::update) calls on the same `Rcu` overlap,
+/// see [`Rcu::detect_overlapping_updates`]
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub enum OnOverlap {
+    /// Panic in the update which started last
+    Panic,
+    /// Call the function with the location of the update which started last, e.g. to log a
+    /// warning
+    Report(fn(&'static Location<'static>)),
+}
+
+/// Counts the running `update` closures
+pub(crate) struct Updates {
+    running: AtomicUsize,
+    on_overlap: Option<OnOverlap>,
+}
+
+impl Updates {
+    pub(crate) const fn new() -> Self {
+        Self {
+            running: AtomicUsize::new(0),
+            on_overlap: None,
+        }
+    }
+
+    /// Marks an `update` closure as running until the returned guard is dropped, and reports if
+    /// another one is running.
+    ///
+    /// Does nothing without debug assertions, or if the check isn't enabled.
+    #[track_caller]
+    pub(crate) fn enter(&self) -> Option<Running<'_>> {
+        if !cfg!(debug_assertions) {
+            return None;
+        }
+        let on_overlap = self.on_overlap?;
+
+        let overlapping = self.running.fetch_add(1, Ordering::AcqRel) != 0;
+        // Created first, so the counter is decremented when panicking
+        let running = Running(&self.running);
+        if overlapping {
+            let location = Location::caller();
+            match on_overlap {
+                OnOverlap::Panic => panic!(
+                    "overlapping Rcu::update calls at {location}, so one of the updates will be \
+                     lost"
+                ),
+                OnOverlap::Report(report) => report(location),
+            }
+        }
+        Some(running)
+    }
+}
+
+pub(crate) struct Running<'a>(&'a AtomicUsize);
+
+impl Drop for Running<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+impl<T, S: Reclaim<T>> Rcu<T, S> {
+    /// Reports when the closures of two [`update`](Self::update),
+    /// [`try_update`](Self::try_update) or [`update_with_old`](Self::update_with_old) calls
+    /// overlap in time, which means that the update which finishes first is overwritten.
+    ///
+    /// This is only checked with debug assertions, so it's meant for catching lost updates in
+    /// tests. Use [`OnOverlap::Report`] to pass them to a logger instead of panicking, e.g.
+    /// `OnOverlap::Report(|location| log::warn!("lost update at {location}"))`. Use
+    /// [`update_retry`](Self::update_retry), [`update_checked`](Self::update_checked) or
+    /// [`update_fair`](Self::update_fair) where updates may run concurrently.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use std::{
+    ///     panic::{catch_unwind, AssertUnwindSafe},
+    ///     sync::Barrier,
+    ///     thread,
+    /// };
+    ///
+    /// use axka_rcu::{OnOverlap, Rcu};
+    ///
+    /// let rcu = Rcu::new(Arc::new(0)).detect_overlapping_updates(OnOverlap::Panic);
+    /// let barrier = Barrier::new(2);
+    ///
+    /// thread::scope(|s| {
+    ///     s.spawn(|| {
+    ///         rcu.update(|n| {
+    ///             barrier.wait();
+    ///             *n += 1;
+    ///             barrier.wait();
+    ///         })
+    ///     });
+    ///
+    ///     barrier.wait();
+    ///     let overlapped = catch_unwind(AssertUnwindSafe(|| rcu.update(|n| *n += 1)));
+    ///     assert_eq!(overlapped.is_err(), cfg!(debug_assertions));
+    ///     barrier.wait();
+    /// });
+    /// # if cfg!(debug_assertions) {
+    /// assert_eq!(*rcu.read(), 1);
+    /// # }
+    /// ```
+    pub fn detect_overlapping_updates(mut self, on_overlap: OnOverlap) -> Self {
+        self.extras.get_mut().updates.on_overlap = Some(on_overlap);
+        self
+    }
+
+    /// Marks an `update` closure as running, see [`Updates::enter`].
+    #[track_caller]
+    pub(crate) fn enter_update(&self) -> Option<Running<'_>> {
+        // The check is only enabled after allocating the extras
+        match self.extras.get() {
+            Some(extras) => extras.updates.enter(),
+            None => None,
+        }
+    }
+}
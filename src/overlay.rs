@@ -0,0 +1,293 @@
+//! Maps which layer small changes over a shared base
+
+use core::{
+    borrow::Borrow,
+    fmt,
+    hash::{BuildHasher, Hash},
+};
+use std::collections::{hash_map::RandomState, HashMap};
+
+use crate::{Arc, MappedGuard, Rcu, RcuMapExt, Reclaim};
+
+/// A map version made of a shared base map and a small overlay of changes on top of it
+///
+/// Cloning an `OverlayMap` only clones the overlay, so with [`Rcu::update`] or [`RcuMapExt`] each
+/// write costs as much as the changes since the last compaction instead of the whole map. Lookups
+/// check the overlay first and then the base. Once the overlay holds
+/// [`compact_at`](Self::with_compact_at) changes, [`RcuMapExt`] writes fold it into a new base,
+/// which clones the base once.
+///
+/// # Example
+///
+/// ```
+/// # use std::{collections::HashMap, sync::Arc};
+/// use axka_rcu::{OverlayMap, Rcu, RcuMapExt};
+/// let base: HashMap<_, _> = (0..100_000).map(|n| (n, n * 2)).collect();
+/// let rcu = Rcu::new(Arc::new(OverlayMap::new(base).with_compact_at(3)));
+///
+/// rcu.insert(1, 10);
+/// assert_eq!(rcu.remove(&2), Some(4));
+/// assert_eq!(rcu.read_with(|map| map.overlay_len()), 2);
+/// assert_eq!(rcu.read().get(&1), Some(&10));
+/// assert_eq!(rcu.read().get(&2), None);
+///
+/// rcu.insert(3, 30);
+/// let map = rcu.read();
+/// assert_eq!(map.overlay_len(), 0);
+/// assert_eq!((map.get(&1), map.get(&3)), (Some(&10), Some(&30)));
+/// assert_eq!(map.len(), 99_999);
+/// ```
+pub struct OverlayMap<K, V, H = RandomState> {
+    base: Arc<HashMap<K, V, H>>,
+    /// The changes to `base`, where `None` is a removed key
+    overlay: HashMap<K, Option<V>, H>,
+    compact_at: usize,
+}
+
+impl<K, V, H: Clone> OverlayMap<K, V, H> {
+    /// The default number of changes after which the overlay is compacted
+    pub const DEFAULT_COMPACT_AT: usize = 1024;
+
+    /// Creates an `OverlayMap` with `base` as its base and an empty overlay.
+    pub fn new(base: HashMap<K, V, H>) -> Self {
+        Self::from_base(Arc::new(base))
+    }
+
+    /// Creates an `OverlayMap` with a base which is shared with other maps.
+    pub fn from_base(base: Arc<HashMap<K, V, H>>) -> Self {
+        Self {
+            overlay: HashMap::with_hasher(base.hasher().clone()),
+            base,
+            compact_at: Self::DEFAULT_COMPACT_AT,
+        }
+    }
+}
+
+impl<K, V, H> OverlayMap<K, V, H> {
+    /// Sets the number of changes in the overlay after which [`RcuMapExt`] writes compact it.
+    pub fn with_compact_at(mut self, compact_at: usize) -> Self {
+        self.compact_at = compact_at;
+        self
+    }
+
+    /// Returns the base map, which doesn't include the changes in the overlay.
+    pub fn base(&self) -> &Arc<HashMap<K, V, H>> {
+        &self.base
+    }
+
+    /// Returns the number of inserted or removed keys in the overlay.
+    pub fn overlay_len(&self) -> usize {
+        self.overlay.len()
+    }
+
+    /// Returns `true` if the overlay holds at least [`compact_at`](Self::with_compact_at) changes.
+    pub fn needs_compaction(&self) -> bool {
+        self.overlay.len() >= self.compact_at
+    }
+}
+
+impl<K: Hash + Eq, V, H: BuildHasher> OverlayMap<K, V, H> {
+    /// Returns a reference to the value corresponding to the key.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.get_key_value(key).map(|(_, value)| value)
+    }
+
+    /// Returns the key-value pair corresponding to the key.
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        match self.overlay.get_key_value(key) {
+            Some((key, value)) => Some((key, value.as_ref()?)),
+            None => self.base.get_key_value(key),
+        }
+    }
+
+    /// Returns `true` if the map contains the key.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Returns the number of keys in the map.
+    ///
+    /// This looks up each key of the overlay in the base.
+    pub fn len(&self) -> usize {
+        self.overlay
+            .iter()
+            .fold(self.base.len(), |len, (key, value)| {
+                match (self.base.contains_key(key), value.is_some()) {
+                    (false, true) => len + 1,
+                    (true, false) => len - 1,
+                    _ => len,
+                }
+            })
+    }
+
+    /// Returns `true` if the map contains no keys.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterates over the key-value pairs of the map in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        let changed = self
+            .overlay
+            .iter()
+            .filter_map(|(key, value)| Some((key, value.as_ref()?)));
+        let unchanged = self
+            .base
+            .iter()
+            .filter(|(key, _)| !self.overlay.contains_key(*key));
+        changed.chain(unchanged)
+    }
+
+    /// Inserts a key-value pair into the overlay.
+    pub fn insert(&mut self, key: K, value: V) {
+        self.overlay.insert(key, Some(value));
+    }
+
+    /// Removes a key, adding a removal to the overlay if the key is in the base.
+    pub fn remove<Q>(&mut self, key: &Q)
+    where
+        K: Borrow<Q> + Clone,
+        Q: ?Sized + Hash + Eq,
+    {
+        match self.base.get_key_value(key) {
+            Some((key, _)) => {
+                self.overlay.insert(key.clone(), None);
+            }
+            None => {
+                self.overlay.remove(key);
+            }
+        }
+    }
+}
+
+impl<K, V, H> OverlayMap<K, V, H>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+    H: BuildHasher + Clone,
+{
+    /// Folds the overlay into a new base.
+    ///
+    /// The base is cloned unless this map is the only one using it.
+    pub fn compact(&mut self) {
+        if self.overlay.is_empty() {
+            return;
+        }
+        let base = Arc::make_mut(&mut self.base);
+        for (key, value) in self.overlay.drain() {
+            match value {
+                Some(value) => base.insert(key, value),
+                None => base.remove(&key),
+            };
+        }
+    }
+}
+
+impl<K: Clone, V: Clone, H: Clone> Clone for OverlayMap<K, V, H> {
+    /// Clones the overlay, sharing the base.
+    fn clone(&self) -> Self {
+        Self {
+            base: Arc::clone(&self.base),
+            overlay: self.overlay.clone(),
+            compact_at: self.compact_at,
+        }
+    }
+}
+
+impl<K, V, H: Clone + Default> Default for OverlayMap<K, V, H> {
+    fn default() -> Self {
+        Self::new(HashMap::default())
+    }
+}
+
+impl<K, V, H: Clone> From<HashMap<K, V, H>> for OverlayMap<K, V, H> {
+    fn from(base: HashMap<K, V, H>) -> Self {
+        Self::new(base)
+    }
+}
+
+impl<K, V, H> fmt::Debug for OverlayMap<K, V, H>
+where
+    K: Hash + Eq + fmt::Debug,
+    V: fmt::Debug,
+    H: BuildHasher,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V, H, S> RcuMapExt<K, V> for Rcu<OverlayMap<K, V, H>, S>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+    H: BuildHasher + Clone,
+    S: Reclaim<OverlayMap<K, V, H>>,
+{
+    fn insert(&self, key: K, value: V) -> Option<V> {
+        self.update_retry(|map| {
+            let old = map.get(&key).cloned();
+            map.insert(key.clone(), value.clone());
+            if map.needs_compaction() {
+                map.compact();
+            }
+            old
+        })
+    }
+
+    fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        if !self.read_with(|map| map.contains_key(key)) {
+            return None;
+        }
+        self.update_retry(|map| {
+            let old = map.get(key).cloned();
+            map.remove(key);
+            if map.needs_compaction() {
+                map.compact();
+            }
+            old
+        })
+    }
+
+    fn get_arc<Q>(&self, key: &Q) -> Option<MappedGuard<V>>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        MappedGuard::try_new(self.read(), |map| map.get(key))
+    }
+
+    fn entry<F, R>(&self, key: K, mut f: F) -> R
+    where
+        F: FnMut(&mut Option<V>) -> R,
+    {
+        self.update_retry(|map| {
+            let mut value = map.get(&key).cloned();
+            let ret = f(&mut value);
+            match value {
+                Some(value) => map.insert(key.clone(), value),
+                None => map.remove(&key),
+            }
+            if map.needs_compaction() {
+                map.compact();
+            }
+            ret
+        })
+    }
+}
@@ -0,0 +1,167 @@
+//! Applying JSON patches to versions
+
+use core::fmt;
+use std::error::Error;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{errors::WriteError, Arc, Rcu, Reclaim};
+
+/// The error returned by [`Rcu::apply_json_patch`]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum JsonPatchError {
+    /// The current version couldn't be converted to JSON
+    Serialize(serde_json::Error),
+    /// The patch is an array, but not a valid JSON Patch
+    InvalidPatch(serde_json::Error),
+    /// An operation of the JSON Patch failed, e.g. a `test` operation or a missing path
+    Patch(json_patch::PatchError),
+    /// The patched JSON couldn't be converted back to `T`
+    Deserialize(serde_json::Error),
+    /// The patched version couldn't be written
+    Write(WriteError),
+}
+
+impl fmt::Display for JsonPatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Serialize(err) => write!(f, "failed to convert the current version: {err}"),
+            Self::InvalidPatch(err) => write!(f, "invalid JSON Patch: {err}"),
+            Self::Patch(err) => write!(f, "failed to apply the patch: {err}"),
+            Self::Deserialize(err) => write!(f, "failed to convert the patched version: {err}"),
+            Self::Write(err) => write!(f, "failed to write the patched version: {err}"),
+        }
+    }
+}
+
+impl Error for JsonPatchError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Serialize(err) | Self::InvalidPatch(err) | Self::Deserialize(err) => Some(err),
+            Self::Patch(err) => Some(err),
+            Self::Write(err) => Some(err),
+        }
+    }
+}
+
+impl<T, S> Rcu<T, S>
+where
+    T: Serialize + DeserializeOwned,
+    S: Reclaim<T>,
+{
+    /// Applies `patch` to the JSON representation of the current version, and writes the result
+    /// as a new version.
+    ///
+    /// An array is applied as a JSON Patch ([RFC 6902]), anything else as a JSON Merge Patch
+    /// ([RFC 7396]). Like [`update_retry`](Self::update_retry), the patch is applied again if
+    /// another version was written concurrently, so no writes are lost.
+    ///
+    /// [RFC 6902]: https://www.rfc-editor.org/rfc/rfc6902
+    /// [RFC 7396]: https://www.rfc-editor.org/rfc/rfc7396
+    ///
+    /// # Errors
+    ///
+    /// See [`JsonPatchError`]. The current version is kept on errors.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::sync::Arc;
+    /// use axka_rcu::Rcu;
+    /// use serde_json::json;
+    ///
+    /// #[derive(serde::Serialize, serde::Deserialize)]
+    /// struct Config {
+    ///     port: u16,
+    ///     hosts: Vec<String>,
+    /// }
+    ///
+    /// let config = Rcu::new(Arc::new(Config { port: 80, hosts: vec![] }));
+    ///
+    /// config.apply_json_patch(&json!({ "port": 8080 })).unwrap();
+    /// config
+    ///     .apply_json_patch(&json!([{ "op": "add", "path": "/hosts/-", "value": "foo" }]))
+    ///     .unwrap();
+    /// assert!(config.apply_json_patch(&json!({ "port": "foo" })).is_err());
+    ///
+    /// let config = config.read();
+    /// assert_eq!((config.port, &*config.hosts), (8080, &["foo".to_owned()][..]));
+    /// ```
+    pub fn apply_json_patch(&self, patch: &Value) -> Result<(), JsonPatchError> {
+        let operations = match patch {
+            Value::Array(_) => {
+                Some(json_patch::Patch::deserialize(patch).map_err(JsonPatchError::InvalidPatch)?)
+            }
+            _ => None,
+        };
+
+        let mut current = self.read();
+        loop {
+            let mut json = serde_json::to_value(&*current).map_err(JsonPatchError::Serialize)?;
+            match &operations {
+                Some(operations) => {
+                    json_patch::patch(&mut json, operations).map_err(JsonPatchError::Patch)?;
+                }
+                None => json_patch::merge(&mut json, patch),
+            }
+            let new_value = Arc::new(T::deserialize(json).map_err(JsonPatchError::Deserialize)?);
+
+            let _writing = self
+                .begin_write(&new_value)
+                .map_err(JsonPatchError::Write)?;
+            if self.claim_generation(&current)
+                && self.compare_exchange_ptr(&current, new_value).is_ok()
+            {
+                return Ok(());
+            }
+            #[cfg(feature = "stats")]
+            self.stats.retry();
+            current = self.read();
+        }
+    }
+}
+
+impl<T: Serialize, S: Reclaim<T>> Rcu<T, S> {
+    /// Returns a JSON Patch ([RFC 6902]) which turns the JSON representation of `old` into the
+    /// one of the current version.
+    ///
+    /// This is a structural diff for any `T` implementing `Serialize`, see also
+    /// [`apply_json_patch`](Self::apply_json_patch).
+    ///
+    /// [RFC 6902]: https://www.rfc-editor.org/rfc/rfc6902
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either version can't be converted to JSON.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::sync::Arc;
+    /// use axka_rcu::Rcu;
+    /// use serde_json::json;
+    ///
+    /// #[derive(Clone, serde::Serialize)]
+    /// struct Config {
+    ///     port: u16,
+    ///     debug: bool,
+    /// }
+    ///
+    /// let config = Rcu::new(Arc::new(Config { port: 80, debug: false }));
+    /// let old = config.read();
+    /// config.update(|config| config.port = 8080);
+    ///
+    /// let diff = config.json_diff_with(&old).unwrap();
+    /// assert_eq!(
+    ///     serde_json::to_value(diff).unwrap(),
+    ///     json!([{ "op": "replace", "path": "/port", "value": 8080 }])
+    /// );
+    /// ```
+    pub fn json_diff_with(&self, old: &T) -> Result<json_patch::Patch, serde_json::Error> {
+        let old = serde_json::to_value(old)?;
+        let current = self.read_with(|current| serde_json::to_value(current))?;
+        Ok(json_patch::diff(&old, &current))
+    }
+}
@@ -0,0 +1,128 @@
+//! Saving versions to and loading them from JSON files
+
+use core::{
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use std::{error::Error, ffi::OsString, fs, io, io::Write, path::Path};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{errors::WriteError, Arc, Rcu, Reclaim};
+
+/// The error returned by [`Rcu::save_to`] and [`Rcu::load_from`]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum PersistError {
+    /// The file couldn't be read or written
+    Io(io::Error),
+    /// The version couldn't be converted to or from JSON
+    Json(serde_json::Error),
+    /// The loaded version couldn't be written
+    Write(WriteError),
+}
+
+impl fmt::Display for PersistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to access file: {err}"),
+            Self::Json(err) => write!(f, "failed to convert version: {err}"),
+            Self::Write(err) => write!(f, "failed to write the loaded version: {err}"),
+        }
+    }
+}
+
+impl Error for PersistError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Json(err) => Some(err),
+            Self::Write(err) => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for PersistError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for PersistError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+impl<T: Serialize, S: Reclaim<T>> Rcu<T, S> {
+    /// Saves the current version to the file at `path` as JSON.
+    ///
+    /// The version is written to a temporary file in the same directory, which is then renamed
+    /// over `path`, so readers of the file never see a partially written version.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PersistError::Json`] if the version can't be converted to JSON and
+    /// [`PersistError::Io`] if the file can't be written.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::sync::Arc;
+    /// use axka_rcu::Rcu;
+    /// # let dir = std::env::temp_dir().join(format!("axka-rcu-persist-{}", std::process::id()));
+    /// # std::fs::create_dir_all(&dir).unwrap();
+    /// # let path = dir.join("ports.json");
+    /// let ports = Rcu::new(Arc::new(vec![80u16, 443]));
+    /// ports.save_to(&path).unwrap();
+    ///
+    /// let loaded = Rcu::new(Arc::new(Vec::<u16>::new()));
+    /// loaded.load_from(&path).unwrap();
+    /// assert_eq!(*loaded.read(), [80, 443]);
+    /// # std::fs::remove_dir_all(&dir).unwrap();
+    /// ```
+    pub fn save_to(&self, path: impl AsRef<Path>) -> Result<(), PersistError> {
+        /// Keeps concurrent saves from sharing a temporary file
+        static SAVES: AtomicUsize = AtomicUsize::new(0);
+
+        let path = path.as_ref();
+        let json = self.read_with(|value| serde_json::to_vec_pretty(value))?;
+
+        let mut temp_name = OsString::from(".");
+        temp_name.push(path.file_name().unwrap_or_default());
+        temp_name.push(format!(
+            ".{}-{}.tmp",
+            std::process::id(),
+            SAVES.fetch_add(1, Ordering::Relaxed)
+        ));
+        let temp_path = path.with_file_name(temp_name);
+
+        let result = (|| {
+            let mut file = fs::File::create(&temp_path)?;
+            file.write_all(&json)?;
+            file.sync_all()?;
+            fs::rename(&temp_path, path)
+        })();
+        if result.is_err() {
+            // The temporary file may not exist, and the original error is more useful
+            let _ = fs::remove_file(&temp_path);
+        }
+        Ok(result?)
+    }
+}
+
+impl<T: DeserializeOwned, S: Reclaim<T>> Rcu<T, S> {
+    /// Loads a version saved by [`save_to`](Self::save_to) from the file at `path`, and
+    /// [writes](Self::try_write) it as a new version.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PersistError::Io`] if the file can't be read, [`PersistError::Json`] if it
+    /// can't be parsed and [`PersistError::Write`] if the version can't be written. The current
+    /// version is kept on errors.
+    pub fn load_from(&self, path: impl AsRef<Path>) -> Result<(), PersistError> {
+        let json = fs::read(path)?;
+        let value = serde_json::from_slice(&json)?;
+        self.try_write(Arc::new(value)).map_err(PersistError::Write)
+    }
+}
@@ -0,0 +1,160 @@
+//! Versions which are kept alive on purpose, and can be listed for diagnostics
+
+use alloc::vec::Vec;
+use core::{fmt, ops::Deref};
+
+use crate::{Arc, Rcu, ReadGuard, Reclaim};
+
+impl<T, S: Reclaim<T>> Rcu<T, S> {
+    /// Returns a [`VersionPin`], which keeps the current version alive and registers its
+    /// [generation](Self::generation) until dropped.
+    ///
+    /// This lets long-running jobs declare which version they're still working on, e.g. "I'm
+    /// still computing on version 41". The pinned generations are listed by
+    /// [`pinned_generations`](Self::pinned_generations) and in the alternate `Debug` output.
+    ///
+    /// # Blocking
+    ///
+    /// The pinned generation always belongs to the version, so like
+    /// [`read_versioned_guard`](Self::read_versioned_guard), this waits while writes are being
+    /// published and may wait for as long as writes keep coming.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new("foo"));
+    ///
+    /// let pin = rcu.pin_current();
+    /// rcu.write(Arc::new("bar"));
+    /// assert_eq!((*pin, pin.generation()), ("foo", 0));
+    /// assert_eq!(rcu.pinned_generations(), [0]);
+    ///
+    /// drop(pin);
+    /// assert!(rcu.pinned_generations().is_empty());
+    /// ```
+    pub fn pin_current(&self) -> VersionPin<'_, T, S> {
+        // Registered while holding the lock, so a barrier either sees the pin or runs before the
+        // version is read
+        let mut pins = self.extras.get_or_alloc().pins.lock();
+        let (guard, generation) = self.read_exact();
+        let version = ReadGuard::to_arc(&guard);
+        drop(guard);
+        pins.push(generation);
+        drop(pins);
+
+        VersionPin {
+            rcu: self,
+            version,
+            generation,
+        }
+    }
+
+    /// Returns the generations of the versions which are [pinned](Self::pin_current), oldest
+    /// first.
+    pub fn pinned_generations(&self) -> Vec<u64> {
+        let mut generations = self
+            .extras
+            .get()
+            .map_or_else(Vec::new, |extras| extras.pins.lock().clone());
+        generations.sort_unstable();
+        generations
+    }
+
+    /// Blocks until `generation` is written and every reader has moved on to it or a newer
+    /// version.
+    ///
+    /// Readers are waited for if they use a [`ReadGuard`](crate::ReadGuard) or a
+    /// [`ReadSession`](crate::ReadSession) created before `generation` was written, see
+    /// [`synchronize`](Self::synchronize), or a [`VersionPin`] of an older generation. `Arc`s
+    /// returned by [`read`](Self::read) aren't tracked, so they must not be used for data which is
+    /// deleted after the barrier.
+    ///
+    /// This tells when it's safe to delete data which is only referenced by older versions, e.g.
+    /// files on disk.
+    ///
+    /// # Deadlocks
+    ///
+    /// This deadlocks if the current thread holds a guard, a session or a pin of an older
+    /// generation.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Arc::new(Rcu::new(Arc::new("old.db")));
+    ///
+    /// let rcu2 = rcu.clone();
+    /// let job = std::thread::spawn(move || {
+    ///     let pin = rcu2.pin_current();
+    ///     std::thread::sleep(std::time::Duration::from_millis(10));
+    ///     *pin
+    /// });
+    ///
+    /// rcu.write(Arc::new("new.db"));
+    /// rcu.barrier(1);
+    /// // "old.db" can be deleted now
+    /// # job.join().unwrap();
+    /// ```
+    pub fn barrier(&self, generation: u64) {
+        while self.generation() < generation {
+            crate::wait();
+        }
+        self.synchronize();
+        let Some(extras) = self.extras.get() else {
+            return;
+        };
+        while extras.pins.lock().iter().any(|&pinned| pinned < generation) {
+            crate::wait();
+        }
+    }
+}
+
+/// Keeps a version alive and registers its generation, created by [`Rcu::pin_current`]
+pub struct VersionPin<'a, T, S> {
+    rcu: &'a Rcu<T, S>,
+    version: Arc<T>,
+    generation: u64,
+}
+
+impl<T, S> VersionPin<'_, T, S> {
+    /// Returns the [generation](Rcu::generation) of the pinned version.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Returns the [`Arc`] of the pinned version.
+    pub fn version(&self) -> &Arc<T> {
+        &self.version
+    }
+}
+
+impl<T, S> Deref for VersionPin<'_, T, S> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.version
+    }
+}
+
+impl<T, S> Drop for VersionPin<'_, T, S> {
+    fn drop(&mut self) {
+        let mut pins = self.rcu.extras.get_or_alloc().pins.lock();
+        if let Some(i) = pins.iter().position(|&other| other == self.generation) {
+            pins.swap_remove(i);
+        }
+    }
+}
+
+impl<T: fmt::Debug, S> fmt::Debug for VersionPin<'_, T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("VersionPin");
+        d.field("data", &self.version);
+        d.field("generation", &self.generation);
+        d.finish()
+    }
+}
@@ -0,0 +1,283 @@
+//! Serialized updates which are served in the order they arrived, with a priority lane
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{
+    errors::{Conflict, TransitionError, WriteError},
+    Arc, Rcu, Reclaim,
+};
+
+/// A ticket lock: every updater takes the next ticket and waits until it's served
+pub(crate) struct WriteQueue {
+    next_ticket: AtomicUsize,
+    now_serving: AtomicUsize,
+    /// The number of priority updaters, which hold back updaters that haven't started yet
+    priority: AtomicUsize,
+}
+
+impl WriteQueue {
+    pub(crate) const fn new() -> Self {
+        Self {
+            next_ticket: AtomicUsize::new(0),
+            now_serving: AtomicUsize::new(0),
+            priority: AtomicUsize::new(0),
+        }
+    }
+
+    /// Waits until every updater which arrived earlier and every priority updater is done.
+    pub(crate) fn enter(&self) -> Ticket<'_> {
+        let ticket = self.next_ticket.fetch_add(1, Ordering::Relaxed);
+        // Serve the ticket first, so a failed priority check doesn't let others cut in line
+        let ticket = loop {
+            if self.now_serving.load(Ordering::Acquire) == ticket {
+                break Ticket { queue: self };
+            }
+            crate::wait();
+        };
+        while self.priority.load(Ordering::SeqCst) != 0 {
+            crate::wait();
+        }
+        ticket
+    }
+
+    /// Holds back updaters which haven't started yet until the returned guard is dropped.
+    fn enter_priority(&self) -> Priority<'_> {
+        self.priority.fetch_add(1, Ordering::SeqCst);
+        Priority { queue: self }
+    }
+
+    /// Returns the number of updaters holding a ticket, including the one being served.
+    fn len(&self) -> usize {
+        let now_serving = self.now_serving.load(Ordering::Relaxed);
+        self.next_ticket
+            .load(Ordering::Relaxed)
+            .wrapping_sub(now_serving)
+    }
+}
+
+/// Serves the next ticket when dropped, even if the updater panicked
+pub(crate) struct Ticket<'a> {
+    queue: &'a WriteQueue,
+}
+
+impl Drop for Ticket<'_> {
+    fn drop(&mut self) {
+        self.queue.now_serving.fetch_add(1, Ordering::Release);
+    }
+}
+
+/// Lets queued updaters start when dropped
+struct Priority<'a> {
+    queue: &'a WriteQueue,
+}
+
+impl Drop for Priority<'_> {
+    fn drop(&mut self) {
+        self.queue.priority.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl<T, S: Reclaim<T>> Rcu<T, S> {
+    /// Like [`update_retry`](Self::update_retry), but waits for earlier `update_fair` calls to
+    /// finish first.
+    ///
+    /// Fair updaters are served in the order they arrived, so a burst of updaters can't starve a
+    /// particular one, and `updater` is only called more than once if the version is replaced by
+    /// a method other than `update_fair`. The cost is that updaters wait in line instead of
+    /// running concurrently.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Arc::new(Rcu::new(Arc::new(0)));
+    ///
+    /// let threads: Vec<_> = (0..4).map(|_| {
+    ///     let rcu = rcu.clone();
+    ///     std::thread::spawn(move || {
+    ///         for _ in 0..100 {
+    ///             rcu.update_fair(|n| *n += 1);
+    ///         }
+    ///     })
+    /// }).collect();
+    /// for thread in threads {
+    ///     thread.join().unwrap();
+    /// }
+    ///
+    /// assert_eq!(*rcu.read(), 400);
+    /// assert_eq!(rcu.queued_updates(), 0);
+    /// ```
+    #[track_caller]
+    pub fn update_fair<F, R>(&self, updater: F) -> R
+    where
+        T: Clone,
+        F: FnMut(&mut T) -> R,
+    {
+        #[cfg(all(feature = "stats", not(feature = "triomphe")))]
+        let start = std::time::Instant::now();
+        let _ticket = self.extras.get_or_alloc().queue.enter();
+        #[cfg(all(feature = "stats", not(feature = "triomphe")))]
+        self.stats.queue_wait(start);
+
+        self.update_retry(updater)
+    }
+
+    /// Like [`update_fair`](Self::update_fair), but doesn't wait in line.
+    ///
+    /// Queued `update_fair` calls don't start until this returns, and are then applied on top of
+    /// the version written by this. This is meant for urgent changes, e.g. flipping a kill switch,
+    /// which can't wait behind a backlog of routine updates.
+    ///
+    /// The priority lane doesn't preempt the `update_fair` call which is already running, if any.
+    /// The two race like [`update_retry`](Self::update_retry) calls, so `updater` may be called
+    /// again once per version written by that call and by other methods in the meantime. The queued
+    /// updaters keep spinning on the ticket lock, calling `std::thread::yield_now` in between, or
+    /// only spinning without `std`, until this returns. Use
+    /// [`write_priority`](Self::write_priority) if the new version doesn't depend on the current
+    /// one, since it never calls anything again.
+    ///
+    /// Note that [`write`](Self::write) never waits in line either.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    ///
+    /// #[derive(Clone)]
+    /// struct Config {
+    ///     enabled: bool,
+    ///     requests: u32,
+    /// }
+    ///
+    /// let rcu = Rcu::new(Arc::new(Config { enabled: true, requests: 0 }));
+    /// rcu.update_fair(|config| config.requests += 1);
+    /// rcu.update_priority(|config| config.enabled = false);
+    ///
+    /// let config = rcu.read();
+    /// assert!(!config.enabled);
+    /// assert_eq!(config.requests, 1);
+    /// ```
+    #[track_caller]
+    pub fn update_priority<F, R>(&self, updater: F) -> R
+    where
+        T: Clone,
+        F: FnMut(&mut T) -> R,
+    {
+        let _priority = self.extras.get_or_alloc().queue.enter_priority();
+        self.update_retry(updater)
+    }
+
+    /// Like [`update_priority`](Self::update_priority), but writes `new_value` instead of
+    /// updating the current version.
+    ///
+    /// The version is published right away, even if an `update_fair` call is running, which then
+    /// calls its `updater` again on top of `new_value`. Queued `update_fair` calls don't start
+    /// until this returns.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `Rcu` is [frozen](Self::freeze) or the [validator](Self::with_validator)
+    /// rejects the new version.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let kill_switch = Rcu::new(Arc::new(false));
+    ///
+    /// kill_switch.write_priority(Arc::new(true));
+    /// kill_switch.update_fair(|killed| assert!(*killed));
+    /// ```
+    #[track_caller]
+    pub fn write_priority(&self, new_value: Arc<T>) {
+        let _priority = self.extras.get_or_alloc().queue.enter_priority();
+        self.write(new_value);
+    }
+
+    /// Runs `transition` on the current version and writes the version it returns, or returns
+    /// its error.
+    ///
+    /// Transitions wait in line like [`update_fair`](Self::update_fair), so concurrent
+    /// transitions never see the same version. This makes them atomic for e.g. enum state
+    /// machines, where a transition is only valid from certain states. The new version is
+    /// returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransitionError::Invalid`] if `transition` fails, [`TransitionError::Write`] if
+    /// the `Rcu` is [frozen](Self::freeze) or the [validator](Self::with_validator) rejects the
+    /// new version, and [`TransitionError::Conflict`] if a method other than `transition`,
+    /// `update_fair` or [`write_if`](Self::write_if) wrote a version while `transition` was
+    /// running.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::{errors::TransitionError, Rcu};
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// enum Connection {
+    ///     Idle,
+    ///     Connecting { attempt: u32 },
+    ///     Connected,
+    /// }
+    ///
+    /// fn connect(state: &Connection) -> Result<Connection, &'static str> {
+    ///     match state {
+    ///         Connection::Idle => Ok(Connection::Connecting { attempt: 1 }),
+    ///         Connection::Connecting { attempt } => {
+    ///             Ok(Connection::Connecting { attempt: attempt + 1 })
+    ///         }
+    ///         Connection::Connected => Err("already connected"),
+    ///     }
+    /// }
+    ///
+    /// let rcu = Rcu::new(Arc::new(Connection::Idle));
+    /// assert_eq!(*rcu.transition(connect).unwrap(), Connection::Connecting { attempt: 1 });
+    ///
+    /// rcu.transition(|_| Ok::<_, ()>(Connection::Connected)).unwrap();
+    /// assert_eq!(rcu.transition(connect), Err(TransitionError::Invalid("already connected")));
+    /// ```
+    #[track_caller]
+    pub fn transition<F, E>(&self, transition: F) -> Result<Arc<T>, TransitionError<E>>
+    where
+        F: FnOnce(&T) -> Result<T, E>,
+    {
+        let _ticket = self.extras.get_or_alloc().queue.enter();
+        if self.is_frozen() {
+            return Err(TransitionError::Write(WriteError::Frozen));
+        }
+
+        let current = self.read();
+        let new_value = Arc::new(transition(&current).map_err(TransitionError::Invalid)?);
+        let _writing = self
+            .begin_write(&new_value)
+            .map_err(TransitionError::Write)?;
+        if self.claim_generation(&current)
+            && self
+                .compare_exchange_ptr(&current, Arc::clone(&new_value))
+                .is_ok()
+        {
+            return Ok(new_value);
+        }
+        Err(TransitionError::Conflict(Conflict {
+            current: self.generation(),
+        }))
+    }
+
+    /// Returns the number of [`update_fair`](Self::update_fair), [`transition`](Self::transition)
+    /// and [`write_if`](Self::write_if) calls which are waiting or running.
+    ///
+    /// This is meant for monitoring, the number may be outdated by the time it's returned.
+    pub fn queued_updates(&self) -> usize {
+        self.extras.get().map_or(0, |extras| extras.queue.len())
+    }
+}
@@ -0,0 +1,83 @@
+//! A trait for code which is generic over the `Rcu` implementation
+
+use crate::{Arc, Rcu, Reclaim};
+
+/// The core operations of an [`Rcu`], so code can accept `impl RcuLike<T>` and be tested against
+/// simpler implementations
+///
+/// Only [`read`](Self::read) and [`write`](Self::write) have to be implemented.
+///
+/// # Example
+///
+/// ```
+#[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+#[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+/// use axka_rcu::{Rcu, RcuLike};
+///
+/// fn bump_version(config: &impl RcuLike<u32>) {
+///     config.update(|version| *version += 1);
+/// }
+///
+/// let rcu = Rcu::new(Arc::new(1));
+/// bump_version(&rcu);
+/// assert_eq!(*rcu.read(), 2);
+/// ```
+pub trait RcuLike<T> {
+    /// Returns the current version.
+    fn read(&self) -> Arc<T>;
+
+    /// Writes a new version.
+    fn write(&self, new_value: Arc<T>);
+
+    /// Clones `T`, runs `updater` on `T` and writes `T`.
+    ///
+    /// Versions written while `updater` runs may be overwritten.
+    fn update<F, R>(&self, updater: F)
+    where
+        T: Clone,
+        F: FnOnce(&mut T) -> R,
+    {
+        let mut value = T::clone(&self.read());
+        updater(&mut value);
+        self.write(Arc::new(value));
+    }
+}
+
+impl<T, S: Reclaim<T>> RcuLike<T> for Rcu<T, S> {
+    #[inline]
+    fn read(&self) -> Arc<T> {
+        Rcu::read(self)
+    }
+
+    #[track_caller]
+    fn write(&self, new_value: Arc<T>) {
+        Rcu::write(self, new_value);
+    }
+
+    fn update<F, R>(&self, updater: F)
+    where
+        T: Clone,
+        F: FnOnce(&mut T) -> R,
+    {
+        Rcu::update(self, updater);
+    }
+}
+
+impl<T, R: RcuLike<T> + ?Sized> RcuLike<T> for &R {
+    fn read(&self) -> Arc<T> {
+        R::read(self)
+    }
+
+    #[track_caller]
+    fn write(&self, new_value: Arc<T>) {
+        R::write(self, new_value);
+    }
+
+    fn update<F, Ret>(&self, updater: F)
+    where
+        T: Clone,
+        F: FnOnce(&mut T) -> Ret,
+    {
+        R::update(self, updater);
+    }
+}
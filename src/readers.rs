@@ -0,0 +1,421 @@
+//! Split reader counters, which keep replaced versions from being dropped while they're read
+//!
+//! Readers increment one of several counters picked by their thread, instead of the strong count
+//! of the version they read. A replaced ("retired") version is only released once each counter has
+//! been seen at zero after it was replaced.
+//!
+//! The counters are split in two halves by the parity of an epoch. When a retired version is
+//! waiting on the current half, the epoch is advanced so new readers use the other half and the
+//! waited-on half can drain.
+
+use alloc::boxed::Box;
+use core::{
+    ptr,
+    sync::atomic::{AtomicPtr, AtomicUsize, Ordering},
+};
+
+use crate::{Arc, Reclaim};
+
+/// The number of counters in each half
+const SHARDS: usize = 8;
+/// Bitmask of all counters
+const ALL_COUNTERS: u32 = (1 << (2 * SHARDS)) - 1;
+
+/// Padded to avoid false sharing between counters
+#[repr(align(128))]
+struct Counter(AtomicUsize);
+
+struct Table {
+    counters: [[Counter; SHARDS]; 2],
+    epoch: AtomicUsize,
+}
+
+impl Table {
+    /// Returns a bitmask of the counters which are currently zero.
+    fn zero_mask(&self) -> u32 {
+        let mut mask = 0;
+        for (half, counters) in self.counters.iter().enumerate() {
+            for (shard, counter) in counters.iter().enumerate() {
+                if counter.0.load(Ordering::SeqCst) == 0 {
+                    mask |= 1 << (half * SHARDS + shard);
+                }
+            }
+        }
+        mask
+    }
+}
+
+/// A replaced version waiting to be released
+struct Retired<T> {
+    /// Created by `Arc::into_raw`
+    ptr: *const T,
+    /// Bitmask of counters seen at zero after the version was replaced
+    seen: u32,
+    next: *mut Retired<T>,
+}
+
+pub(crate) struct Readers<T> {
+    /// Allocated by the first reader, so `Rcu`s which are never read don't pay for it
+    table: AtomicPtr<Table>,
+    /// A stack of retired versions
+    retired: AtomicPtr<Retired<T>>,
+    /// The number of versions in `retired`, including ones taken by a release in progress
+    pending: AtomicUsize,
+    /// Set in real-time mode, where retiring a version never releases any
+    #[cfg(all(feature = "rt", not(feature = "triomphe")))]
+    deferring: bool,
+}
+
+impl<T> Readers<T> {
+    pub(crate) const fn new() -> Self {
+        Self {
+            table: AtomicPtr::new(ptr::null_mut()),
+            retired: AtomicPtr::new(ptr::null_mut()),
+            pending: AtomicUsize::new(0),
+            #[cfg(all(feature = "rt", not(feature = "triomphe")))]
+            deferring: false,
+        }
+    }
+
+    /// Makes [`retire`](Self::retire) leave the versions to [`advance`](Self::advance) and
+    /// [`synchronize`](Self::synchronize), and allocates the table so reads never allocate.
+    #[cfg(all(feature = "rt", not(feature = "triomphe")))]
+    pub(crate) fn defer(&mut self) {
+        self.deferring = true;
+        self.prepare();
+    }
+
+    /// Allocates the table, so reads never allocate.
+    pub(crate) fn prepare(&self) {
+        self.table();
+    }
+
+    #[cfg(all(feature = "rt", not(feature = "triomphe")))]
+    pub(crate) fn is_deferring(&self) -> bool {
+        self.deferring
+    }
+
+    #[inline]
+    fn table(&self) -> &Table {
+        let table = self.table.load(Ordering::SeqCst);
+        if !table.is_null() {
+            // SAFETY: The table is only freed when dropping `self`
+            return unsafe { &*table };
+        }
+        self.alloc_table()
+    }
+
+    /// Allocates the table on the first read, out of line to keep the read path small.
+    #[cold]
+    #[inline(never)]
+    fn alloc_table(&self) -> &Table {
+        let new_table = Box::into_raw(Box::new(Table {
+            counters: core::array::from_fn(|_| {
+                core::array::from_fn(|_| Counter(AtomicUsize::new(0)))
+            }),
+            epoch: AtomicUsize::new(0),
+        }));
+        match self.table.compare_exchange(
+            ptr::null_mut(),
+            new_table,
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        ) {
+            // SAFETY: The table is only freed when dropping `self`
+            Ok(_) => unsafe { &*new_table },
+            Err(table) => {
+                // SAFETY: The new table wasn't shared
+                drop(unsafe { Box::from_raw(new_table) });
+                // SAFETY: The table is only freed when dropping `self`
+                unsafe { &*table }
+            }
+        }
+    }
+
+    /// Registers a reader. Versions loaded after this won't be released until the returned counter
+    /// is decremented.
+    #[inline]
+    pub(crate) fn pin(&self) -> &AtomicUsize {
+        let table = self.table();
+        // The epoch is just a hint for which half to use
+        let half = table.epoch.load(Ordering::Relaxed) & 1;
+
+        let counter = &table.counters[half][shard()].0;
+        counter.fetch_add(1, Ordering::SeqCst);
+        counter
+    }
+
+    /// Returns the number of active readers, or `None` if the `Rcu` was never read.
+    pub(crate) fn count(&self) -> Option<usize> {
+        let table = self.table.load(Ordering::SeqCst);
+        if table.is_null() {
+            return None;
+        }
+        // SAFETY: The table is only freed when dropping `self`
+        let table = unsafe { &*table };
+        let counters = table.counters.iter().flatten();
+        Some(
+            counters
+                .map(|counter| counter.0.load(Ordering::Relaxed))
+                .sum(),
+        )
+    }
+
+    /// Returns the number of retired versions which may still be read.
+    pub(crate) fn pending(&self) -> usize {
+        self.pending.load(Ordering::Relaxed)
+    }
+
+    /// Hands a replaced version to `strategy` once no reader may still be using it.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be created by `Arc::into_raw` and have been replaced with a `SeqCst` operation.
+    pub(crate) unsafe fn retire<S: Reclaim<T>>(&self, ptr: *const T, strategy: &S) {
+        #[cfg(all(feature = "rt", not(feature = "triomphe")))]
+        if self.deferring {
+            // No counter has been seen at zero yet
+            self.pending.fetch_add(1, Ordering::Relaxed);
+            self.push(Box::into_raw(Box::new(Retired {
+                ptr,
+                seen: 0,
+                next: ptr::null_mut(),
+            })));
+            return;
+        }
+
+        // The table is loaded after the version was replaced, so if there's no table, no reader
+        // could have loaded the version
+        let table = self.table.load(Ordering::SeqCst);
+        if table.is_null() {
+            strategy.reclaim(unsafe { Arc::from_raw(ptr) });
+            return;
+        }
+        // SAFETY: The table is only freed when dropping `self`
+        let table = unsafe { &*table };
+
+        let seen = table.zero_mask();
+        if seen == ALL_COUNTERS && self.retired.load(Ordering::Acquire).is_null() {
+            strategy.reclaim(unsafe { Arc::from_raw(ptr) });
+            return;
+        }
+
+        self.pending.fetch_add(1, Ordering::Relaxed);
+        self.push(Box::into_raw(Box::new(Retired {
+            ptr,
+            seen,
+            next: ptr::null_mut(),
+        })));
+        self.release(table, strategy);
+    }
+
+    /// Hands the retired versions which no reader may still be using to `strategy`.
+    fn release<S: Reclaim<T>>(&self, table: &Table, strategy: &S) {
+        // Reverse the stack, so older versions are released first
+        let mut stack = self.retired.swap(ptr::null_mut(), Ordering::AcqRel);
+        let mut node = ptr::null_mut();
+        while !stack.is_null() {
+            // SAFETY: The list was taken by this call, so no one else can access the nodes
+            let retired = unsafe { &mut *stack };
+            stack = core::mem::replace(&mut retired.next, node);
+            node = retired;
+        }
+
+        // All versions in the list were replaced before this
+        let zero_mask = table.zero_mask();
+        let current_half = (table.epoch.load(Ordering::SeqCst) & 1) as u32;
+        let current_half_mask = ((1 << SHARDS) - 1) << (current_half * SHARDS as u32);
+
+        let mut waits_on_current_half = false;
+        while !node.is_null() {
+            // SAFETY: The list was taken by this call, so no one else can access the nodes
+            let retired = unsafe { &mut *node };
+            let next = retired.next;
+
+            retired.seen |= zero_mask;
+            if retired.seen == ALL_COUNTERS {
+                self.pending.fetch_sub(1, Ordering::Relaxed);
+                // SAFETY: No reader can have the version anymore
+                unsafe {
+                    strategy.reclaim(Arc::from_raw(retired.ptr));
+                    drop(Box::from_raw(node));
+                }
+            } else {
+                waits_on_current_half |= retired.seen & current_half_mask != current_half_mask;
+                self.push(node);
+            }
+
+            node = next;
+        }
+
+        if waits_on_current_half {
+            table.epoch.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Hands the retired versions which no reader may still be using to `strategy`, without
+    /// waiting.
+    pub(crate) fn advance<S: Reclaim<T>>(&self, strategy: &S) {
+        // Without a table, no version could have been retired
+        let table = self.table.load(Ordering::SeqCst);
+        if table.is_null() {
+            return;
+        }
+        // SAFETY: The table is only freed when dropping `self`
+        self.release(unsafe { &*table }, strategy);
+    }
+
+    /// Waits until no reader may still be using the versions retired before this call, and hands
+    /// them to `strategy`.
+    pub(crate) fn synchronize<S: Reclaim<T>>(&self, strategy: &S) {
+        let mut grace_period = self.start_grace_period();
+        while !grace_period.poll() {
+            crate::wait();
+        }
+        grace_period.finish(strategy);
+    }
+
+    /// Takes the versions retired before this call, which can be released once the returned
+    /// grace period has ended.
+    pub(crate) fn start_grace_period(&self) -> GracePeriod<'_, T> {
+        // Without a table, no version could have been retired
+        let table = self.table.load(Ordering::SeqCst);
+        if table.is_null() {
+            return GracePeriod {
+                readers: self,
+                table: None,
+                stack: ptr::null_mut(),
+                seen: ALL_COUNTERS,
+            };
+        }
+
+        GracePeriod {
+            readers: self,
+            // SAFETY: The table is only freed when dropping `self`
+            table: Some(unsafe { &*table }),
+            stack: self.retired.swap(ptr::null_mut(), Ordering::AcqRel),
+            seen: 0,
+        }
+    }
+
+    fn push(&self, node: *mut Retired<T>) {
+        let mut head = self.retired.load(Ordering::Relaxed);
+        loop {
+            // SAFETY: The node isn't shared yet
+            unsafe { (*node).next = head };
+            match self.retired.compare_exchange_weak(
+                head,
+                node,
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return,
+                Err(new_head) => head = new_head,
+            }
+        }
+    }
+
+    /// Hands all retired versions to `strategy`, oldest first.
+    pub(crate) fn clear<S: Reclaim<T>>(&mut self, strategy: &S) {
+        let mut stack = core::mem::replace(self.retired.get_mut(), ptr::null_mut());
+        // Reverse the stack, so older versions are released first
+        let mut node = ptr::null_mut();
+        while !stack.is_null() {
+            // SAFETY: The list was taken by this call, so no one else can access the nodes
+            let retired = unsafe { &mut *stack };
+            stack = core::mem::replace(&mut retired.next, node);
+            node = retired;
+        }
+        while !node.is_null() {
+            // SAFETY: There can't be readers, since `self` is borrowed mutably
+            let retired = unsafe { Box::from_raw(node) };
+            *self.pending.get_mut() -= 1;
+            strategy.reclaim(unsafe { Arc::from_raw(retired.ptr) });
+            node = retired.next;
+        }
+    }
+}
+
+impl<T> Drop for Readers<T> {
+    fn drop(&mut self) {
+        // The `Rcu` hands the retired versions to its strategy before this is dropped
+        self.clear(&crate::RefCount);
+
+        let table = *self.table.get_mut();
+        if !table.is_null() {
+            // SAFETY: The table was created by Box::into_raw and there are no readers
+            drop(unsafe { Box::from_raw(table) });
+        }
+    }
+}
+
+/// Waits for the readers of the versions taken by [`Readers::start_grace_period`]
+///
+/// Many grace periods can be polled in turn, so waiting for the readers of many `Rcu`s takes as
+/// long as the slowest of them.
+pub(crate) struct GracePeriod<'a, T> {
+    readers: &'a Readers<T>,
+    table: Option<&'a Table>,
+    /// A stack of the taken versions
+    stack: *mut Retired<T>,
+    /// Bitmask of counters seen at zero since the grace period started
+    seen: u32,
+}
+
+impl<T> GracePeriod<'_, T> {
+    /// Returns `true` if no reader may still be using the taken versions.
+    pub(crate) fn poll(&mut self) -> bool {
+        let Some(table) = self.table else {
+            return true;
+        };
+        if self.seen == ALL_COUNTERS {
+            return true;
+        }
+
+        self.seen |= table.zero_mask();
+        if self.seen == ALL_COUNTERS {
+            return true;
+        }
+        // Move new readers to the other half once it has drained, so this half can drain
+        let current_half = (table.epoch.load(Ordering::SeqCst) & 1) as u32;
+        let current_half_mask = ((1 << SHARDS) - 1) << (current_half * SHARDS as u32);
+        if self.seen | current_half_mask == ALL_COUNTERS {
+            table.epoch.fetch_add(1, Ordering::SeqCst);
+        }
+        false
+    }
+
+    /// Hands the taken versions to `strategy`, oldest first.
+    ///
+    /// Must only be called once [`poll`](Self::poll) returned `true`.
+    pub(crate) fn finish<S: Reclaim<T>>(self, strategy: &S) {
+        debug_assert_eq!(self.seen, ALL_COUNTERS, "the grace period hasn't ended");
+
+        // Reverse the stack, so older versions are released first
+        let mut stack = self.stack;
+        let mut node = ptr::null_mut();
+        while !stack.is_null() {
+            // SAFETY: The list was taken by this grace period, so no one else can access the nodes
+            let retired = unsafe { &mut *stack };
+            stack = core::mem::replace(&mut retired.next, node);
+            node = retired;
+        }
+        while !node.is_null() {
+            // SAFETY: Every counter was seen at zero after the versions were replaced
+            let retired = unsafe { Box::from_raw(node) };
+            self.readers.pending.fetch_sub(1, Ordering::Relaxed);
+            strategy.reclaim(unsafe { Arc::from_raw(retired.ptr) });
+            node = retired.next;
+        }
+    }
+}
+
+/// Picks a counter for the current thread.
+#[inline]
+fn shard() -> usize {
+    // Threads have separate stacks, so the address of a local is a cheap hint of the thread, even
+    // without `std`
+    let local = 0u8;
+    let addr = ptr::addr_of!(local) as usize;
+    ((addr >> 16) as u32).wrapping_mul(0x9E37_79B9) as usize >> (32 - SHARDS.trailing_zeros())
+}
@@ -0,0 +1,215 @@
+//! Strategies for reclaiming replaced versions
+
+use alloc::{boxed::Box, vec::Vec};
+use core::{
+    fmt,
+    marker::PhantomData,
+    ptr,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+use crate::{Arc, Rcu};
+
+/// Decides what happens to replaced versions of an [`Rcu`](crate::Rcu)
+///
+/// A version is handed over once it has been replaced and no
+/// [`ReadGuard`](crate::ReadGuard) can reach it anymore. `Arc`s returned by
+/// [`Rcu::read`](crate::Rcu::read) may still keep it alive after that. When the `Rcu` is dropped,
+/// the replaced versions which were still waiting for their readers are handed over, and then
+/// [`flush`](Self::flush) is called.
+///
+/// The strategy is chosen with [`Rcu::with_reclaim`](crate::Rcu::with_reclaim).
+pub trait Reclaim<T> {
+    /// Takes the reference to a replaced version that was owned by the `Rcu`.
+    fn reclaim(&self, version: Arc<T>);
+
+    /// Drops the versions which the strategy is holding on to, if any.
+    ///
+    /// Does nothing by default.
+    fn flush(&self) {}
+}
+
+/// Drops replaced versions right away, which is the default
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RefCount;
+
+impl<T> Reclaim<T> for RefCount {
+    fn reclaim(&self, version: Arc<T>) {
+        drop(version);
+    }
+}
+
+/// Keeps replaced versions until [`flush`](Self::flush) is called
+///
+/// This lets you choose when the versions are dropped, e.g. outside latency-sensitive code.
+///
+/// # Example
+///
+/// ```
+#[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+#[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+/// use axka_rcu::{Deferred, Rcu};
+/// let rcu = Rcu::with_reclaim(Arc::new("foo"), Deferred::new());
+///
+/// let first = rcu.read();
+/// rcu.write(Arc::new("bar"));
+/// assert_eq!(Arc::strong_count(&first), 2);
+///
+/// rcu.reclaimer().flush();
+/// assert_eq!(Arc::strong_count(&first), 1);
+/// ```
+pub struct Deferred<T> {
+    /// A stack of versions
+    head: AtomicPtr<Node<T>>,
+    _marker: PhantomData<Arc<T>>,
+}
+
+struct Node<T> {
+    version: Arc<T>,
+    next: *mut Node<T>,
+}
+
+impl<T> Deferred<T> {
+    /// Creates an empty `Deferred`.
+    pub const fn new() -> Self {
+        Self {
+            head: AtomicPtr::new(ptr::null_mut()),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns `true` if there are no versions waiting to be dropped.
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire).is_null()
+    }
+
+    /// Drops all versions which were replaced before this call, oldest first.
+    pub fn flush(&self) {
+        let mut node = self.head.swap(ptr::null_mut(), Ordering::AcqRel);
+
+        let mut versions = Vec::new();
+        while !node.is_null() {
+            // SAFETY: The stack was taken by this call, so no one else can access the nodes
+            let boxed = unsafe { Box::from_raw(node) };
+            node = boxed.next;
+            versions.push(boxed.version);
+        }
+        while let Some(version) = versions.pop() {
+            drop(version);
+        }
+    }
+}
+
+impl<T> Reclaim<T> for Deferred<T> {
+    fn reclaim(&self, version: Arc<T>) {
+        let node = Box::into_raw(Box::new(Node {
+            version,
+            next: ptr::null_mut(),
+        }));
+
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            // SAFETY: The node isn't shared yet
+            unsafe { (*node).next = head };
+            match self
+                .head
+                .compare_exchange_weak(head, node, Ordering::AcqRel, Ordering::Relaxed)
+            {
+                Ok(_) => return,
+                Err(new_head) => head = new_head,
+            }
+        }
+    }
+
+    fn flush(&self) {
+        Deferred::flush(self);
+    }
+}
+
+impl<T> Default for Deferred<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for Deferred<T> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+impl<T> fmt::Debug for Deferred<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("Deferred");
+        d.field("is_empty", &self.is_empty());
+        d.finish_non_exhaustive()
+    }
+}
+
+impl<T, S: Reclaim<T>> Rcu<T, S> {
+    /// Drops every replaced version which isn't used anymore, and reports what's still kept
+    /// alive, e.g. in a memory-pressure handler.
+    ///
+    /// This [flushes](Self::flush_deferred) the [`Reclaim`] strategy and evicts the expired
+    /// versions of the history, if any. Like `flush_deferred`, it never blocks.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::{Deferred, Rcu};
+    /// let rcu = Rcu::with_reclaim(Arc::new("foo"), Deferred::new());
+    ///
+    /// let guard = rcu.read_guard();
+    /// let pin = rcu.pin_current();
+    /// rcu.write(Arc::new("bar"));
+    ///
+    /// let report = rcu.drop_old_now();
+    /// assert_eq!(report.waiting_for_guards, 1);
+    /// assert_eq!(report.guards, 1);
+    /// assert_eq!(report.pinned, [0]);
+    ///
+    /// drop((guard, pin));
+    /// let report = rcu.drop_old_now();
+    /// assert_eq!(report.waiting_for_guards, 0);
+    /// assert!(rcu.reclaimer().is_empty());
+    /// ```
+    pub fn drop_old_now(&self) -> ReclaimReport {
+        self.flush_deferred();
+        ReclaimReport {
+            waiting_for_guards: self.readers.pending(),
+            guards: self.readers.count().unwrap_or(0),
+            pinned: self.pinned_generations(),
+            #[cfg(all(feature = "history", not(feature = "triomphe")))]
+            history: self
+                .history
+                .as_ref()
+                .map_or(0, |history| history.evict_expired()),
+            #[cfg(all(feature = "lineage", not(feature = "triomphe")))]
+            lineage: self.lineage(),
+        }
+    }
+}
+
+/// What keeps replaced versions of an [`Rcu`] alive, returned by [`Rcu::drop_old_now`]
+///
+/// `Arc`s returned by [`Rcu::read`] aren't known to the `Rcu`, unless the `lineage` feature is
+/// enabled.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ReclaimReport {
+    /// The number of replaced versions which wait for [`ReadGuard`](crate::ReadGuard)s and
+    /// [`ReadSession`](crate::ReadSession)s
+    pub waiting_for_guards: usize,
+    /// The number of `ReadGuard`s and `ReadSession`s
+    pub guards: usize,
+    /// The [pinned](Rcu::pin_current) generations
+    pub pinned: Vec<u64>,
+    /// The number of replaced versions kept by the [history](Rcu::with_history)
+    #[cfg(all(feature = "history", not(feature = "triomphe")))]
+    pub history: usize,
+    /// The versions which are alive, and how many references each one has
+    #[cfg(all(feature = "lineage", not(feature = "triomphe")))]
+    pub lineage: crate::Lineage,
+}
@@ -0,0 +1,138 @@
+//! Refreshing versions periodically from an async source
+
+use core::{fmt, future::Future, time::Duration};
+use std::sync::{Arc, Weak};
+
+use tokio::{
+    task::JoinHandle,
+    time::{self, Instant, MissedTickBehavior},
+};
+
+use crate::{
+    errors::{RefreshError, WriteError},
+    Rcu, Reclaim,
+};
+
+/// A task refreshing an `Rcu`, returned by [`Rcu::spawn_refresher`]
+///
+/// The task is stopped when this is dropped or [shut down](Self::shutdown).
+pub struct Refresher {
+    task: JoinHandle<()>,
+}
+
+impl Refresher {
+    /// Stops the task, cancelling a fetch which is in progress.
+    pub fn shutdown(self) {
+        self.task.abort();
+    }
+
+    /// Returns `true` if the task has stopped, e.g. because the `Rcu` was dropped.
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+impl Drop for Refresher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl fmt::Debug for Refresher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("Refresher");
+        d.field("finished", &self.is_finished());
+        d.finish_non_exhaustive()
+    }
+}
+
+impl<T, S> Rcu<T, S>
+where
+    T: Send + Sync + 'static,
+    S: Reclaim<T> + Send + Sync + 'static,
+{
+    /// Spawns a Tokio task which writes a new version fetched by `fetch` every `interval`.
+    ///
+    /// Errors are passed to `on_error` and the current version is kept until the next fetch. A
+    /// [frozen](Rcu::freeze) `Rcu` isn't refreshed. The first fetch happens after `interval`, and
+    /// a fetch which takes longer than `interval` delays the next one instead of causing a burst.
+    ///
+    /// The task stops when the returned [`Refresher`] is dropped or the `Rcu` is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero, or if this isn't called in a Tokio runtime with the time
+    /// driver enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::{sync::Arc, time::Duration};
+    /// use std::sync::atomic::{AtomicU32, Ordering};
+    ///
+    /// use axka_rcu::Rcu;
+    /// # let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+    /// # runtime.block_on(async {
+    ///
+    /// static FETCHES: AtomicU32 = AtomicU32::new(0);
+    ///
+    /// let rates = Arc::new(Rcu::new(Arc::new(0)));
+    /// let refresher = rates.spawn_refresher(
+    ///     Duration::from_millis(10),
+    ///     || async {
+    ///         match FETCHES.fetch_add(1, Ordering::Relaxed) {
+    ///             0 => Err("connection refused"),
+    ///             n => Ok(n),
+    ///         }
+    ///     },
+    ///     |err| eprintln!("Failed to refresh: {err}"),
+    /// );
+    ///
+    /// while *rates.read() == 0 {
+    ///     tokio::time::sleep(Duration::from_millis(10)).await;
+    /// }
+    /// assert_eq!(*rates.read(), 1);
+    /// refresher.shutdown();
+    /// # });
+    /// ```
+    pub fn spawn_refresher<F, Fut, E, EF>(
+        self: &Arc<Self>,
+        interval: Duration,
+        mut fetch: F,
+        on_error: EF,
+    ) -> Refresher
+    where
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<T, E>> + Send,
+        EF: Fn(RefreshError<E>) + Send + 'static,
+    {
+        let rcu: Weak<Self> = Arc::downgrade(self);
+        let mut ticks = time::interval_at(Instant::now() + interval, interval);
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        let task = tokio::spawn(async move {
+            loop {
+                ticks.tick().await;
+                if rcu.strong_count() == 0 {
+                    break;
+                }
+                let fetched = fetch().await;
+                // The `Rcu` isn't kept alive while fetching
+                let Some(rcu) = rcu.upgrade() else {
+                    break;
+                };
+                match fetched {
+                    // A frozen `Rcu` keeps its version
+                    Ok(value) => {
+                        if let Err(WriteError::Rejected) = rcu.try_write(Arc::new(value)) {
+                            on_error(RefreshError::Rejected);
+                        }
+                    }
+                    Err(err) => on_error(RefreshError::Fetch(err)),
+                }
+            }
+        });
+
+        Refresher { task }
+    }
+}
@@ -0,0 +1,149 @@
+//! An `Rcu` which refreshes stale versions in the background when they're read
+
+use alloc::boxed::Box;
+use core::{
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+use std::{sync::Arc, thread};
+
+use crate::Rcu;
+
+/// A read-through cache: reading a version older than a TTL refreshes it from a provider in the
+/// background, while the stale version is returned right away
+///
+/// Only one refresh runs at a time, so a burst of reads of a stale version calls the provider
+/// once. The provider returns `None` to keep the current version, e.g. when a remote source is
+/// unavailable, and is then called again by the next read.
+///
+/// # Example
+///
+/// ```
+/// # use std::{sync::Arc, time::Duration};
+/// use std::sync::atomic::{AtomicU32, Ordering};
+///
+/// use axka_rcu::RefreshingRcu;
+///
+/// static REMOTE_LIMIT: AtomicU32 = AtomicU32::new(10);
+///
+/// let limit = RefreshingRcu::new(Arc::new(10), Duration::ZERO, || {
+///     Some(REMOTE_LIMIT.load(Ordering::Relaxed))
+/// });
+///
+/// REMOTE_LIMIT.store(20, Ordering::Relaxed);
+/// // The stale version is returned while the refresh runs
+/// assert_eq!(*limit.read(), 10);
+///
+/// limit.refresh();
+/// assert_eq!(*limit.read(), 20);
+/// ```
+pub struct RefreshingRcu<T> {
+    inner: Arc<Inner<T>>,
+}
+
+struct Inner<T> {
+    rcu: Rcu<T>,
+    ttl: Duration,
+    provider: Box<dyn Fn() -> Option<T> + Send + Sync>,
+    /// Set while a refresh is running
+    refreshing: AtomicBool,
+}
+
+impl<T: Send + Sync + 'static> RefreshingRcu<T> {
+    /// Creates a new `RefreshingRcu` with `initial` as the current version, whose versions are
+    /// refreshed by `provider` once they're older than `ttl`.
+    pub fn new<F>(initial: Arc<T>, ttl: Duration, provider: F) -> Self
+    where
+        F: Fn() -> Option<T> + Send + Sync + 'static,
+    {
+        Self {
+            inner: Arc::new(Inner {
+                rcu: Rcu::new(initial),
+                ttl,
+                provider: Box::new(provider),
+                refreshing: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    /// Returns the current version, and starts refreshing it on another thread if it's older
+    /// than the TTL.
+    pub fn read(&self) -> Arc<T> {
+        let version = self.inner.rcu.read();
+        if self.is_stale() && !self.inner.refreshing.swap(true, Ordering::Acquire) {
+            let inner = Arc::clone(&self.inner);
+            thread::spawn(move || inner.refresh_claimed());
+        }
+        version
+    }
+
+    /// Refreshes the version on the current thread, returning `true` if the provider returned a
+    /// new version.
+    ///
+    /// If a refresh is already running, this waits for it instead of calling the provider again.
+    pub fn refresh(&self) -> bool {
+        loop {
+            if !self.inner.refreshing.swap(true, Ordering::Acquire) {
+                return self.inner.refresh_claimed();
+            }
+            let generation = self.inner.rcu.generation();
+            while self.inner.refreshing.load(Ordering::Acquire) {
+                crate::wait();
+            }
+            if self.inner.rcu.generation() != generation {
+                return true;
+            }
+        }
+    }
+
+    /// Returns `true` if the current version is older than the TTL.
+    pub fn is_stale(&self) -> bool {
+        self.inner.rcu.age() > self.inner.ttl
+    }
+
+    /// Returns the `Rcu` holding the versions, e.g. for writing a version directly.
+    pub fn rcu(&self) -> &Rcu<T> {
+        &self.inner.rcu
+    }
+}
+
+impl<T> Inner<T> {
+    /// Calls the provider, after `refreshing` was set by the caller.
+    fn refresh_claimed(&self) -> bool {
+        /// Clears `refreshing` when dropped, even if the provider panics
+        struct Claim<'a>(&'a AtomicBool);
+        impl Drop for Claim<'_> {
+            fn drop(&mut self) {
+                self.0.store(false, Ordering::Release);
+            }
+        }
+
+        let _claim = Claim(&self.refreshing);
+        match (self.provider)() {
+            Some(value) => {
+                self.rcu.write(Arc::new(value));
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl<T> Clone for RefreshingRcu<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+/// Formats the current version, without refreshing it.
+impl<T: fmt::Debug> fmt::Debug for RefreshingRcu<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("RefreshingRcu");
+        d.field("data", &&*self.inner.rcu.read_guard());
+        d.field("ttl", &self.inner.ttl);
+        d.finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,187 @@
+//! A global registry of named `Rcu`s, for runtime introspection
+//!
+//! `Rcu`s are registered with [`Rcu::register`], and listed with [`entries`], e.g. by an admin
+//! endpoint. The registry only holds weak references, so `Rcu`s disappear from it when they're
+//! dropped.
+//!
+//! # Example
+//!
+//! ```
+//! # use std::sync::Arc;
+//! use axka_rcu::{registry, Rcu};
+//!
+//! let config = Arc::new(Rcu::new(Arc::new(String::from("debug = false"))));
+//! config.register_sized("config", |config| config.capacity());
+//! config.write(Arc::new(String::from("debug = true")));
+//!
+//! let entry = registry::find("config").unwrap();
+//! assert_eq!(entry.generation(), 1);
+//! assert_eq!(entry.size_hint(), 12);
+//!
+//! drop(config);
+//! assert!(registry::find("config").is_none());
+//! ```
+
+use alloc::{borrow::Cow, vec::Vec};
+use core::{any::Any, fmt, time::Duration};
+use std::sync::{Arc, Weak};
+
+use crate::{lock::Mutex, Rcu, Reclaim};
+
+/// The registered `Rcu`s
+static REGISTRY: Mutex<Vec<Registered>> = Mutex::new(Vec::new());
+
+struct Registered {
+    name: Cow<'static, str>,
+    rcu: Weak<dyn Introspect>,
+    /// The `fn(&T) -> usize` estimating the size of a version
+    size_of: Arc<dyn Any + Send + Sync>,
+}
+
+/// The type-erased queries of a registered `Rcu`
+trait Introspect: Send + Sync {
+    fn entry(&self, name: Cow<'static, str>, size_of: &dyn Any) -> RegistryEntry;
+}
+
+impl<T, S> Introspect for Rcu<T, S>
+where
+    T: Send + Sync + 'static,
+    S: Reclaim<T> + Send + Sync,
+{
+    fn entry(&self, name: Cow<'static, str>, size_of: &dyn Any) -> RegistryEntry {
+        RegistryEntry {
+            name,
+            type_name: core::any::type_name::<T>(),
+            generation: self.generation(),
+            age: self.age(),
+            readers: self.readers.count().unwrap_or(0),
+            size_hint: size_of
+                .downcast_ref::<fn(&T) -> usize>()
+                .map_or(0, |&size_of| self.read_with(size_of)),
+        }
+    }
+}
+
+impl<T, S> Rcu<T, S>
+where
+    T: Send + Sync + 'static,
+    S: Reclaim<T> + Send + Sync + 'static,
+{
+    /// Registers this `Rcu` under `name` in the [global registry](crate::registry), until it's
+    /// dropped.
+    ///
+    /// The size hint of the entry is the size of `T`, not including its heap allocations. See
+    /// [`register_sized`](Self::register_sized) for estimating those too.
+    ///
+    /// An `Rcu` registered before under the same name is replaced.
+    pub fn register(self: &Arc<Self>, name: impl Into<Cow<'static, str>>) {
+        self.register_sized(name, core::mem::size_of_val::<T>);
+    }
+
+    /// Like [`register`](Self::register), but the size hint of the current version is estimated
+    /// by `size_of`.
+    pub fn register_sized(
+        self: &Arc<Self>,
+        name: impl Into<Cow<'static, str>>,
+        size_of: fn(&T) -> usize,
+    ) {
+        let name = name.into();
+        let rcu: Weak<Self> = Arc::downgrade(self);
+        let mut registry = REGISTRY.lock();
+        registry.retain(|registered| registered.name != name && registered.rcu.strong_count() != 0);
+        registry.push(Registered {
+            name,
+            rcu,
+            size_of: Arc::new(size_of),
+        });
+    }
+}
+
+/// Queries the registered `Rcu`s which match `filter` and are alive.
+fn query(filter: impl Fn(&str) -> bool) -> Vec<RegistryEntry> {
+    // The `Rcu`s are queried and released outside of the lock, since dropping the last reference
+    // to one may drop versions
+    let mut alive = Vec::new();
+    {
+        let mut registry = REGISTRY.lock();
+        registry.retain(|registered| registered.rcu.strong_count() != 0);
+        for registered in registry
+            .iter()
+            .filter(|registered| filter(&registered.name))
+        {
+            if let Some(rcu) = registered.rcu.upgrade() {
+                let size_of = Arc::clone(&registered.size_of);
+                alive.push((registered.name.clone(), rcu, size_of));
+            }
+        }
+    }
+    alive
+        .into_iter()
+        .map(|(name, rcu, size_of)| rcu.entry(name, &*size_of))
+        .collect()
+}
+
+/// Returns the registered `Rcu`s which are alive, in the order they were registered.
+pub fn entries() -> Vec<RegistryEntry> {
+    query(|_| true)
+}
+
+/// Returns the `Rcu` registered under `name`, if it's alive.
+pub fn find(name: &str) -> Option<RegistryEntry> {
+    query(|registered| registered == name).pop()
+}
+
+/// A snapshot of a registered `Rcu`, returned by [`entries`] and [`find`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegistryEntry {
+    name: Cow<'static, str>,
+    type_name: &'static str,
+    generation: u64,
+    age: Duration,
+    readers: usize,
+    size_hint: usize,
+}
+
+impl RegistryEntry {
+    /// Returns the name the `Rcu` was registered under.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the name of the type of the versions, see [`core::any::type_name`].
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Returns the [generation](Rcu::generation) of the current version.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Returns how long ago the current version was published, see [`Rcu::age`].
+    pub fn age(&self) -> Duration {
+        self.age
+    }
+
+    /// Returns the number of [`ReadGuard`](crate::ReadGuard)s and
+    /// [`ReadSession`](crate::ReadSession)s.
+    pub fn readers(&self) -> usize {
+        self.readers
+    }
+
+    /// Returns the approximate size of the current version in bytes, see
+    /// [`Rcu::register_sized`].
+    pub fn size_hint(&self) -> usize {
+        self.size_hint
+    }
+}
+
+impl fmt::Display for RegistryEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}): v{}, updated {:?} ago, {} readers, ~{} bytes",
+            self.name, self.type_name, self.generation, self.age, self.readers, self.size_hint
+        )
+    }
+}
@@ -0,0 +1,178 @@
+//! Reloading versions from a file when it changes
+
+use std::{
+    error::Error,
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{errors::WriteError, Rcu, Reclaim};
+
+/// Watches a file for [`Rcu::spawn_file_reload`]
+///
+/// The file is watched until this is dropped.
+pub struct FileReload {
+    path: PathBuf,
+    _watcher: RecommendedWatcher,
+}
+
+impl FileReload {
+    /// Returns the path of the watched file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl fmt::Debug for FileReload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("FileReload");
+        d.field("path", &self.path);
+        d.finish_non_exhaustive()
+    }
+}
+
+/// An error that happened while reloading a file, passed to the error callback of
+/// [`Rcu::spawn_file_reload`]
+///
+/// The current version is kept when an error happens.
+#[derive(Debug)]
+pub enum ReloadError<E> {
+    /// The file watcher returned an error
+    Watch(notify::Error),
+    /// The file couldn't be read
+    Io(std::io::Error),
+    /// The file contents couldn't be parsed
+    Parse(E),
+    /// The parsed version was rejected by the [validator](Rcu::with_validator)
+    Rejected,
+}
+
+impl<E: fmt::Display> fmt::Display for ReloadError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Watch(err) => write!(f, "failed to watch file: {err}"),
+            Self::Io(err) => write!(f, "failed to read file: {err}"),
+            Self::Parse(err) => write!(f, "failed to parse file: {err}"),
+            Self::Rejected => f.write_str("the parsed file was rejected by the validator"),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for ReloadError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Watch(err) => Some(err),
+            Self::Io(err) => Some(err),
+            Self::Parse(err) => Some(err),
+            Self::Rejected => None,
+        }
+    }
+}
+
+impl<T, S> Rcu<T, S>
+where
+    T: Send + Sync + 'static,
+    S: Reclaim<T> + Send + Sync + 'static,
+{
+    /// Watches the file at `path` and writes a new version parsed by `parse` whenever it changes.
+    ///
+    /// Errors are passed to `on_error` and the current version is kept. If the file can't be read
+    /// or parsed or is rejected, the `Rcu` is [poisoned](Rcu::poison) until it's reloaded
+    /// successfully. The file is watched until the returned [`FileReload`] is dropped. A
+    /// [frozen](Rcu::freeze) `Rcu` isn't reloaded.
+    ///
+    /// The parent directory of the file is watched, so replacing the file by renaming over it is
+    /// noticed. The file isn't read when this is called, only when it changes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::{sync::Arc, thread::sleep, time::Duration};
+    /// use axka_rcu::Rcu;
+    /// # let dir = std::env::temp_dir().join(format!("axka-rcu-reload-{}", std::process::id()));
+    /// # std::fs::create_dir_all(&dir).unwrap();
+    /// # let path = dir.join("port");
+    /// std::fs::write(&path, "80").unwrap();
+    ///
+    /// let port = Arc::new(Rcu::new(Arc::new(80u16)));
+    /// let _reload = port.spawn_file_reload(
+    ///     &path,
+    ///     |bytes| std::str::from_utf8(bytes).unwrap().trim().parse::<u16>(),
+    ///     |err| eprintln!("Failed to reload: {err}"),
+    /// ).unwrap();
+    ///
+    /// std::fs::write(&path, "8080").unwrap();
+    /// # for _ in 0..100 {
+    /// #     if *port.read() == 8080 { break }
+    /// #     sleep(Duration::from_millis(50));
+    /// # }
+    /// assert_eq!(*port.read(), 8080);
+    /// # std::fs::remove_dir_all(&dir).unwrap();
+    /// ```
+    pub fn spawn_file_reload<P, F, PE, EF>(
+        self: &Arc<Self>,
+        path: P,
+        parse: F,
+        on_error: EF,
+    ) -> notify::Result<FileReload>
+    where
+        P: AsRef<Path>,
+        F: Fn(&[u8]) -> Result<T, PE> + Send + 'static,
+        EF: Fn(ReloadError<PE>) + Send + 'static,
+    {
+        let path = path.as_ref().to_path_buf();
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+
+        let rcu = Arc::clone(self);
+        let file_name = path.file_name().map(ToOwned::to_owned);
+        let watched_path = path.clone();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(err) => return on_error(ReloadError::Watch(err)),
+                };
+                if !(event.kind.is_create() || event.kind.is_modify())
+                    || !event
+                        .paths
+                        .iter()
+                        .any(|path| path.file_name() == file_name.as_deref())
+                {
+                    return;
+                }
+
+                let bytes = match fs::read(&watched_path) {
+                    Ok(bytes) => bytes,
+                    Err(err) => {
+                        rcu.poison();
+                        return on_error(ReloadError::Io(err));
+                    }
+                };
+                match parse(&bytes) {
+                    // A frozen `Rcu` keeps its version
+                    Ok(value) => {
+                        if let Err(WriteError::Rejected) = rcu.try_write(Arc::new(value)) {
+                            rcu.poison();
+                            on_error(ReloadError::Rejected);
+                        }
+                    }
+                    Err(err) => {
+                        rcu.poison();
+                        on_error(ReloadError::Parse(err));
+                    }
+                }
+            })?;
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+
+        Ok(FileReload {
+            path,
+            _watcher: watcher,
+        })
+    }
+}
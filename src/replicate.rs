@@ -0,0 +1,153 @@
+//! Mirroring the versions of an `Rcu` in another process
+//!
+//! Each version is sent as a frame: its length in bytes as a little-endian `u64`, followed by the
+//! version as JSON.
+
+use core::fmt;
+use std::{
+    io::{self, Read, Write},
+    sync::{Arc, Weak},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{lock::Mutex, PersistError, Rcu, Reclaim};
+
+impl<T, S> Rcu<T, S>
+where
+    T: Serialize + Send + Sync + 'static,
+    S: Reclaim<T> + Send + Sync + 'static,
+{
+    /// Writes the current version and every version written after it to `writer`, until the
+    /// returned [`VersionExport`] is dropped.
+    ///
+    /// The versions can be applied to another `Rcu`, e.g. in a follower process, with
+    /// [`follow`](Self::follow). Each export writes the version which is current at that time, so
+    /// versions written in quick succession may be skipped, but the last version is always
+    /// exported.
+    ///
+    /// Errors are passed to `on_error`, after which nothing more is exported.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::sync::Arc;
+    /// use axka_rcu::Rcu;
+    /// let leader = Arc::new(Rcu::new(Arc::new(vec![1u32])));
+    /// let follower = Rcu::new(Arc::new(Vec::<u32>::new()));
+    ///
+    /// let (reader, writer) = std::io::pipe().unwrap();
+    /// let export = leader.export_versions(writer, |err| eprintln!("Failed to export: {err}"));
+    /// leader.write(Arc::new(vec![1, 2]));
+    /// drop(export);
+    ///
+    /// follower.follow(reader).unwrap();
+    /// assert_eq!(*follower.read(), [1, 2]);
+    /// ```
+    pub fn export_versions<W, EF>(self: &Arc<Self>, writer: W, on_error: EF) -> VersionExport<T, S>
+    where
+        W: Write + Send + 'static,
+        EF: Fn(PersistError) + Send + Sync + 'static,
+    {
+        let rcu = Arc::downgrade(self);
+        // The last exported version, kept as a `Weak` so its address can't be reused
+        let state = Mutex::new((writer, None::<Weak<T>>));
+        let export = Arc::new(move || {
+            let Some(rcu) = rcu.upgrade() else {
+                return false;
+            };
+            let mut state = state.lock();
+            let (writer, last) = &mut *state;
+
+            // Exported while holding the lock, so a version exported later is never older
+            let current = rcu.read();
+            if last
+                .as_ref()
+                .is_some_and(|last| last.as_ptr() == Arc::as_ptr(&current))
+            {
+                return true;
+            }
+            match write_frame(writer, &*current) {
+                Ok(()) => {
+                    *last = Some(Arc::downgrade(&current));
+                    true
+                }
+                Err(err) => {
+                    on_error(err);
+                    false
+                }
+            }
+        });
+
+        // Registered before exporting the current version, so no version is missed
+        let hook = Arc::clone(&export);
+        let id = self.hooks.add(move |_| hook());
+        if !export() {
+            self.hooks.remove(id);
+        }
+
+        VersionExport {
+            rcu: Arc::downgrade(self),
+            id,
+        }
+    }
+}
+
+fn write_frame<T: Serialize>(writer: &mut impl Write, version: &T) -> Result<(), PersistError> {
+    let json = serde_json::to_vec(version)?;
+    writer.write_all(&(json.len() as u64).to_le_bytes())?;
+    writer.write_all(&json)?;
+    writer.flush()?;
+    Ok(())
+}
+
+impl<T: DeserializeOwned, S: Reclaim<T>> Rcu<T, S> {
+    /// Reads the versions exported by [`export_versions`](Self::export_versions) from `reader`,
+    /// and writes each of them as a new version, until `reader` ends.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PersistError::Io`] if `reader` fails, [`PersistError::Json`] if a version can't
+    /// be parsed and [`PersistError::Write`] if a version can't be written.
+    pub fn follow<R: Read>(&self, mut reader: R) -> Result<(), PersistError> {
+        loop {
+            let mut len = [0; 8];
+            match reader.read_exact(&mut len) {
+                Ok(()) => {}
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+                Err(err) => return Err(err.into()),
+            }
+            let len = usize::try_from(u64::from_le_bytes(len))
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+            let mut json = vec![0; len];
+            reader.read_exact(&mut json)?;
+            let value = serde_json::from_slice(&json)?;
+            self.try_write(Arc::new(value))
+                .map_err(PersistError::Write)?;
+        }
+    }
+}
+
+/// Stops exporting versions when dropped, returned by [`Rcu::export_versions`]
+#[must_use = "exporting stops when the handle is dropped"]
+pub struct VersionExport<T, S> {
+    rcu: Weak<Rcu<T, S>>,
+    id: usize,
+}
+
+impl<T, S> Drop for VersionExport<T, S> {
+    fn drop(&mut self) {
+        if let Some(rcu) = self.rcu.upgrade() {
+            rcu.hooks.remove(self.id);
+        }
+    }
+}
+
+impl<T, S> fmt::Debug for VersionExport<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("VersionExport");
+        d.field("id", &self.id);
+        d.finish_non_exhaustive()
+    }
+}
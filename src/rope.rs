@@ -0,0 +1,157 @@
+//! Large texts which are edited without cloning them
+
+use core::{
+    fmt,
+    ops::{Bound, RangeBounds},
+};
+
+use ropey::Rope;
+
+use crate::{Arc, Rcu};
+
+/// A text stored as a [`Rope`], e.g. the buffer of a collaborative editor
+///
+/// Ropes share their nodes when cloned, so each edit creates a new version in `O(log n)` instead
+/// of cloning the whole text, and readers keep a consistent snapshot while edits are written.
+/// Positions are in chars, like in `ropey`.
+///
+/// Edits are applied to the version which is current when they're written, so an edit made from a
+/// stale snapshot may land at a shifted position. Use [`Rcu::update_checked`] on
+/// [`rcu`](Self::rcu) to reject such edits instead.
+///
+/// # Example
+///
+/// ```
+/// use axka_rcu::RcuString;
+/// let buffer = RcuString::from("Hello world");
+///
+/// let snapshot = buffer.read();
+/// buffer.insert(5, ",");
+/// buffer.replace(7..12, "there");
+/// buffer.insert(12, "!");
+///
+/// assert_eq!(*snapshot, "Hello world");
+/// assert_eq!(buffer.to_string(), "Hello, there!");
+/// ```
+pub struct RcuString {
+    rcu: Rcu<Rope>,
+}
+
+impl RcuString {
+    /// Creates an empty `RcuString`.
+    pub fn new() -> Self {
+        Self::from_rope(Rope::new())
+    }
+
+    /// Creates an `RcuString` containing `rope`.
+    pub fn from_rope(rope: Rope) -> Self {
+        Self {
+            rcu: Rcu::new(Arc::new(rope)),
+        }
+    }
+
+    /// Inserts `text` at `char_idx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds.
+    #[track_caller]
+    pub fn insert(&self, char_idx: usize, text: &str) {
+        self.update(|rope| rope.insert(char_idx, text));
+    }
+
+    /// Removes the chars in `char_range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_range` is out of bounds.
+    #[track_caller]
+    pub fn remove<R>(&self, char_range: R)
+    where
+        R: RangeBounds<usize> + Clone,
+    {
+        self.update(|rope| rope.remove(char_range.clone()));
+    }
+
+    /// Replaces the chars in `char_range` with `text` in one version.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_range` is out of bounds.
+    #[track_caller]
+    pub fn replace<R>(&self, char_range: R, text: &str)
+    where
+        R: RangeBounds<usize> + Clone,
+    {
+        self.update(|rope| {
+            let start = match char_range.start_bound() {
+                Bound::Included(&start) => start,
+                Bound::Excluded(&start) => start + 1,
+                Bound::Unbounded => 0,
+            };
+            rope.remove(char_range.clone());
+            rope.insert(start, text);
+        });
+    }
+
+    /// Runs `updater` on a clone of the current version and writes it, retrying if another edit
+    /// was written concurrently, see [`Rcu::update_retry`].
+    ///
+    /// Cloning the rope doesn't clone the text.
+    #[track_caller]
+    pub fn update<F, R>(&self, updater: F) -> R
+    where
+        F: FnMut(&mut Rope) -> R,
+    {
+        self.rcu.update_retry(updater)
+    }
+
+    /// Returns the current version.
+    pub fn read(&self) -> Arc<Rope> {
+        self.rcu.read()
+    }
+
+    /// Returns the number of chars in the current version.
+    pub fn len_chars(&self) -> usize {
+        self.rcu.read_with(Rope::len_chars)
+    }
+
+    /// Returns the `Rcu` holding the versions.
+    pub fn rcu(&self) -> &Rcu<Rope> {
+        &self.rcu
+    }
+}
+
+impl Default for RcuString {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<&str> for RcuString {
+    fn from(text: &str) -> Self {
+        Self::from_rope(Rope::from_str(text))
+    }
+}
+
+impl From<Rope> for RcuString {
+    fn from(rope: Rope) -> Self {
+        Self::from_rope(rope)
+    }
+}
+
+/// Writes the current version.
+impl fmt::Display for RcuString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.rcu.read_with(|rope| fmt::Display::fmt(rope, f))
+    }
+}
+
+impl fmt::Debug for RcuString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("RcuString");
+        d.field("len_chars", &self.len_chars());
+        d.field("generation", &self.rcu.generation());
+        d.finish_non_exhaustive()
+    }
+}
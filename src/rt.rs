@@ -0,0 +1,220 @@
+//! Real-time mode, where reads never allocate, free or lock, and writes never free
+//!
+//! In debug builds, the guarantees are checked by [`RtAllocator`] while a thread is in an
+//! [`RtSection`], or inside the read and write paths of a real-time `Rcu`.
+
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    cell::Cell,
+    fmt,
+    marker::PhantomData,
+};
+use std::{alloc::System, io::Write};
+
+use crate::{Rcu, Reclaim};
+
+/// Bit of [`FORBIDDEN`] which is set when allocating is forbidden
+const ALLOC: u8 = 1;
+/// Bit of [`FORBIDDEN`] which is set when freeing is forbidden
+const DEALLOC: u8 = 2;
+
+/// The operations forbidden on a read of a real-time `Rcu`
+pub(crate) const READ: u8 = ALLOC | DEALLOC;
+/// The operations forbidden on a write of a real-time `Rcu`
+pub(crate) const WRITE: u8 = DEALLOC;
+
+std::thread_local! {
+    /// The operations forbidden on the current thread
+    static FORBIDDEN: Cell<u8> = const { Cell::new(0) };
+}
+
+impl<T, S: Reclaim<T>> Rcu<T, S> {
+    /// Switches the `Rcu` to real-time mode, for readers which must have bounded latency, e.g.
+    /// audio or control loops.
+    ///
+    /// In real-time mode:
+    /// - [`read_guard`](Self::read_guard), [`read_with`](Self::read_with) and
+    ///   [`read_session`](Self::read_session) never allocate, free memory or take a lock.
+    /// - Writes never free memory themselves. Replaced versions are kept until
+    ///   [`flush_deferred`](Self::flush_deferred) or [`synchronize`](Self::synchronize) is called,
+    ///   e.g. periodically on a non-real-time thread, which then hands them to the [`Reclaim`]
+    ///   strategy.
+    ///
+    /// The `Arc` returned by [`read`](Self::read) may free its version when dropped after it was
+    /// reclaimed, so real-time threads should use guards instead. Installed
+    /// [hooks](crate::RcuHooks) and callbacks run inside the read and write paths, so they have to
+    /// keep the guarantees too.
+    ///
+    /// In debug builds, violations abort the process when [`RtAllocator`] is the global allocator.
+    /// The `lineage` and `audit` features free old records on writes, so they violate the
+    /// guarantees.
+    ///
+    /// # Example
+    ///
+    #[cfg_attr(any(feature = "lineage", feature = "audit"), doc = "```ignore")]
+    #[cfg_attr(not(any(feature = "lineage", feature = "audit")), doc = "```")]
+    /// # use std::sync::Arc;
+    /// use std::alloc::System;
+    ///
+    /// use axka_rcu::{Rcu, RtAllocator, RtSection};
+    ///
+    /// #[global_allocator]
+    /// static ALLOCATOR: RtAllocator = RtAllocator::new(System);
+    ///
+    /// fn main() {
+    ///     let gain = Rcu::new(Arc::new(0.5f32)).realtime();
+    ///     let first = gain.read();
+    ///     gain.write(Arc::new(0.8));
+    ///
+    ///     // On the audio thread
+    ///     let sample = {
+    ///         let _section = RtSection::enter();
+    ///         *gain.read_guard() * 0.25
+    ///     };
+    ///     assert_eq!(sample, 0.2);
+    ///
+    ///     // On a control thread
+    ///     assert_eq!(Arc::strong_count(&first), 2);
+    ///     gain.flush_deferred();
+    ///     assert_eq!(Arc::strong_count(&first), 1);
+    /// }
+    /// ```
+    pub fn realtime(mut self) -> Self {
+        self.readers.defer();
+        self
+    }
+
+    /// Returns `true` if the `Rcu` is in [real-time mode](Self::realtime).
+    pub fn is_realtime(&self) -> bool {
+        self.readers.is_deferring()
+    }
+
+    /// Forbids `operations` on the current thread until the returned value is dropped, if the
+    /// guarantees are checked.
+    #[inline]
+    pub(crate) fn forbid(&self, operations: u8) -> Option<Forbid> {
+        (cfg!(debug_assertions) && self.is_realtime()).then(|| Forbid::enter(operations))
+    }
+}
+
+/// Restores the forbidden operations of the thread when dropped
+pub(crate) struct Forbid {
+    previous: u8,
+    /// The thread-local state must be restored on the same thread
+    _marker: PhantomData<*const ()>,
+}
+
+impl Forbid {
+    fn enter(operations: u8) -> Self {
+        let previous = FORBIDDEN.with(|forbidden| forbidden.replace(forbidden.get() | operations));
+        Self {
+            previous,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl Drop for Forbid {
+    fn drop(&mut self) {
+        FORBIDDEN.with(|forbidden| forbidden.set(self.previous));
+    }
+}
+
+/// Marks the current thread as real-time until dropped, so allocating or freeing memory is a
+/// bug
+///
+/// In debug builds, [`RtAllocator`] aborts the process on such a bug. In release builds, this
+/// does nothing. Sections can be nested.
+///
+/// See [`Rcu::realtime`] for an example.
+pub struct RtSection {
+    forbid: Option<Forbid>,
+}
+
+impl RtSection {
+    /// Enters a real-time section on the current thread.
+    #[inline]
+    pub fn enter() -> Self {
+        Self {
+            forbid: cfg!(debug_assertions).then(|| Forbid::enter(ALLOC | DEALLOC)),
+        }
+    }
+}
+
+impl fmt::Debug for RtSection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("RtSection");
+        d.field("checked", &self.forbid.is_some());
+        d.finish()
+    }
+}
+
+/// A global allocator which checks the guarantees of [`RtSection`]s and [real-time
+/// `Rcu`s](Rcu::realtime) in debug builds, by wrapping another allocator
+///
+/// Allocating or freeing memory where it's forbidden prints the violation and aborts the
+/// process. In release builds, this only forwards to the wrapped allocator.
+///
+/// See [`Rcu::realtime`] for an example.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RtAllocator<A = System>(A);
+
+impl<A> RtAllocator<A> {
+    /// Wraps `allocator`.
+    pub const fn new(allocator: A) -> Self {
+        Self(allocator)
+    }
+}
+
+// SAFETY: All allocations are forwarded to the wrapped allocator
+unsafe impl<A: GlobalAlloc> GlobalAlloc for RtAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        check(ALLOC);
+        unsafe { self.0.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        check(ALLOC);
+        unsafe { self.0.alloc_zeroed(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        check(DEALLOC);
+        unsafe { self.0.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        check(ALLOC | DEALLOC);
+        unsafe { self.0.realloc(ptr, layout, new_size) }
+    }
+}
+
+/// Aborts if `operation` is forbidden on the current thread.
+#[inline]
+fn check(operation: u8) {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+    // The thread-local may already be destroyed while the thread exits
+    let forbidden = FORBIDDEN.try_with(Cell::get).unwrap_or(0);
+    if forbidden & operation != 0 {
+        violation(forbidden & operation);
+    }
+}
+
+#[cold]
+#[inline(never)]
+fn violation(operation: u8) -> ! {
+    // Allocators must not unwind, so abort instead of panicking
+    let _ = FORBIDDEN.try_with(|forbidden| forbidden.set(0));
+    let what = if operation & ALLOC != 0 {
+        "allocated"
+    } else {
+        "freed"
+    };
+    let _ = writeln!(
+        std::io::stderr(),
+        "axka-rcu: memory was {what} in a real-time section"
+    );
+    std::process::abort();
+}
@@ -0,0 +1,185 @@
+//! Sharing an `IsrRcu` between the tasks of an RTIC app
+
+use core::fmt;
+
+use rtic_core::Mutex;
+
+use crate::{Arc, IsrRcu, ReadGuard, Reclaim, RefCount};
+
+impl<T, S: Reclaim<T>> IsrRcu<T, S> {
+    /// Splits a unique borrow into a [`Copy`] reader and the only writer, e.g. for the local
+    /// resources of RTIC tasks.
+    ///
+    /// Since reads never lock, the handles don't have to be shared resources: RTIC doesn't raise
+    /// any priority ceiling for them, so a read in a high-priority interrupt handler is never
+    /// blocked by a lower-priority task, not even one which is preempted in the middle of a
+    /// write. Writes allocate and drop versions, so the writer belongs to a low-priority task or
+    /// `idle`.
+    ///
+    /// The writer implements [`rtic_core::Mutex`] with [`update`](IsrRcu::update) semantics, so
+    /// code written against shared RTIC resources can use it as well. As it's the only writer, no
+    /// update can be lost.
+    ///
+    /// # Example
+    ///
+    /// An app which reads a calibration in an interrupt handler and updates it in a software
+    /// task:
+    ///
+    /// ```ignore
+    /// #[rtic::app(device = stm32f4xx_hal::pac, dispatchers = [EXTI0])]
+    /// mod app {
+    ///     use alloc::{boxed::Box, sync::Arc};
+    ///     use axka_rcu::{IsrReader, IsrRcu, IsrWriter};
+    ///     use rtic::Mutex;
+    ///
+    ///     #[shared]
+    ///     struct Shared {}
+    ///
+    ///     #[local]
+    ///     struct Local {
+    ///         reader: IsrReader<'static, Calibration>,
+    ///         writer: IsrWriter<'static, Calibration>,
+    ///     }
+    ///
+    ///     #[init]
+    ///     fn init(cx: init::Context) -> (Shared, Local) {
+    ///         let rcu = Box::leak(Box::new(IsrRcu::new(Arc::new(Calibration::default()))));
+    ///         let (reader, writer) = rcu.split();
+    ///         calibrate::spawn().unwrap();
+    ///         (Shared {}, Local { reader, writer })
+    ///     }
+    ///
+    ///     #[task(binds = ADC, priority = 3, local = [reader])]
+    ///     fn on_sample(cx: on_sample::Context) {
+    ///         let offset = cx.local.reader.read().offset;
+    ///         // ...
+    ///     }
+    ///
+    ///     #[task(priority = 1, local = [writer])]
+    ///     async fn calibrate(cx: calibrate::Context) {
+    ///         cx.local.writer.lock(|calibration| calibration.offset = -3);
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// The writer works with code which is generic over RTIC resources:
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::IsrRcu;
+    /// use rtic_core::Mutex;
+    ///
+    /// fn increment(counter: &mut impl Mutex<T = u32>) {
+    ///     counter.lock(|counter| *counter += 1);
+    /// }
+    ///
+    /// let mut rcu = IsrRcu::new(Arc::new(0));
+    /// let (reader, mut writer) = rcu.split();
+    ///
+    /// increment(&mut writer);
+    /// assert_eq!(*reader.read(), 1);
+    /// ```
+    pub fn split(&mut self) -> (IsrReader<'_, T, S>, IsrWriter<'_, T, S>) {
+        let rcu = &*self;
+        (IsrReader { rcu }, IsrWriter { rcu })
+    }
+}
+
+/// A handle for reading an [`IsrRcu`], returned by [`IsrRcu::split`]
+pub struct IsrReader<'a, T, S = RefCount> {
+    rcu: &'a IsrRcu<T, S>,
+}
+
+impl<'a, T, S: Reclaim<T>> IsrReader<'a, T, S> {
+    /// Returns a guard to the current version, see [`IsrRcu::read`].
+    #[inline]
+    pub fn read(&self) -> ReadGuard<'a, T> {
+        self.rcu.read()
+    }
+
+    /// Runs `f` on a reference to the current version, see [`IsrRcu::read_with`].
+    #[inline]
+    pub fn read_with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        self.rcu.read_with(f)
+    }
+
+    /// Returns the generation of the current version, see [`IsrRcu::generation`].
+    #[inline]
+    pub fn generation(&self) -> u64 {
+        self.rcu.generation()
+    }
+}
+
+impl<T, S> Clone for IsrReader<'_, T, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, S> Copy for IsrReader<'_, T, S> {}
+
+impl<T: fmt::Debug, S: Reclaim<T>> fmt::Debug for IsrReader<'_, T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("IsrReader");
+        d.field("rcu", self.rcu);
+        d.finish()
+    }
+}
+
+/// The only handle for writing an [`IsrRcu`], returned by [`IsrRcu::split`]
+///
+/// This must not be used in an interrupt handler, except in RTIC tasks whose priority is lower
+/// than the ones of all readers.
+pub struct IsrWriter<'a, T, S = RefCount> {
+    rcu: &'a IsrRcu<T, S>,
+}
+
+impl<'a, T, S: Reclaim<T>> IsrWriter<'a, T, S> {
+    /// Returns a guard to the current version, see [`IsrRcu::read`].
+    #[inline]
+    pub fn read(&self) -> ReadGuard<'a, T> {
+        self.rcu.read()
+    }
+
+    /// Writes a new version.
+    ///
+    /// # Panics
+    ///
+    /// See [`Rcu::write`](crate::Rcu::write).
+    pub fn write(&mut self, new_value: Arc<T>) {
+        self.rcu.write(new_value);
+    }
+
+    /// Returns a reader for the same `IsrRcu`.
+    pub fn reader(&self) -> IsrReader<'a, T, S> {
+        IsrReader { rcu: self.rcu }
+    }
+}
+
+/// Clones the current version, runs `f` on it and writes it.
+///
+/// # Panics
+///
+/// See [`Rcu::write`](crate::Rcu::write).
+impl<T: Clone, S: Reclaim<T>> Mutex for IsrWriter<'_, T, S> {
+    type T = T;
+
+    fn lock<R>(&mut self, f: impl FnOnce(&mut T) -> R) -> R {
+        let mut new_value = T::clone(&self.rcu.read());
+        let ret = f(&mut new_value);
+        self.rcu.write(Arc::new(new_value));
+        ret
+    }
+}
+
+impl<T: fmt::Debug, S: Reclaim<T>> fmt::Debug for IsrWriter<'_, T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("IsrWriter");
+        d.field("rcu", self.rcu);
+        d.finish()
+    }
+}
@@ -0,0 +1,316 @@
+//! An `Rcu` built only on safe code, for comparing against the lock-free one
+#![forbid(unsafe_code)]
+
+use core::fmt;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::{
+    errors::{Conflict, WriteError},
+    RcuLike,
+};
+
+/// An [`Rcu`](crate::Rcu) built on an `RwLock<Arc<T>>`, without any unsafe code of its own
+///
+/// This has the same core methods as `Rcu`, so it can be swapped in with a type alias, e.g. to
+/// A/B test the lock-free implementation or to rule it out when debugging. Reads take a read lock
+/// for as long as cloning the `Arc` takes, and writes wait for them. Validators, hooks, reclaim
+/// strategies and the feature-gated methods of `Rcu` aren't supported.
+///
+/// # Example
+///
+/// ```
+/// # use std::sync::Arc;
+/// // Switched to `axka_rcu::Rcu<T>` when not debugging
+/// type Rcu<T> = axka_rcu::SafeRcu<T>;
+///
+/// let rcu: Rcu<String> = Rcu::new(Arc::new("foo".to_owned()));
+/// let (_, generation) = rcu.read_versioned();
+///
+/// rcu.update(|s| s.push_str(" bar"));
+/// assert_eq!(*rcu.read(), "foo bar");
+/// assert!(rcu.update_checked(generation, |s| s.clear()).is_err());
+/// assert_eq!(rcu.generation(), 1);
+/// ```
+pub struct SafeRcu<T> {
+    state: RwLock<State<T>>,
+}
+
+struct State<T> {
+    current: Arc<T>,
+    generation: u64,
+    frozen: bool,
+}
+
+impl<T> SafeRcu<T> {
+    /// Creates a new `SafeRcu` with `value` as the current version.
+    pub fn new(value: Arc<T>) -> Self {
+        Self {
+            state: RwLock::new(State {
+                current: value,
+                generation: 0,
+                frozen: false,
+            }),
+        }
+    }
+
+    // A panicking updater runs outside of the lock, so the state is never left half-written
+    fn state(&self) -> RwLockReadGuard<'_, State<T>> {
+        self.state.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn state_mut(&self) -> RwLockWriteGuard<'_, State<T>> {
+        self.state.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the current version.
+    pub fn read(&self) -> Arc<T> {
+        Arc::clone(&self.state().current)
+    }
+
+    /// Runs `f` on the current version.
+    ///
+    /// Unlike [`Rcu::read_with`](crate::Rcu::read_with), the version is cloned out of the lock
+    /// first, so `f` doesn't block writers.
+    pub fn read_with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        f(&self.read())
+    }
+
+    /// Returns the current version and its [generation](Self::generation).
+    pub fn read_versioned(&self) -> (Arc<T>, u64) {
+        let state = self.state();
+        (Arc::clone(&state.current), state.generation)
+    }
+
+    /// Returns the number of versions written after the initial one.
+    pub fn generation(&self) -> u64 {
+        self.state().generation
+    }
+
+    /// Consumes the `SafeRcu`, returning the current version.
+    pub fn into_arc(self) -> Arc<T> {
+        self.state
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+            .current
+    }
+
+    /// Consumes the `SafeRcu`, returning the current version if no `Arc` returned by
+    /// [`read`](Self::read) still references it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the `SafeRcu` back if the current version is still referenced.
+    pub fn try_unwrap(self) -> Result<T, Self> {
+        let State {
+            current,
+            generation,
+            frozen,
+        } = self
+            .state
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner);
+        Arc::try_unwrap(current).map_err(|current| Self {
+            state: RwLock::new(State {
+                current,
+                generation,
+                frozen,
+            }),
+        })
+    }
+
+    /// Writes a new version.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `SafeRcu` is [frozen](Self::freeze).
+    #[track_caller]
+    pub fn write(&self, new_value: Arc<T>) {
+        if let Err(err) = self.try_write(new_value) {
+            panic!("{err}");
+        }
+    }
+
+    /// Writes a new version, unless the `SafeRcu` is [frozen](Self::freeze).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`WriteError::Frozen`] if the `SafeRcu` is frozen.
+    pub fn try_write(&self, new_value: Arc<T>) -> Result<(), WriteError> {
+        let old = {
+            let mut state = self.state_mut();
+            if state.frozen {
+                return Err(WriteError::Frozen);
+            }
+            state.generation += 1;
+            core::mem::replace(&mut state.current, new_value)
+        };
+        // Dropped outside of the lock
+        drop(old);
+        Ok(())
+    }
+
+    /// Writes `new_value` if `current` is still the current version.
+    ///
+    /// On failure, `new_value` is returned back.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `SafeRcu` is [frozen](Self::freeze).
+    #[track_caller]
+    pub fn compare_exchange(&self, current: &Arc<T>, new_value: Arc<T>) -> Result<(), Arc<T>> {
+        let old = {
+            let mut state = self.state_mut();
+            assert!(!state.frozen, "{}", WriteError::Frozen);
+            if !Arc::ptr_eq(&state.current, current) {
+                return Err(new_value);
+            }
+            state.generation += 1;
+            core::mem::replace(&mut state.current, new_value)
+        };
+        drop(old);
+        Ok(())
+    }
+
+    /// Clones `T`, runs `updater` on `T` and [`write`](Self::write)s `T`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `SafeRcu` is [frozen](Self::freeze).
+    #[track_caller]
+    pub fn update<F, R>(&self, updater: F)
+    where
+        T: Clone,
+        F: FnOnce(&mut T) -> R,
+    {
+        if let Err(err) = self.try_update(updater) {
+            panic!("{err}");
+        }
+    }
+
+    /// Like [`update`](Self::update), but returns an error instead of panicking if the
+    /// `SafeRcu` is [frozen](Self::freeze).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`WriteError::Frozen`] if the `SafeRcu` is frozen.
+    pub fn try_update<F, R>(&self, updater: F) -> Result<(), WriteError>
+    where
+        T: Clone,
+        F: FnOnce(&mut T) -> R,
+    {
+        if self.is_frozen() {
+            return Err(WriteError::Frozen);
+        }
+        let mut value = T::clone(&self.read());
+        updater(&mut value);
+        self.try_write(Arc::new(value))
+    }
+
+    /// Clones `T`, runs `updater` on `T` and [`write`](Self::write)s `T`, retrying if another
+    /// version was written concurrently.
+    ///
+    /// The return value of the call that got written is returned.
+    #[track_caller]
+    pub fn update_retry<F, R>(&self, mut updater: F) -> R
+    where
+        T: Clone,
+        F: FnMut(&mut T) -> R,
+    {
+        let mut current = self.read();
+        loop {
+            let mut value = T::clone(&current);
+            let ret = updater(&mut value);
+            match self.compare_exchange(&current, Arc::new(value)) {
+                Ok(()) => return ret,
+                Err(_) => current = self.read(),
+            }
+        }
+    }
+
+    /// Clones `T`, runs `updater` on `T` and [`write`](Self::write)s `T` if the current
+    /// [generation](Self::generation) is `expected`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Conflict`] if another version was written after `expected`, including while
+    /// `updater` was running.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `SafeRcu` is [frozen](Self::freeze).
+    #[track_caller]
+    pub fn update_checked<F, R>(&self, expected: u64, updater: F) -> Result<(), Conflict>
+    where
+        T: Clone,
+        F: FnOnce(&mut T) -> R,
+    {
+        let (current, generation) = self.read_versioned();
+        if generation != expected {
+            return Err(Conflict {
+                current: generation,
+            });
+        }
+
+        let mut value = T::clone(&current);
+        updater(&mut value);
+        let old = {
+            let mut state = self.state_mut();
+            assert!(!state.frozen, "{}", WriteError::Frozen);
+            if state.generation != expected {
+                return Err(Conflict {
+                    current: state.generation,
+                });
+            }
+            state.generation += 1;
+            core::mem::replace(&mut state.current, Arc::new(value))
+        };
+        drop(old);
+        Ok(())
+    }
+
+    /// Makes every subsequent write fail, keeping the current version forever.
+    pub fn freeze(&self) {
+        self.state_mut().frozen = true;
+    }
+
+    /// Returns `true` if the `SafeRcu` is [frozen](Self::freeze).
+    pub fn is_frozen(&self) -> bool {
+        self.state().frozen
+    }
+}
+
+impl<T> RcuLike<T> for SafeRcu<T> {
+    fn read(&self) -> Arc<T> {
+        SafeRcu::read(self)
+    }
+
+    #[track_caller]
+    fn write(&self, new_value: Arc<T>) {
+        SafeRcu::write(self, new_value);
+    }
+}
+
+impl<T: Default> Default for SafeRcu<T> {
+    fn default() -> Self {
+        Self::new(Arc::new(T::default()))
+    }
+}
+
+impl<T> From<T> for SafeRcu<T> {
+    fn from(value: T) -> Self {
+        Self::new(Arc::new(value))
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SafeRcu<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (current, generation) = self.read_versioned();
+        let mut d = f.debug_struct("SafeRcu");
+        d.field("data", &current);
+        d.field("generation", &generation);
+        d.finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,43 @@
+//! Serde serialization of the current version
+
+use ::serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{Arc, Rcu, Reclaim};
+
+/// Serializes the current version.
+///
+/// # Example
+///
+/// ```
+#[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+#[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+/// use axka_rcu::Rcu;
+/// let rcu = Rcu::new(Arc::new(vec![1, 2]));
+///
+/// let json = serde_json::to_string(&rcu).unwrap();
+/// assert_eq!(json, "[1,2]");
+/// let rcu2: Rcu<Vec<u32>> = serde_json::from_str(&json).unwrap();
+/// assert_eq!(*rcu2.read(), [1, 2]);
+/// ```
+impl<T: Serialize, S: Reclaim<T>> Serialize for Rcu<T, S> {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        self.read_with(|value| value.serialize(serializer))
+    }
+}
+
+/// Deserializes a value into a new `Rcu`.
+///
+/// Deserializing in place writes the value to the existing `Rcu` as a new version, see
+/// [`serde_arc::deserialize_into`](crate::serde_arc::deserialize_into).
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Rcu<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(|value| Self::new(Arc::new(value)))
+    }
+
+    fn deserialize_in_place<D: Deserializer<'de>>(
+        deserializer: D,
+        place: &mut Self,
+    ) -> Result<(), D::Error> {
+        crate::serde_arc::deserialize_into(place, deserializer)
+    }
+}
@@ -0,0 +1,83 @@
+//! Helpers for `Rcu` fields of types deriving serde's traits
+//!
+//! Use the module with `#[serde(with = "axka_rcu::serde_arc")]`. Unlike the `Deserialize` impl of
+//! `Rcu`, this works with any [`Reclaim`] strategy implementing `Default`.
+//!
+//! # Example
+//!
+//! ```
+#![cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+#![cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+//! use axka_rcu::{Deferred, Rcu};
+//!
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct Config {
+//!     #[serde(with = "axka_rcu::serde_arc")]
+//!     limits: Rcu<Vec<u32>, Deferred<Vec<u32>>>,
+//!     #[serde(
+//!         with = "axka_rcu::serde_arc",
+//!         default,
+//!         skip_serializing_if = "axka_rcu::serde_arc::is_default"
+//!     )]
+//!     name: Rcu<String>,
+//! }
+//!
+//! let config: Config = serde_json::from_str(r#"{ "limits": [1, 2] }"#).unwrap();
+//! assert_eq!(*config.limits.read(), [1, 2]);
+//! assert_eq!(serde_json::to_string(&config).unwrap(), r#"{"limits":[1,2]}"#);
+//!
+//! // Publish a new version instead of replacing the `Rcu`
+//! let mut deserializer = serde_json::Deserializer::from_str("[3]");
+//! axka_rcu::serde_arc::deserialize_into(&config.limits, &mut deserializer).unwrap();
+//! assert_eq!(*config.limits.read(), [3]);
+//! ```
+
+use ::serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{Arc, Rcu, Reclaim};
+
+/// Serializes the current version.
+pub fn serialize<T, S, Ser>(rcu: &Rcu<T, S>, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+where
+    T: Serialize,
+    S: Reclaim<T>,
+    Ser: Serializer,
+{
+    rcu.read_with(|value| value.serialize(serializer))
+}
+
+/// Deserializes a value into a new `Rcu` with the default [`Reclaim`] strategy `S`.
+pub fn deserialize<'de, T, S, D>(deserializer: D) -> Result<Rcu<T, S>, D::Error>
+where
+    T: Deserialize<'de>,
+    S: Reclaim<T> + Default,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(|value| Rcu::with_reclaim(Arc::new(value), S::default()))
+}
+
+/// Deserializes a value and [writes](Rcu::try_write) it to `rcu` as a new version.
+///
+/// # Errors
+///
+/// Returns the error of the deserializer, or a custom error if the value can't be written, see
+/// [`Rcu::try_write`].
+pub fn deserialize_into<'de, T, S, D>(rcu: &Rcu<T, S>, deserializer: D) -> Result<(), D::Error>
+where
+    T: Deserialize<'de>,
+    S: Reclaim<T>,
+    D: Deserializer<'de>,
+{
+    let value = T::deserialize(deserializer)?;
+    rcu.try_write(Arc::new(value)).map_err(D::Error::custom)
+}
+
+/// Returns `true` if the current version is `T::default()`, for use with
+/// `#[serde(skip_serializing_if = "axka_rcu::serde_arc::is_default")]`.
+pub fn is_default<T, S>(rcu: &Rcu<T, S>) -> bool
+where
+    T: Default + PartialEq,
+    S: Reclaim<T>,
+{
+    rcu.read_with(|value| *value == T::default())
+}
@@ -0,0 +1,249 @@
+//! Helpers for `Rcu` fields which are serialized with a schema version, and migrated from older
+//! schemas when they're deserialized
+//!
+//! Use the module with `#[serde(with = "axka_rcu::serde_versioned")]`. The version is serialized
+//! as `{ "version": 2, "data": ... }`, where `version` is [`Migrate::VERSION`]. When an older
+//! version is deserialized, it's deserialized as the type of its schema and migrated step by step
+//! along the [`Migrate::Previous`] chain.
+//!
+//! # Example
+//!
+//! ```
+//! use axka_rcu::{
+//!     serde_versioned::{Migrate, NoPrevious},
+//!     Rcu,
+//! };
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Deserialize)]
+//! struct ConfigV1 {
+//!     port: u16,
+//! }
+//!
+//! impl Migrate for ConfigV1 {
+//!     const VERSION: u32 = 1;
+//!     type Previous = NoPrevious;
+//!     fn migrate(previous: NoPrevious) -> Self {
+//!         match previous {}
+//!     }
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     addr: String,
+//! }
+//!
+//! impl Migrate for Config {
+//!     const VERSION: u32 = 2;
+//!     type Previous = ConfigV1;
+//!     fn migrate(previous: ConfigV1) -> Self {
+//!         Self { addr: format!("0.0.0.0:{}", previous.port) }
+//!     }
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct State {
+//!     #[serde(with = "axka_rcu::serde_versioned")]
+//!     config: Rcu<Config>,
+//! }
+//!
+//! let state: State =
+//!     serde_json::from_str(r#"{ "config": { "version": 1, "data": { "port": 80 } } }"#).unwrap();
+//! assert_eq!(state.config.read().addr, "0.0.0.0:80");
+//! assert_eq!(
+//!     serde_json::to_string(&state).unwrap(),
+//!     r#"{"config":{"version":2,"data":{"addr":"0.0.0.0:80"}}}"#,
+//! );
+//! ```
+
+use core::{fmt, marker::PhantomData};
+
+use ::serde::{
+    de::{self, DeserializeOwned, DeserializeSeed, Error, MapAccess, SeqAccess, Visitor},
+    ser::SerializeStruct,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{Arc, Rcu, Reclaim};
+
+/// A schema of a serialized version, which can be migrated from the previous schema
+pub trait Migrate: DeserializeOwned {
+    /// The version of this schema, which must be greater than the one of
+    /// [`Previous`](Self::Previous)
+    const VERSION: u32;
+
+    /// The previous schema, or [`NoPrevious`] for the first one
+    type Previous: Migrate;
+
+    /// Converts a value of the previous schema to this one.
+    fn migrate(previous: Self::Previous) -> Self;
+}
+
+/// The [`Migrate::Previous`] schema of the first schema, which can't be deserialized
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NoPrevious {}
+
+impl<'de> Deserialize<'de> for NoPrevious {
+    fn deserialize<D: Deserializer<'de>>(_: D) -> Result<Self, D::Error> {
+        Err(D::Error::custom("no schema before the first one"))
+    }
+}
+
+impl Migrate for NoPrevious {
+    const VERSION: u32 = 0;
+    type Previous = Self;
+
+    fn migrate(previous: Self) -> Self {
+        previous
+    }
+}
+
+/// Deserializes a value of the schema `version`, and migrates it to `T`.
+fn deserialize_version<'de, T: Migrate, D: Deserializer<'de>>(
+    version: u32,
+    deserializer: D,
+) -> Result<T, D::Error> {
+    if version == T::VERSION {
+        return T::deserialize(deserializer);
+    }
+    // The check of `Previous` stops the recursion at `NoPrevious`, and on chains which don't
+    // descend
+    if version > T::VERSION || T::Previous::VERSION >= T::VERSION {
+        return Err(D::Error::custom(format_args!(
+            "unknown schema version {version}"
+        )));
+    }
+    deserialize_version::<T::Previous, D>(version, deserializer).map(T::migrate)
+}
+
+struct VersionSeed<T>(u32, PhantomData<T>);
+
+impl<'de, T: Migrate> DeserializeSeed<'de> for VersionSeed<T> {
+    type Value = T;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<T, D::Error> {
+        deserialize_version(self.0, deserializer)
+    }
+}
+
+const FIELDS: &[&str] = &["version", "data"];
+
+enum Field {
+    Version,
+    Data,
+}
+
+impl<'de> Deserialize<'de> for Field {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FieldVisitor;
+
+        impl Visitor<'_> for FieldVisitor {
+            type Value = Field;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("`version` or `data`")
+            }
+
+            fn visit_u64<E: Error>(self, value: u64) -> Result<Field, E> {
+                match value {
+                    0 => Ok(Field::Version),
+                    1 => Ok(Field::Data),
+                    _ => Err(E::invalid_value(de::Unexpected::Unsigned(value), &self)),
+                }
+            }
+
+            fn visit_str<E: Error>(self, value: &str) -> Result<Field, E> {
+                match value {
+                    "version" => Ok(Field::Version),
+                    "data" => Ok(Field::Data),
+                    _ => Err(E::unknown_field(value, FIELDS)),
+                }
+            }
+        }
+
+        deserializer.deserialize_identifier(FieldVisitor)
+    }
+}
+
+struct EnvelopeVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Migrate> Visitor<'de> for EnvelopeVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a version with its schema version")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<T, A::Error> {
+        let version = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        seq.next_element_seed(VersionSeed(version, PhantomData))?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<T, A::Error> {
+        let mut version = None;
+        while let Some(field) = map.next_key()? {
+            match field {
+                Field::Version if version.is_some() => {
+                    return Err(A::Error::duplicate_field("version"))
+                }
+                Field::Version => version = Some(map.next_value()?),
+                // The schema has to be known before the data can be deserialized
+                Field::Data => {
+                    let version = version
+                        .ok_or_else(|| A::Error::custom("`version` must come before `data`"))?;
+                    return map.next_value_seed(VersionSeed(version, PhantomData));
+                }
+            }
+        }
+        Err(A::Error::missing_field("data"))
+    }
+}
+
+/// Serializes the current version with its schema version.
+pub fn serialize<T, S, Ser>(rcu: &Rcu<T, S>, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+where
+    T: Migrate + Serialize,
+    S: Reclaim<T>,
+    Ser: Serializer,
+{
+    rcu.read_with(|value| {
+        let mut envelope = serializer.serialize_struct("Versioned", 2)?;
+        envelope.serialize_field("version", &T::VERSION)?;
+        envelope.serialize_field("data", value)?;
+        envelope.end()
+    })
+}
+
+/// Deserializes a version of any schema into a new `Rcu` with the default [`Reclaim`] strategy
+/// `S`, migrating it to `T`.
+pub fn deserialize<'de, T, S, D>(deserializer: D) -> Result<Rcu<T, S>, D::Error>
+where
+    T: Migrate,
+    S: Reclaim<T> + Default,
+    D: Deserializer<'de>,
+{
+    deserializer
+        .deserialize_struct("Versioned", FIELDS, EnvelopeVisitor(PhantomData))
+        .map(|value| Rcu::with_reclaim(Arc::new(value), S::default()))
+}
+
+/// Deserializes a version of any schema, migrates it to `T` and [writes](Rcu::try_write) it to
+/// `rcu` as a new version.
+///
+/// # Errors
+///
+/// Returns the error of the deserializer, or a custom error if the schema version is unknown or
+/// the value can't be written, see [`Rcu::try_write`].
+pub fn deserialize_into<'de, T, S, D>(rcu: &Rcu<T, S>, deserializer: D) -> Result<(), D::Error>
+where
+    T: Migrate,
+    S: Reclaim<T>,
+    D: Deserializer<'de>,
+{
+    let value =
+        deserializer.deserialize_struct("Versioned", FIELDS, EnvelopeVisitor(PhantomData))?;
+    rcu.try_write(Arc::new(value)).map_err(D::Error::custom)
+}
@@ -0,0 +1,118 @@
+//! Serialized updates with a user-provided lock
+
+use core::fmt;
+
+use lock_api::{Mutex, RawMutex};
+
+use crate::{Arc, Rcu, Reclaim, RefCount};
+
+impl<T, S: Reclaim<T>> Rcu<T, S> {
+    /// Returns a [`SerializedWriter`], which serializes the updates made through it with the lock
+    /// `R`.
+    ///
+    /// Unlike [`update_fair`](Self::update_fair), which spins while waiting, this lets you pick
+    /// the lock, e.g. a mutex provided by an RTOS on `no_std` targets.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new(0));
+    /// let writer = rcu.serialized::<parking_lot::RawMutex>();
+    ///
+    /// std::thread::scope(|s| {
+    ///     for _ in 0..4 {
+    ///         s.spawn(|| {
+    ///             for _ in 0..100 {
+    ///                 writer.update(|n| *n += 1);
+    ///             }
+    ///         });
+    ///     }
+    /// });
+    /// assert_eq!(*rcu.read(), 400);
+    /// ```
+    pub fn serialized<R: RawMutex>(&self) -> SerializedWriter<'_, T, R, S> {
+        SerializedWriter {
+            rcu: self,
+            lock: Mutex::new(()),
+        }
+    }
+}
+
+#[cfg(feature = "spin")]
+impl<T, S: Reclaim<T>> Rcu<T, S> {
+    /// Like [`serialized`](Self::serialized), but uses a spinlock from [`spin`], which works
+    /// without `std`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new(0));
+    /// let writer = rcu.serialized_spin();
+    ///
+    /// writer.update(|n| *n += 1);
+    /// assert_eq!(*rcu.read(), 1);
+    /// ```
+    pub fn serialized_spin(&self) -> SpinWriter<'_, T, S> {
+        self.serialized()
+    }
+}
+
+/// A [`SerializedWriter`] which uses a spinlock, created by [`Rcu::serialized_spin`]
+#[cfg(feature = "spin")]
+pub type SpinWriter<'a, T, S = RefCount> = SerializedWriter<'a, T, spin::Mutex<()>, S>;
+
+/// Serializes the updates to an [`Rcu`] made through it, created by [`Rcu::serialized`]
+///
+/// Share one `SerializedWriter` between the writers. Updates made directly through the `Rcu` aren't
+/// serialized.
+pub struct SerializedWriter<'a, T, R, S = RefCount> {
+    rcu: &'a Rcu<T, S>,
+    lock: Mutex<R, ()>,
+}
+
+impl<'a, T, R: RawMutex, S: Reclaim<T>> SerializedWriter<'a, T, R, S> {
+    /// Returns the `Rcu` this writes to.
+    pub fn rcu(&self) -> &'a Rcu<T, S> {
+        self.rcu
+    }
+
+    /// Like [`Rcu::update_retry`], but waits for the other updates made through this writer to
+    /// finish first.
+    ///
+    /// `updater` is only called more than once if the version is written directly through the
+    /// `Rcu` at the same time.
+    pub fn update<F, Ret>(&self, updater: F) -> Ret
+    where
+        T: Clone,
+        F: FnMut(&mut T) -> Ret,
+    {
+        let _lock = self.lock.lock();
+        self.rcu.update_retry(updater)
+    }
+
+    /// Like [`Rcu::write`], but waits for the updates made through this writer to finish first.
+    ///
+    /// # Panics
+    ///
+    /// See [`Rcu::write`].
+    #[track_caller]
+    pub fn write(&self, new_value: Arc<T>) {
+        let _lock = self.lock.lock();
+        self.rcu.write(new_value);
+    }
+}
+
+impl<T: fmt::Debug, R: RawMutex, S: Reclaim<T>> fmt::Debug for SerializedWriter<'_, T, R, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("SerializedWriter");
+        d.field("rcu", self.rcu);
+        d.field("is_locked", &self.lock.is_locked());
+        d.finish()
+    }
+}
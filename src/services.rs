@@ -0,0 +1,169 @@
+//! Registries of trait-object services, which can be swapped at runtime
+
+use alloc::vec::Vec;
+use core::{borrow::Borrow, fmt, hash::Hash};
+use std::{collections::HashMap, sync::Arc};
+
+use crate::Rcu;
+
+/// A map from keys to shared services, typically trait objects, e.g. the plugins of an extension
+/// point
+///
+/// Lookups don't lock. Registering, replacing and removing a service writes a new version of the
+/// map, so it's meant for maps which are looked up much more often than they're changed.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use axka_rcu::RcuRegistry;
+///
+/// trait Codec: Send + Sync {
+///     fn encode(&self, input: &str) -> String;
+/// }
+///
+/// struct Upper;
+/// impl Codec for Upper {
+///     fn encode(&self, input: &str) -> String {
+///         input.to_uppercase()
+///     }
+/// }
+///
+/// struct Reverse;
+/// impl Codec for Reverse {
+///     fn encode(&self, input: &str) -> String {
+///         input.chars().rev().collect()
+///     }
+/// }
+///
+/// let codecs: RcuRegistry<&str, dyn Codec> = RcuRegistry::new();
+/// codecs.register("text", Arc::new(Upper)).ok().unwrap();
+/// assert!(codecs.register("text", Arc::new(Reverse)).is_err());
+/// assert_eq!(codecs.get("text").unwrap().encode("abc"), "ABC");
+///
+/// codecs.replace("text", Arc::new(Reverse));
+/// assert_eq!(codecs.get("text").unwrap().encode("abc"), "cba");
+///
+/// codecs.remove("text");
+/// assert!(codecs.get("text").is_none());
+/// ```
+pub struct RcuRegistry<K, V: ?Sized> {
+    rcu: Rcu<HashMap<K, Arc<V>>>,
+}
+
+impl<K, V: ?Sized> RcuRegistry<K, V>
+where
+    K: Hash + Eq + Clone,
+{
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self {
+            rcu: Rcu::new(Arc::new(HashMap::new())),
+        }
+    }
+
+    /// Returns the service registered under `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<Arc<V>>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.rcu.read_with(|services| services.get(key).cloned())
+    }
+
+    /// Returns `true` if a service is registered under `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.rcu.read_with(|services| services.contains_key(key))
+    }
+
+    /// Registers `service` under `key`, unless another service is registered under it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `service` back if the key is taken.
+    pub fn register(&self, key: K, service: Arc<V>) -> Result<(), Arc<V>> {
+        if self.contains_key(&key) {
+            return Err(service);
+        }
+        self.rcu.update_retry(|services| {
+            if services.contains_key(&key) {
+                return Err(Arc::clone(&service));
+            }
+            services.insert(key.clone(), Arc::clone(&service));
+            Ok(())
+        })
+    }
+
+    /// Registers `service` under `key`, returning the service it replaced.
+    ///
+    /// Lookups which already returned the old service keep using it.
+    pub fn replace(&self, key: K, service: Arc<V>) -> Option<Arc<V>> {
+        self.rcu
+            .update_retry(|services| services.insert(key.clone(), Arc::clone(&service)))
+    }
+
+    /// Removes the service registered under `key`, and returns it.
+    pub fn remove<Q>(&self, key: &Q) -> Option<Arc<V>>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        if !self.contains_key(key) {
+            return None;
+        }
+        self.rcu.update_retry(|services| services.remove(key))
+    }
+
+    /// Returns the keys of the registered services.
+    pub fn keys(&self) -> Vec<K> {
+        self.rcu
+            .read_with(|services| services.keys().cloned().collect())
+    }
+
+    /// Returns the number of registered services.
+    pub fn len(&self) -> usize {
+        self.rcu.read_with(HashMap::len)
+    }
+
+    /// Returns `true` if no service is registered.
+    pub fn is_empty(&self) -> bool {
+        self.rcu.read_with(HashMap::is_empty)
+    }
+
+    /// Returns the current version of the map, e.g. for looking up several services in the same
+    /// version.
+    pub fn snapshot(&self) -> Arc<HashMap<K, Arc<V>>> {
+        self.rcu.read()
+    }
+
+    /// Returns the `Rcu` holding the map, e.g. for being [notified](Rcu::on_write) of changes.
+    pub fn rcu(&self) -> &Rcu<HashMap<K, Arc<V>>> {
+        &self.rcu
+    }
+}
+
+impl<K, V: ?Sized> Default for RcuRegistry<K, V>
+where
+    K: Hash + Eq + Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Formats the keys of the registered services.
+impl<K, V: ?Sized> fmt::Debug for RcuRegistry<K, V>
+where
+    K: Hash + Eq + Clone + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("RcuRegistry");
+        d.field("keys", &self.keys());
+        d.finish_non_exhaustive()
+    }
+}
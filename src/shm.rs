@@ -0,0 +1,277 @@
+//! A double-buffered value in a shared memory segment, which can be read by other processes
+//!
+//! The segment holds two slots and the index of the active one. The writer copies the new value
+//! into the standby slot once its readers are gone, and then flips the index. Readers copy the
+//! value out of the active slot, so the slots are never referenced across processes.
+
+use core::{
+    cell::UnsafeCell,
+    fmt,
+    marker::PhantomData,
+    mem,
+    ptr::{self, NonNull},
+    sync::atomic::{AtomicU32, AtomicU64, Ordering},
+};
+use std::{ffi::CString, io};
+
+/// Identifies segments created by [`ShmRcu::create`]
+const MAGIC: u64 = u64::from_le_bytes(*b"AXKARCU1");
+
+#[repr(C)]
+struct Segment<T> {
+    /// Stored last, once the rest of the segment is initialized
+    magic: AtomicU64,
+    /// The size of `T` in the process which created the segment
+    size: u64,
+    /// Held by the writer
+    writer: AtomicU32,
+    /// The index of the slot read by new readers
+    active: AtomicU32,
+    /// The number of readers of each slot
+    readers: [AtomicU32; 2],
+    slots: [UnsafeCell<T>; 2],
+}
+
+/// A value shared between processes through a named shared memory segment
+///
+/// This is an [`Rcu`](crate::Rcu)-like value for e.g. a control plane process publishing tables
+/// which are read by worker processes. Reads copy the value, and never block. Writes wait for the
+/// readers of the previous value, which only copy it.
+///
+/// Only `Copy` values without pointers or references can be shared, since the other processes
+/// can't follow them.
+///
+/// # Example
+///
+/// ```
+/// use axka_rcu::ShmRcu;
+/// # let name = format!("/axka-rcu-doc-{}", std::process::id());
+///
+/// let table = ShmRcu::create(&name, [0u32; 16]).unwrap();
+/// // In a worker process
+/// // SAFETY: The segment was created with the same type
+/// let worker = unsafe { ShmRcu::<[u32; 16]>::open(&name) }.unwrap();
+///
+/// table.write([1; 16]);
+/// assert_eq!(worker.read(), [1; 16]);
+/// ShmRcu::<[u32; 16]>::unlink(&name).unwrap();
+/// ```
+pub struct ShmRcu<T> {
+    segment: NonNull<Segment<T>>,
+    _marker: PhantomData<T>,
+}
+
+// SAFETY: The slots are only written while they have no readers, and values are copied out
+unsafe impl<T: Copy + Send> Send for ShmRcu<T> {}
+unsafe impl<T: Copy + Send> Sync for ShmRcu<T> {}
+
+impl<T: Copy> ShmRcu<T> {
+    /// Creates the shared memory segment `name` containing `value`.
+    ///
+    /// `name` should start with a slash and contain no other slashes, see `shm_open(3)`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the segment already exists or can't be created.
+    pub fn create(name: &str, value: T) -> io::Result<Self> {
+        let segment = map(name, libc::O_CREAT | libc::O_EXCL)?;
+        // SAFETY: The segment was just created and is mapped, and `open` doesn't access it before
+        // the magic number is published
+        unsafe {
+            segment.as_ptr().write(Segment {
+                magic: AtomicU64::new(0),
+                size: mem::size_of::<T>() as u64,
+                writer: AtomicU32::new(0),
+                active: AtomicU32::new(0),
+                readers: [AtomicU32::new(0), AtomicU32::new(0)],
+                slots: [UnsafeCell::new(value), UnsafeCell::new(value)],
+            });
+            segment.as_ref().magic.store(MAGIC, Ordering::Release);
+        }
+        Ok(Self {
+            segment,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Opens the shared memory segment `name`, created by [`create`](Self::create).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the segment doesn't exist, or wasn't created by `create` with a `T` of
+    /// the same size.
+    ///
+    /// # Safety
+    ///
+    /// The segment must have been created with the same `T`, and `T` must not contain pointers or
+    /// references.
+    pub unsafe fn open(name: &str) -> io::Result<Self> {
+        let segment = map::<T>(name, 0)?;
+        let this = Self {
+            segment,
+            _marker: PhantomData,
+        };
+        let header = this.segment.as_ptr();
+        // SAFETY: The segment is mapped, and a new segment is filled with zeros, which is a valid
+        // `AtomicU64`
+        let magic = unsafe { &*ptr::addr_of!((*header).magic) };
+        // Fails the check below if the creator is still initializing the segment, and otherwise
+        // makes the rest of the header visible
+        if magic.load(Ordering::Acquire) != MAGIC
+            // SAFETY: The header isn't written after the magic number is published
+            || unsafe { (*header).size } != mem::size_of::<T>() as u64
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the shared memory segment has a different type",
+            ));
+        }
+        Ok(this)
+    }
+
+    /// Removes the shared memory segment `name`.
+    ///
+    /// Processes which have it open can keep using it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the segment doesn't exist.
+    pub fn unlink(name: &str) -> io::Result<()> {
+        let name = c_name(name)?;
+        // SAFETY: The name is a valid C string
+        if unsafe { libc::shm_unlink(name.as_ptr()) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    fn segment(&self) -> &Segment<T> {
+        // SAFETY: The segment stays mapped until `self` is dropped
+        unsafe { self.segment.as_ref() }
+    }
+
+    /// Returns a copy of the current value.
+    ///
+    /// This never blocks.
+    pub fn read(&self) -> T {
+        let segment = self.segment();
+        loop {
+            let active = segment.active.load(Ordering::SeqCst) as usize & 1;
+            let readers = &segment.readers[active];
+            readers.fetch_add(1, Ordering::SeqCst);
+            // The writer may have flipped the slots and started writing this one before the reader
+            // was counted
+            if segment.active.load(Ordering::SeqCst) as usize & 1 == active {
+                // SAFETY: The writer doesn't write the slot while it has readers
+                let value = unsafe { ptr::read_volatile(segment.slots[active].get()) };
+                readers.fetch_sub(1, Ordering::Release);
+                return value;
+            }
+            readers.fetch_sub(1, Ordering::Release);
+        }
+    }
+
+    /// Writes a new value.
+    ///
+    /// Writers in all processes are serialized, and wait for the readers of the previous value.
+    ///
+    /// # Deadlocks
+    ///
+    /// If a process dies while writing or reading, this may block forever.
+    pub fn write(&self, value: T) {
+        let segment = self.segment();
+        while segment
+            .writer
+            .compare_exchange_weak(0, 1, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            crate::wait();
+        }
+
+        let standby = (segment.active.load(Ordering::SeqCst) as usize & 1) ^ 1;
+        while segment.readers[standby].load(Ordering::SeqCst) != 0 {
+            crate::wait();
+        }
+        // SAFETY: The standby slot has no readers, and new readers back off until it's active
+        unsafe { ptr::write_volatile(segment.slots[standby].get(), value) };
+        segment.active.store(standby as u32, Ordering::SeqCst);
+
+        segment.writer.store(0, Ordering::Release);
+    }
+}
+
+impl<T> Drop for ShmRcu<T> {
+    fn drop(&mut self) {
+        // SAFETY: The segment was mapped with this size, and isn't accessed afterwards
+        unsafe { libc::munmap(self.segment.as_ptr().cast(), mem::size_of::<Segment<T>>()) };
+    }
+}
+
+impl<T: Copy + fmt::Debug> fmt::Debug for ShmRcu<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("ShmRcu");
+        d.field("data", &self.read());
+        d.finish_non_exhaustive()
+    }
+}
+
+fn c_name(name: &str) -> io::Result<CString> {
+    CString::new(name).map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))
+}
+
+/// Opens the segment `name` with `flags` in addition to `O_RDWR`, and maps it.
+fn map<T>(name: &str, flags: libc::c_int) -> io::Result<NonNull<Segment<T>>> {
+    let name = c_name(name)?;
+    let len = mem::size_of::<Segment<T>>();
+
+    // SAFETY: The name is a valid C string
+    let fd = unsafe { libc::shm_open(name.as_ptr(), libc::O_RDWR | flags, 0o600) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let result = (|| {
+        if flags & libc::O_CREAT != 0 {
+            // SAFETY: `fd` is open
+            if unsafe { libc::ftruncate(fd, len as libc::off_t) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        } else {
+            // SAFETY: An all-zero `stat` is valid, and `fd` is open
+            let mut stat = unsafe { mem::zeroed::<libc::stat>() };
+            if unsafe { libc::fstat(fd, &mut stat) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if (stat.st_size as u64) < len as u64 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "the shared memory segment is too small",
+                ));
+            }
+        }
+
+        // SAFETY: Mapping a new region doesn't affect existing memory
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                fd,
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        // mmap never returns null on success
+        Ok(NonNull::new(ptr.cast()).expect("mmap returned null"))
+    })();
+
+    // SAFETY: `fd` is open, and the mapping stays valid after closing it
+    unsafe { libc::close(fd) };
+    if result.is_err() && flags & libc::O_CREAT != 0 {
+        // SAFETY: The name is a valid C string
+        unsafe { libc::shm_unlink(name.as_ptr()) };
+    }
+    result
+}
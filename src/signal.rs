@@ -0,0 +1,145 @@
+//! Reloading versions when the process receives a signal
+
+use core::fmt;
+use std::{
+    borrow::Borrow,
+    io,
+    os::raw::c_int,
+    sync::{Arc, Weak},
+    thread::{self, JoinHandle},
+};
+
+use signal_hook::iterator::{Handle, Signals};
+
+use crate::{
+    errors::{RefreshError, WriteError},
+    Rcu, Reclaim,
+};
+
+/// Reloads an `Rcu` when a signal is received, returned by [`Rcu::spawn_signal_reload`]
+///
+/// The signal handlers stay registered until this is dropped, and dropping it waits for a reload
+/// which is in progress.
+pub struct SignalReload {
+    handle: Handle,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for SignalReload {
+    fn drop(&mut self) {
+        self.handle.close();
+        if let Some(thread) = self.thread.take() {
+            // A panic in the reload closure was already reported by the thread
+            let _ = thread.join();
+        }
+    }
+}
+
+impl fmt::Debug for SignalReload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("SignalReload");
+        d.field("closed", &self.handle.is_closed());
+        d.finish_non_exhaustive()
+    }
+}
+
+impl<T, S> Rcu<T, S>
+where
+    T: Send + Sync + 'static,
+    S: Reclaim<T> + Send + Sync + 'static,
+{
+    /// Writes a new version returned by `reload` whenever the process receives one of `signals`,
+    /// typically [`SIGHUP`](signal_hook::consts::SIGHUP).
+    ///
+    /// The signal handlers only wake a dedicated thread, which calls `reload`, so `reload` doesn't
+    /// have to be async-signal-safe. Signals received while `reload` runs are coalesced into one
+    /// more reload. Errors are passed to `on_error` and the current version is kept. A
+    /// [frozen](Rcu::freeze) `Rcu` isn't reloaded.
+    ///
+    /// The signals are handled until the returned [`SignalReload`] is dropped or the `Rcu` is
+    /// dropped. Other handlers of the same signals, e.g. ones registered with `signal-hook`, keep
+    /// running.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a handler can't be registered, e.g. for a signal like `SIGKILL`, or the
+    /// thread can't be spawned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::{sync::Arc, thread::sleep, time::Duration};
+    /// use std::sync::atomic::{AtomicU16, Ordering};
+    ///
+    /// use axka_rcu::{signal_hook::consts::SIGHUP, Rcu};
+    ///
+    /// static CONFIGURED_PORT: AtomicU16 = AtomicU16::new(80);
+    ///
+    /// let port = Arc::new(Rcu::new(Arc::new(80)));
+    /// let _reload = port.spawn_signal_reload(
+    ///     [SIGHUP],
+    ///     || Ok::<_, std::io::Error>(CONFIGURED_PORT.load(Ordering::Relaxed)),
+    ///     |err| eprintln!("Failed to reload: {err}"),
+    /// ).unwrap();
+    ///
+    /// CONFIGURED_PORT.store(8080, Ordering::Relaxed);
+    /// signal_hook::low_level::raise(SIGHUP).unwrap();
+    /// # for _ in 0..100 {
+    /// #     if *port.read() == 8080 { break }
+    /// #     sleep(Duration::from_millis(10));
+    /// # }
+    /// assert_eq!(*port.read(), 8080);
+    /// ```
+    pub fn spawn_signal_reload<I, F, E, EF>(
+        self: &Arc<Self>,
+        signals: I,
+        mut reload: F,
+        on_error: EF,
+    ) -> io::Result<SignalReload>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<c_int>,
+        F: FnMut() -> Result<T, E> + Send + 'static,
+        EF: Fn(RefreshError<E>) + Send + 'static,
+    {
+        let mut signals = Signals::new(signals)?;
+        let handle = signals.handle();
+        let rcu: Weak<Self> = Arc::downgrade(self);
+
+        let thread = thread::Builder::new()
+            .name("axka-rcu-signal-reload".into())
+            .spawn(move || {
+                // Ends when the handle is closed
+                for _ in signals.forever() {
+                    if rcu.strong_count() == 0 {
+                        break;
+                    }
+                    let reloaded = reload();
+                    let Some(rcu) = rcu.upgrade() else {
+                        break;
+                    };
+                    match reloaded {
+                        // A frozen `Rcu` keeps its version
+                        Ok(value) => {
+                            if let Err(WriteError::Rejected) = rcu.try_write(Arc::new(value)) {
+                                on_error(RefreshError::Rejected);
+                            }
+                        }
+                        Err(err) => on_error(RefreshError::Fetch(err)),
+                    }
+                }
+            });
+        let thread = match thread {
+            Ok(thread) => thread,
+            Err(err) => {
+                handle.close();
+                return Err(err);
+            }
+        };
+
+        Ok(SignalReload {
+            handle,
+            thread: Some(thread),
+        })
+    }
+}
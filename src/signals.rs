@@ -0,0 +1,106 @@
+//! Mirroring the versions of an `Rcu` in a `Mutable` of `futures-signals`
+
+use core::fmt;
+use std::sync::{Arc, Weak};
+
+use futures_signals::signal::{Mutable, MutableSignalCloned, ReadOnlyMutable};
+
+use crate::{Rcu, Reclaim};
+
+impl<T, S> Rcu<T, S>
+where
+    T: Send + Sync + 'static,
+    S: Reclaim<T> + Send + Sync + 'static,
+{
+    /// Returns a [`MutableMirror`], which holds the current version in a [`Mutable`] until it's
+    /// dropped.
+    ///
+    /// This lets reactive code, e.g. with Dominator, use the combinators of `futures-signals` on
+    /// the versions of this `Rcu`. The mirror is one-way: new versions are written to the `Rcu`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::sync::Arc;
+    /// use axka_rcu::Rcu;
+    /// use futures::executor::block_on_stream;
+    /// use futures_signals::signal::SignalExt;
+    /// let count = Arc::new(Rcu::new(Arc::new(1)));
+    /// let mirror = count.to_mutable();
+    ///
+    /// let mut labels = block_on_stream(mirror.signal().map(|n| format!("{n} items")).to_stream());
+    /// assert_eq!(labels.next().unwrap(), "1 items");
+    ///
+    /// count.write(Arc::new(2));
+    /// assert_eq!(labels.next().unwrap(), "2 items");
+    /// ```
+    pub fn to_mutable(self: &Arc<Self>) -> MutableMirror<T, S> {
+        let mutable = Mutable::new(self.read());
+
+        let rcu = Arc::downgrade(self);
+        let mirror = mutable.clone();
+        let id = self.hooks.add(move |_| {
+            let Some(rcu) = rcu.upgrade() else {
+                return false;
+            };
+            sync(&rcu, &mirror);
+            true
+        });
+        // A version may have been written before the callback was registered
+        sync(self, &mutable);
+
+        MutableMirror {
+            mutable,
+            rcu: Arc::downgrade(self),
+            id,
+        }
+    }
+}
+
+/// Sets `mutable` to the current version of `rcu`.
+fn sync<T, S: Reclaim<T>>(rcu: &Rcu<T, S>, mutable: &Mutable<Arc<T>>) {
+    // Read while holding the lock of the `Mutable`, so concurrent writes can't leave an older
+    // version behind
+    let mut value = mutable.lock_mut();
+    let current = rcu.read();
+    if !Arc::ptr_eq(&value, &current) {
+        *value = current;
+    }
+}
+
+/// Holds the current version of an [`Rcu`] in a [`Mutable`], returned by [`Rcu::to_mutable`]
+///
+/// The `Mutable` stops being updated when this is dropped.
+pub struct MutableMirror<T, S> {
+    mutable: Mutable<Arc<T>>,
+    rcu: Weak<Rcu<T, S>>,
+    id: usize,
+}
+
+impl<T, S> MutableMirror<T, S> {
+    /// Returns a read-only handle to the `Mutable`.
+    pub fn read_only(&self) -> ReadOnlyMutable<Arc<T>> {
+        self.mutable.read_only()
+    }
+
+    /// Returns a signal of the current version.
+    pub fn signal(&self) -> MutableSignalCloned<Arc<T>> {
+        self.mutable.signal_cloned()
+    }
+}
+
+impl<T, S> Drop for MutableMirror<T, S> {
+    fn drop(&mut self) {
+        if let Some(rcu) = self.rcu.upgrade() {
+            rcu.hooks.remove(self.id);
+        }
+    }
+}
+
+impl<T: fmt::Debug, S> fmt::Debug for MutableMirror<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("MutableMirror");
+        d.field("mutable", &self.mutable);
+        d.finish_non_exhaustive()
+    }
+}
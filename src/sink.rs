@@ -0,0 +1,81 @@
+//! Writing the versions of a stream
+
+use core::{
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_sink::Sink;
+
+use crate::{errors::WriteError, Arc, Rcu, Reclaim};
+
+impl<T, S: Reclaim<T>> Rcu<T, S> {
+    /// Returns a [`Sink`] which [writes](Self::try_write) every version sent to it.
+    ///
+    /// The sink fails with a [`WriteError`] once a version can't be written, e.g. because the `Rcu`
+    /// is [frozen](Self::freeze).
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// use futures::{executor::block_on, stream, StreamExt};
+    /// let rcu = Rcu::new(Arc::new("foo"));
+    ///
+    /// let updates = stream::iter([Arc::new("bar"), Arc::new("baz")]).map(Ok);
+    /// block_on(updates.forward(rcu.sink())).unwrap();
+    /// assert_eq!(*rcu.read(), "baz");
+    /// ```
+    pub fn sink(&self) -> RcuSink<'_, T, S> {
+        RcuSink { rcu: self }
+    }
+}
+
+/// A [`Sink`] which writes versions to an [`Rcu`], created by [`Rcu::sink`]
+///
+/// Writing never blocks, so the sink is always ready unless the `Rcu` is frozen.
+pub struct RcuSink<'a, T, S> {
+    rcu: &'a Rcu<T, S>,
+}
+
+impl<T, S: Reclaim<T>> Sink<Arc<T>> for RcuSink<'_, T, S> {
+    type Error = WriteError;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), WriteError>> {
+        if self.rcu.is_frozen() {
+            return Poll::Ready(Err(WriteError::Frozen));
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Arc<T>) -> Result<(), WriteError> {
+        self.rcu.try_write(item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), WriteError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), WriteError>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T, S> Clone for RcuSink<'_, T, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, S> Copy for RcuSink<'_, T, S> {}
+
+impl<T: fmt::Debug, S: Reclaim<T>> fmt::Debug for RcuSink<'_, T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("RcuSink");
+        d.field("rcu", self.rcu);
+        d.finish()
+    }
+}
@@ -0,0 +1,97 @@
+//! Versions which are prepared before they're published
+
+use crate::{errors::WriteError, Arc, Rcu, Reclaim};
+
+impl<T, S: Reclaim<T>> Rcu<T, S> {
+    /// Stages `new_value` as the next version without publishing it, replacing any version staged
+    /// before.
+    ///
+    /// The staged version can be inspected with [`read_staged`](Self::read_staged), e.g. for
+    /// smoke tests, and then published with [`commit`](Self::commit) or dropped with
+    /// [`discard`](Self::discard). Readers keep seeing the current version in the meantime.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`WriteError::Frozen`] if the `Rcu` is [frozen](Self::freeze) and
+    /// [`WriteError::Rejected`] if the [validator](Self::with_validator) rejects the new version.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new("blue")).with_validator(|color| !color.is_empty());
+    ///
+    /// assert!(rcu.stage(Arc::new("")).is_err());
+    /// rcu.stage(Arc::new("green")).unwrap();
+    /// assert_eq!(*rcu.read(), "blue");
+    /// assert_eq!(rcu.read_staged().as_deref(), Some(&"green"));
+    ///
+    /// assert!(rcu.commit().unwrap());
+    /// assert_eq!(*rcu.read(), "green");
+    /// assert!(rcu.read_staged().is_none());
+    /// ```
+    pub fn stage(&self, new_value: Arc<T>) -> Result<(), WriteError> {
+        if self.is_frozen() {
+            return Err(WriteError::Frozen);
+        }
+        if !self.hooks.validate(&new_value) {
+            return Err(WriteError::Rejected);
+        }
+        let replaced = self.extras.get_or_alloc().staged.lock().replace(new_value);
+        // Dropped outside of the lock
+        drop(replaced);
+        Ok(())
+    }
+
+    /// Returns the [staged](Self::stage) version, if any.
+    pub fn read_staged(&self) -> Option<Arc<T>> {
+        self.extras.get()?.staged.lock().clone()
+    }
+
+    /// Publishes the [staged](Self::stage) version, returning `false` if there was none.
+    ///
+    /// # Errors
+    ///
+    /// See [`try_write`](Self::try_write). The version stays staged if it can't be published,
+    /// unless another one was staged in the meantime.
+    #[track_caller]
+    pub fn commit(&self) -> Result<bool, WriteError> {
+        let Some(extras) = self.extras.get() else {
+            return Ok(false);
+        };
+        let Some(staged) = extras.staged.lock().take() else {
+            return Ok(false);
+        };
+        match self.try_write(Arc::clone(&staged)) {
+            Ok(()) => Ok(true),
+            Err(err) => {
+                let mut slot = extras.staged.lock();
+                if slot.is_none() {
+                    *slot = Some(staged);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// Drops the [staged](Self::stage) version without publishing it, returning it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new("blue"));
+    ///
+    /// rcu.stage(Arc::new("green")).unwrap();
+    /// assert_eq!(rcu.discard().as_deref(), Some(&"green"));
+    /// assert!(!rcu.commit().unwrap());
+    /// assert_eq!(*rcu.read(), "blue");
+    /// ```
+    pub fn discard(&self) -> Option<Arc<T>> {
+        self.extras.get()?.staged.lock().take()
+    }
+}
@@ -0,0 +1,147 @@
+//! Counters of contention between writers
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(not(feature = "triomphe"))]
+use std::time::{Duration, Instant};
+
+use crate::{Arc, Rcu, Reclaim};
+
+/// How often the writers of an [`Rcu`] got in each other's way, returned by
+/// [`Rcu::contention_stats`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ContentionStats {
+    /// The number of times an updater or the predicate of [`Rcu::write_if`] was called again
+    /// because another version was written while it was running
+    pub retries: u64,
+    /// The number of compare-and-swaps and checked updates which failed because another version
+    /// was written
+    pub conflicts: u64,
+    /// The total time spent waiting in line by [`Rcu::update_fair`]
+    ///
+    /// This requires `std`, so it's not available with the `triomphe` feature.
+    #[cfg(not(feature = "triomphe"))]
+    pub queue_wait: Duration,
+}
+
+/// The read handles of an [`Rcu`] which are alive, returned by [`Rcu::outstanding_readers`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct OutstandingReaders {
+    /// The number of [`ReadGuard`](crate::ReadGuard)s, [`ReadSession`](crate::ReadSession)s and
+    /// other handles which pin whichever versions were current while they were created
+    pub guards: usize,
+    /// The number of `Arc`s returned by [`Rcu::read`] which hold the current version
+    pub current: usize,
+}
+
+pub(crate) struct Stats {
+    retries: AtomicUsize,
+    conflicts: AtomicUsize,
+    #[cfg(not(feature = "triomphe"))]
+    queue_wait_nanos: AtomicUsize,
+}
+
+impl Stats {
+    pub(crate) const fn new() -> Self {
+        Self {
+            retries: AtomicUsize::new(0),
+            conflicts: AtomicUsize::new(0),
+            #[cfg(not(feature = "triomphe"))]
+            queue_wait_nanos: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) fn retry(&self) {
+        self.retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn conflict(&self) {
+        self.conflicts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Adds the time since `start` to the time spent waiting in line.
+    #[cfg(not(feature = "triomphe"))]
+    pub(crate) fn queue_wait(&self, start: Instant) {
+        let nanos = start.elapsed().as_nanos().try_into().unwrap_or(usize::MAX);
+        self.queue_wait_nanos.fetch_add(nanos, Ordering::Relaxed);
+    }
+}
+
+impl<T, S: Reclaim<T>> Rcu<T, S> {
+    /// Returns how often the writers of this `Rcu` got in each other's way since it was created.
+    ///
+    /// This helps with tuning writer concurrency, e.g. choosing between
+    /// [`update_retry`](Self::update_retry) and [`update_fair`](Self::update_fair).
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new(0));
+    ///
+    /// let old = rcu.read();
+    /// rcu.write(Arc::new(1));
+    /// assert!(rcu.compare_exchange(&old, Arc::new(2)).is_err());
+    ///
+    /// let mut calls = 0;
+    /// rcu.update_retry(|n| {
+    ///     calls += 1;
+    ///     if calls == 1 {
+    ///         // Simulate another writer
+    ///         rcu.write(Arc::new(10));
+    ///     }
+    ///     *n += 1;
+    /// });
+    /// assert_eq!(*rcu.read(), 11);
+    ///
+    /// let stats = rcu.contention_stats();
+    /// assert_eq!(stats.retries, 1);
+    /// assert_eq!(stats.conflicts, 2);
+    /// ```
+    pub fn contention_stats(&self) -> ContentionStats {
+        ContentionStats {
+            retries: self.stats.retries.load(Ordering::Relaxed) as u64,
+            conflicts: self.stats.conflicts.load(Ordering::Relaxed) as u64,
+            #[cfg(not(feature = "triomphe"))]
+            queue_wait: Duration::from_nanos(
+                self.stats.queue_wait_nanos.load(Ordering::Relaxed) as u64
+            ),
+        }
+    }
+
+    /// Returns how many read handles are alive.
+    ///
+    /// A growing number of guards means that readers hold on to them for too long, which keeps
+    /// replaced versions from being dropped. The `Arc`s which hold replaced versions aren't known
+    /// to the `Rcu`, but the `lineage` feature lists them per version, see `Rcu::lineage`.
+    ///
+    /// The numbers may be off while reads happen concurrently.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::Rcu;
+    /// let rcu = Rcu::new(Arc::new(0));
+    ///
+    /// let guard = rcu.read_guard();
+    /// let arcs = [rcu.read(), rcu.read()];
+    ///
+    /// let readers = rcu.outstanding_readers();
+    /// assert_eq!(readers.guards, 1);
+    /// assert_eq!(readers.current, 2);
+    /// ```
+    pub fn outstanding_readers(&self) -> OutstandingReaders {
+        let guards = self.readers.count().unwrap_or(0);
+        let current = self.read();
+        OutstandingReaders {
+            guards,
+            // Minus the references of the `Rcu` and `current`
+            current: Arc::strong_count(&current).saturating_sub(2),
+        }
+    }
+}
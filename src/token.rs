@@ -0,0 +1,153 @@
+//! Tokens for telling which of two versions is newer
+
+use core::{cmp::Ordering, fmt, ops::Deref, sync::atomic};
+
+use crate::{Rcu, ReadGuard, Reclaim, WRITER};
+
+/// Identifies a version of an `Rcu` by its [generation](Rcu::generation), created by
+/// [`Rcu::read_versioned_guard`]
+///
+/// Tokens of the same `Rcu` are ordered from the oldest to the newest version, so caches can tell
+/// which of two snapshots is newer without comparing them. Comparing tokens of different `Rcu`s
+/// is meaningless.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VersionToken {
+    generation: u64,
+}
+
+impl VersionToken {
+    /// Returns the generation of the version.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+/// A [`ReadGuard`] along with the [`VersionToken`] of its version, returned by
+/// [`Rcu::read_versioned_guard`]
+pub struct VersionedGuard<'a, T> {
+    guard: ReadGuard<'a, T>,
+    token: VersionToken,
+}
+
+impl<'a, T> VersionedGuard<'a, T> {
+    /// Returns the token of the version.
+    ///
+    /// This is an associated function so it doesn't shadow methods of `T`.
+    pub fn token(this: &Self) -> VersionToken {
+        this.token
+    }
+
+    /// Returns the guard without the token.
+    pub fn into_guard(this: Self) -> ReadGuard<'a, T> {
+        this.guard
+    }
+}
+
+impl<T> Deref for VersionedGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for VersionedGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("VersionedGuard");
+        d.field("data", &*self.guard);
+        d.field("generation", &self.token.generation);
+        d.finish()
+    }
+}
+
+impl<T, S: Reclaim<T>> Rcu<T, S> {
+    /// Returns a [`ReadGuard`] to the current version along with its [`VersionToken`].
+    ///
+    /// Unlike [`read_versioned`](Self::read_versioned), the generation always belongs to the
+    /// version, so this waits while a write is being published.
+    ///
+    /// # Blocking
+    ///
+    /// Unlike the other reads, this isn't wait-free. It retries until no write is published
+    /// between reading the version and its generation, so it may wait for as long as writes keep
+    /// coming, or while a writer is preempted in the middle of publishing. Use
+    /// [`read_versioned`](Self::read_versioned) where a generation which may be newer than the
+    /// version is enough.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use axka_rcu::{Rcu, VersionedGuard};
+    /// let rcu = Rcu::new(Arc::new("foo"));
+    ///
+    /// let old = rcu.read_versioned_guard();
+    /// rcu.write(Arc::new("bar"));
+    /// let new = rcu.read_versioned_guard();
+    ///
+    /// assert_eq!((*old, *new), ("foo", "bar"));
+    /// assert!(VersionedGuard::token(&old) < VersionedGuard::token(&new));
+    /// ```
+    pub fn read_versioned_guard(&self) -> VersionedGuard<'_, T> {
+        let (guard, generation) = self.read_exact();
+        VersionedGuard {
+            guard,
+            token: VersionToken { generation },
+        }
+    }
+
+    /// Orders two tokens of this `Rcu` from the oldest to the newest version.
+    ///
+    /// This is the same as comparing the tokens, but checks in debug builds that neither token is
+    /// newer than the current version, which would mean it's from another `Rcu`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    #[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+    #[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+    /// use std::cmp::Ordering;
+    ///
+    /// use axka_rcu::{Rcu, VersionedGuard};
+    /// let rcu = Rcu::new(Arc::new(1));
+    ///
+    /// let cached = VersionedGuard::token(&rcu.read_versioned_guard());
+    /// rcu.update(|n| *n += 1);
+    /// let fetched = VersionedGuard::token(&rcu.read_versioned_guard());
+    ///
+    /// assert_eq!(rcu.compare_versions(&cached, &fetched), Ordering::Less);
+    /// assert_eq!(rcu.compare_versions(&fetched, &fetched), Ordering::Equal);
+    /// ```
+    pub fn compare_versions(&self, a: &VersionToken, b: &VersionToken) -> Ordering {
+        debug_assert!(
+            a.max(b).generation <= self.generation(),
+            "the token is from another Rcu"
+        );
+        a.cmp(b)
+    }
+
+    /// Returns a guard to the current version along with its generation.
+    ///
+    /// This blocks while writes are being published, see
+    /// [`read_versioned_guard`](Self::read_versioned_guard).
+    pub(crate) fn read_exact(&self) -> (ReadGuard<'_, T>, u64) {
+        loop {
+            // A write which began earlier may have bumped the generation, but not published yet
+            if self.state.load(atomic::Ordering::SeqCst) < WRITER {
+                let generation = self.generation();
+                let guard = self.read_guard();
+                // The generation is bumped before publishing, so it only matches the version if no
+                // write was published in the meantime. The guard keeps the address of the version
+                // from being reused.
+                if self.state.load(atomic::Ordering::SeqCst) < WRITER
+                    && self.generation() == generation
+                    && core::ptr::eq(self.ptr.load(atomic::Ordering::SeqCst), &*guard)
+                {
+                    return (guard, generation);
+                }
+            }
+            crate::wait();
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! Hot-swapping `tower` services
+
+use core::{
+    fmt,
+    task::{Context, Poll},
+};
+
+use tower_service::Service;
+
+use crate::{Arc, Rcu};
+
+/// A [`Service`] which delegates each request to the current version of an inner service, e.g.
+/// a router which can be replaced without restarting the server
+///
+/// Each request is handled by a clone of the version which was current when
+/// [`poll_ready`](Service::poll_ready) was called. Clones of an `RcuService` share the inner
+/// service, so [replacing](Self::replace) it through any clone affects all of them, while requests
+/// which were already made keep using the old version.
+///
+/// # Example
+///
+/// ```
+#[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+#[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+/// use std::{convert::Infallible, future::{ready, Ready}, task::{Context, Poll}};
+///
+/// use axka_rcu::{tower_service::Service, RcuService};
+///
+/// #[derive(Clone)]
+/// struct Greet(&'static str);
+///
+/// impl Service<&'static str> for Greet {
+///     type Response = String;
+///     type Error = Infallible;
+///     type Future = Ready<Result<String, Infallible>>;
+///
+///     fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+///         Poll::Ready(Ok(()))
+///     }
+///
+///     fn call(&mut self, name: &'static str) -> Self::Future {
+///         ready(Ok(format!("{}, {name}!", self.0)))
+///     }
+/// }
+///
+/// async fn oneshot(service: &mut RcuService<Greet>, name: &'static str) -> String {
+///     std::future::poll_fn(|cx| service.poll_ready(cx)).await.unwrap();
+///     service.call(name).await.unwrap()
+/// }
+///
+/// let mut service = RcuService::new(Greet("Hello"));
+/// let handle = service.clone();
+/// # futures::executor::block_on(async {
+/// assert_eq!(oneshot(&mut service, "world").await, "Hello, world!");
+///
+/// handle.replace(Greet("Goodbye"));
+/// assert_eq!(oneshot(&mut service, "world").await, "Goodbye, world!");
+/// # });
+/// ```
+pub struct RcuService<Svc> {
+    rcu: Arc<Rcu<Svc>>,
+    /// The clone which was polled ready, and handles the next request
+    ready: Option<Svc>,
+}
+
+impl<Svc> RcuService<Svc> {
+    /// Creates a new `RcuService` delegating to `service`.
+    pub fn new(service: Svc) -> Self {
+        Self::from_rcu(Arc::new(Rcu::new(Arc::new(service))))
+    }
+
+    /// Creates a new `RcuService` delegating to the current version of `rcu`.
+    pub fn from_rcu(rcu: Arc<Rcu<Svc>>) -> Self {
+        Self { rcu, ready: None }
+    }
+
+    /// Replaces the inner service for requests which weren't polled ready yet.
+    ///
+    /// # Panics
+    ///
+    /// See [`Rcu::write`].
+    #[track_caller]
+    pub fn replace(&self, service: Svc) {
+        self.rcu.write(Arc::new(service));
+    }
+
+    /// Returns the `Rcu` holding the inner service.
+    pub fn rcu(&self) -> &Arc<Rcu<Svc>> {
+        &self.rcu
+    }
+}
+
+impl<Svc, Request> Service<Request> for RcuService<Svc>
+where
+    Svc: Service<Request> + Clone,
+{
+    type Response = Svc::Response;
+    type Error = Svc::Error;
+    type Future = Svc::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let rcu = &self.rcu;
+        self.ready
+            .get_or_insert_with(|| rcu.read_with(Svc::clone))
+            .poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        self.ready
+            .take()
+            .expect("RcuService::call was called before poll_ready")
+            .call(request)
+    }
+}
+
+/// Clones share the inner service, but each one polls its own clone of it.
+impl<Svc> Clone for RcuService<Svc> {
+    fn clone(&self) -> Self {
+        Self::from_rcu(Arc::clone(&self.rcu))
+    }
+}
+
+impl<Svc> fmt::Debug for RcuService<Svc> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("RcuService");
+        d.field("generation", &self.rcu.generation());
+        d.field("ready", &self.ready.is_some());
+        d.finish_non_exhaustive()
+    }
+}
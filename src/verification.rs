@@ -0,0 +1,120 @@
+//! Kani proof harnesses for the pointer and reference count invariants
+//!
+//! Run them with `cargo kani`. Kani doesn't model threads, so interleavings of readers and writers
+//! are modelled as nondeterministic sequences of operations on one thread. On every path, Kani
+//! checks that no pointer is freed twice or used after being freed, and the harnesses check that
+//! every version is dropped exactly once, i.e. that every `Arc::into_raw` is matched by exactly one
+//! `Arc::from_raw` which releases it.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{Arc, Rcu};
+
+static CREATED: AtomicUsize = AtomicUsize::new(0);
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+/// A version which counts how many times it's created and dropped
+struct Tracked;
+
+impl Tracked {
+    fn new() -> Arc<Self> {
+        CREATED.fetch_add(1, Ordering::Relaxed);
+        Arc::new(Self)
+    }
+}
+
+impl Clone for Tracked {
+    fn clone(&self) -> Self {
+        CREATED.fetch_add(1, Ordering::Relaxed);
+        Self
+    }
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        DROPPED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn live() -> usize {
+    CREATED.load(Ordering::Relaxed) - DROPPED.load(Ordering::Relaxed)
+}
+
+/// Runs a nondeterministic operation on `rcu`, keeping read handles in `arc` and `guard`.
+fn step<'a>(
+    rcu: &'a Rcu<Tracked>,
+    arc: &mut Option<Arc<Tracked>>,
+    guard: &mut Option<crate::ReadGuard<'a, Tracked>>,
+) {
+    match kani::any::<u8>() % 7 {
+        0 => *arc = Some(rcu.read()),
+        1 => *arc = None,
+        2 => *guard = Some(rcu.read_guard()),
+        3 => *guard = None,
+        4 => rcu.write(Tracked::new()),
+        5 => rcu.update(|_| ()),
+        _ => {
+            let current = arc.clone().unwrap_or_else(|| rcu.read());
+            drop(rcu.compare_exchange(&current, Tracked::new()));
+        }
+    }
+}
+
+/// Every version is dropped exactly once after the `Rcu` and its readers are dropped, whatever
+/// order reads, writes and releases happen in.
+#[kani::proof]
+#[kani::unwind(5)]
+fn versions_are_dropped_once() {
+    let rcu = Rcu::new(Tracked::new());
+    let mut arc = None;
+    let mut guard = None;
+    for _ in 0..4 {
+        step(&rcu, &mut arc, &mut guard);
+    }
+    drop(guard);
+    drop(arc);
+    drop(rcu);
+    assert_eq!(live(), 0);
+}
+
+/// Once no guard is left, [`Rcu::synchronize`] releases every replaced version, so only the
+/// current version and the one held by a reader are alive.
+#[kani::proof]
+#[kani::unwind(5)]
+fn synchronize_releases_retired_versions() {
+    let rcu = Rcu::new(Tracked::new());
+    let mut arc = None;
+    let mut guard = None;
+    for _ in 0..4 {
+        step(&rcu, &mut arc, &mut guard);
+    }
+    drop(guard);
+    rcu.synchronize();
+    assert!(live() <= 1 + usize::from(arc.is_some()));
+    drop(arc);
+    drop(rcu);
+}
+
+/// Consuming the `Rcu` transfers its reference instead of releasing or leaking it.
+#[kani::proof]
+#[kani::unwind(3)]
+fn into_arc_transfers_the_reference() {
+    let rcu = Rcu::new(Tracked::new());
+    if kani::any() {
+        rcu.write(Tracked::new());
+    }
+    let reader = kani::any::<bool>().then(|| rcu.read());
+
+    let current = if kani::any() {
+        rcu.into_arc()
+    } else {
+        match rcu.try_unwrap() {
+            Ok(value) => Arc::new(value),
+            Err(rcu) => rcu.into_arc(),
+        }
+    };
+    assert_eq!(live(), 1);
+    drop(reader);
+    drop(current);
+    assert_eq!(live(), 0);
+}
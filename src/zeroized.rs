@@ -0,0 +1,124 @@
+//! Zeroizing replaced versions before they're deallocated
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use zeroize::Zeroize;
+
+use crate::{lock::Lock, Arc, Reclaim};
+
+/// Zeroizes replaced versions before their memory is freed, e.g. for rotated credentials
+///
+/// The strategy keeps a reference to each replaced version, so `Arc`s returned by
+/// [`Rcu::read`](crate::Rcu::read) never free it. Once the strategy holds the only reference, the
+/// version is zeroized and dropped. This is checked whenever a version is replaced and when
+/// [`flush`](Self::flush) is called.
+///
+/// Versions which were replaced while a [`ReadGuard`](crate::ReadGuard) was held are handed over
+/// by a later write or [`Rcu::synchronize`](crate::Rcu::synchronize) once the guard is gone, or
+/// when the `Rcu` is dropped at the latest. The current version is
+/// dropped without being zeroized when the `Rcu` is dropped, as are replaced versions whose `Arc`s
+/// outlive the `Rcu`. With the `lineage` feature, which keeps weak references to versions, a
+/// version is moved out of its allocation before it's zeroized, so the bytes of `T` itself may be
+/// left behind, but not its heap allocations. Use [`zeroize::Zeroizing`] as the version type too,
+/// if that matters, or for versions in an [`Arena`](crate::Arena), whose slots are reused.
+///
+/// # Example
+///
+/// ```
+#[cfg_attr(feature = "triomphe", doc = "# use triomphe::Arc;")]
+#[cfg_attr(not(feature = "triomphe"), doc = "# use std::sync::Arc;")]
+/// use axka_rcu::{Rcu, Zeroized};
+/// let token = Rcu::with_reclaim(Arc::new(String::from("hunter2")), Zeroized::new());
+///
+/// let reader = token.read();
+/// token.write(Arc::new(String::from("correct horse")));
+/// assert_eq!(token.reclaimer().pending(), 1);
+///
+/// drop(reader);
+/// token.reclaimer().flush();
+/// assert_eq!(token.reclaimer().pending(), 0);
+/// ```
+pub struct Zeroized<T: Zeroize> {
+    /// Replaced versions which may still be read
+    pending: Lock<Vec<Arc<T>>>,
+}
+
+impl<T: Zeroize> Zeroized<T> {
+    /// Creates a `Zeroized` without pending versions.
+    pub const fn new() -> Self {
+        Self {
+            pending: Lock::new(Vec::new()),
+        }
+    }
+
+    /// Returns the number of replaced versions which wait for their readers before they're
+    /// zeroized.
+    pub fn pending(&self) -> usize {
+        self.pending.lock().len()
+    }
+
+    /// Zeroizes and drops the replaced versions which aren't read anymore.
+    pub fn flush(&self) {
+        let (zeroized, moved) = {
+            let mut pending = self.pending.lock();
+            let mut zeroized = Vec::new();
+            let mut moved = Vec::new();
+            let mut i = 0;
+            while i < pending.len() {
+                if let Some(value) = Arc::get_mut(&mut pending[i]) {
+                    value.zeroize();
+                    zeroized.push(pending.swap_remove(i));
+                } else if Arc::strong_count(&pending[i]) == 1 {
+                    // Only weak references are left, e.g. of the lineage, so the value is moved
+                    // out of the allocation instead
+                    match Arc::try_unwrap(pending.swap_remove(i)) {
+                        Ok(mut value) => {
+                            value.zeroize();
+                            moved.push(value);
+                        }
+                        // A weak reference was upgraded in the meantime
+                        Err(version) => pending.push(version),
+                    }
+                } else {
+                    i += 1;
+                }
+            }
+            (zeroized, moved)
+        };
+        // Dropped outside of the lock
+        drop((zeroized, moved));
+    }
+}
+
+impl<T: Zeroize> Reclaim<T> for Zeroized<T> {
+    fn reclaim(&self, version: Arc<T>) {
+        self.pending.lock().push(version);
+        self.flush();
+    }
+
+    fn flush(&self) {
+        Zeroized::flush(self);
+    }
+}
+
+impl<T: Zeroize> Default for Zeroized<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Zeroize> Drop for Zeroized<T> {
+    fn drop(&mut self) {
+        // Versions which are still read are freed by their last reader
+        self.flush();
+    }
+}
+
+impl<T: Zeroize> fmt::Debug for Zeroized<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("Zeroized");
+        d.field("pending", &self.pending());
+        d.finish_non_exhaustive()
+    }
+}